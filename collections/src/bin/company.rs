@@ -0,0 +1,18 @@
+// The text interface to `exercises::three::Company`: add people to departments and list them.
+//
+//     $ cargo run --bin company
+//     > Add Sally to Engineering
+//     Added Sally to Engineering.
+//     > List all
+
+extern crate collections;
+
+use std::io;
+
+use collections::exercises::three::{repl, Company, HELP};
+
+fn main() {
+    println!("{}", HELP);
+    let stdin = io::stdin();
+    repl(&mut Company::new(), stdin.lock(), io::stdout()).expect("Failed to read line");
+}
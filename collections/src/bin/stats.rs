@@ -0,0 +1,56 @@
+// Summary statistics and a histogram for numbers piped in on stdin (or read from files), built on
+// the statistics in `exercises::one`.
+//
+//     seq 1 100 | cargo run --bin stats
+//     cargo run --bin stats -- --column price --bins 20 sales.csv
+//     cargo run --bin stats -- --json < numbers.txt
+
+extern crate collections;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::process;
+
+use collections::exercises::one::report::{print_json, print_text, read_numbers, Config, ReadError, Report};
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    eprintln!("Usage: stats [--column N|NAME] [--delimiter C] [--bins N] [--json] [FILE...]");
+    process::exit(1);
+}
+
+fn main() {
+    let config = Config::new(env::args().skip(1)).unwrap_or_else(|e| usage_error(&e));
+    let mut numbers = Vec::new();
+    let mut skipped = 0;
+    let result = if config.files.is_empty() || config.files == ["-"] {
+        let stdin = io::stdin();
+        read_numbers(stdin.lock(), &config, &mut numbers).map(|s| skipped += s)
+    } else {
+        config.files.iter().try_for_each(|path| {
+            let file = File::open(path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))?;
+            skipped += read_numbers(BufReader::new(file), &config, &mut numbers)?;
+            Ok(())
+        })
+    };
+    match result {
+        Ok(()) => {}
+        Err(ReadError::NoColumn(name)) => usage_error(&format!("No column named {} in the header", name)),
+        Err(ReadError::Io(e)) => {
+            eprintln!("Couldn't read input: {}", e);
+            process::exit(1);
+        }
+    }
+    if skipped > 0 {
+        eprintln!("Skipped {} lines that weren't finite numbers", skipped);
+    }
+
+    let report = Report::new(&numbers, config.bins).unwrap_or_else(|e| {
+        eprintln!("Nothing to report: {}", e);
+        process::exit(1);
+    });
+    let stdout = io::stdout();
+    let printed = if config.json { print_json(&report, stdout.lock()) } else { print_text(&report, stdout.lock()) };
+    printed.expect("Failed to write output");
+}
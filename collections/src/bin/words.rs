@@ -0,0 +1,115 @@
+// The most common words in text piped in on stdin (or read from files), or how two texts
+// compare, built on `words`.
+//
+//     cargo run --bin words -- --top 20 --stop-words < book.txt
+//     cargo run --bin words -- --ngram 2 chapter1.txt chapter2.txt
+//     cargo run --bin words -- --compare first.txt second.txt
+
+extern crate collections;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+use std::process;
+
+use collections::words::{compare, stop_words, Counter, Frequencies, Options};
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    eprintln!("Usage: words [--top N] [--ngram N] [--stop-words] [--keep-case] [--compare] [FILE...]");
+    process::exit(1);
+}
+
+struct Config {
+    top: usize,
+    options: Options,
+    // Compare the two files given rather than counting them together.
+    compare: bool,
+    files: Vec<String>,
+}
+
+impl Config {
+    fn new<I: Iterator<Item = String>>(mut args: I) -> Config {
+        let mut config = Config { top: 10, options: Options::default(), compare: false, files: Vec::new() };
+        let number = |args: &mut I, flag: &str| match args.next().and_then(|n| n.parse().ok()) {
+            Some(n) if n > 0 => n,
+            _ => usage_error(&format!("{} needs a number above 0", flag)),
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--top" | "-n" => config.top = number(&mut args, "--top"),
+                "--ngram" | "-g" => config.options.ngram = number(&mut args, "--ngram"),
+                "--stop-words" | "-s" => config.options.stop_words = stop_words(),
+                "--keep-case" => config.options.fold_case = false,
+                "--compare" => config.compare = true,
+                _ if arg.starts_with('-') && arg != "-" => usage_error(&format!("Unknown argument {}", arg)),
+                _ => config.files.push(arg),
+            }
+        }
+        if config.compare && config.files.len() != 2 {
+            usage_error("--compare needs exactly two files");
+        }
+        config
+    }
+}
+
+// Counts a file, or stdin for "-".
+fn count(path: &str, counter: &mut Counter) -> io::Result<()> {
+    if path == "-" {
+        let stdin = io::stdin();
+        return counter.read(stdin.lock());
+    }
+    let file = File::open(path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))?;
+    counter.read(BufReader::new(file))
+}
+
+fn print_top<W: Write>(frequencies: &Frequencies, top: usize, mut out: W) -> io::Result<()> {
+    let top = frequencies.top(top);
+    let width = top.iter().map(|&(_, count)| count.to_string().len()).max().unwrap_or(0);
+    for (word, count) in top {
+        writeln!(out, "{:>w$}  {}", count, word, w = width)?;
+    }
+    writeln!(out, "{} in all, {} different", frequencies.total, frequencies.counts.len())
+}
+
+fn print_comparison<W: Write>(names: &[String], a: &Frequencies, b: &Frequencies, top: usize, mut out: W) -> io::Result<()> {
+    let comparison = compare(a, b, top);
+    writeln!(out, "similarity {:.3}", comparison.similarity)?;
+    for (name, distinctive) in names.iter().zip(&[comparison.distinctive_a, comparison.distinctive_b]) {
+        writeln!(out)?;
+        writeln!(out, "more in {}:", name)?;
+        for &(ref word, ratio) in distinctive {
+            writeln!(out, "  {:<20} x{:.1}", word, 2f64.powf(ratio))?;
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let config = Config::new(env::args().skip(1));
+    let files = if config.files.is_empty() { vec![String::from("-")] } else { config.files.clone() };
+    let counted = if config.compare {
+        files
+            .iter()
+            .map(|path| {
+                let mut counter = Counter::new(config.options.clone());
+                count(path, &mut counter).map(|_| counter.finish())
+            })
+            .collect::<io::Result<Vec<_>>>()
+    } else {
+        let mut counter = Counter::new(config.options.clone());
+        files.iter().try_for_each(|path| count(path, &mut counter)).map(|_| vec![counter.finish()])
+    };
+    let counted = counted.unwrap_or_else(|e| {
+        eprintln!("Couldn't read input: {}", e);
+        process::exit(1);
+    });
+
+    let stdout = io::stdout();
+    let printed = if config.compare {
+        print_comparison(&files, &counted[0], &counted[1], config.top, stdout.lock())
+    } else {
+        print_top(&counted[0], config.top, stdout.lock())
+    };
+    printed.expect("Failed to write output");
+}
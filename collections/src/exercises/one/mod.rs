@@ -1,55 +1,388 @@
-use std::collections::HashMap;
+// Question: Given a list of integers, use a vector and return the mean (the average value),
+// median (when sorted, the value in the middle position), and mode (the value that occurs most
+// often; a hash map will be helpful here) of the list.
+//
+// The first attempt at this panicked on an empty list, summed into an i32 that could overflow,
+// took the median of an even-length list with integer division and picked one mode at random
+// (whichever the HashMap happened to visit first). This is the grown-up version:
+// - `Running` keeps a mean and variance up to date one value at a time (Welford's method), so
+//   it never needs the whole list or a sum that can overflow.
+// - `quantile` is exact and needs the values; `P2Quantile` estimates one on the fly in constant
+//   memory.
+// - `modes` returns every value that ties for most common.
+// Everything works for any of the built-in number types, and asking about no values at all is an
+// error rather than a panic.
 
-// This is the public function from this module.
-pub fn ls(ints: Vec<i32>) -> (f32, i32, i32) {
-    if ints.len() == 0 {
-        panic!("Empty vector");
+use std::cmp::Ordering;
+use std::fmt;
+
+pub mod report;
+
+// The number types we can take statistics of. Means and the like come out as f64 whatever goes
+// in; i64 and u64 values past 2^53 lose some precision on the way.
+pub trait Sample: Copy + PartialOrd + fmt::Debug {
+    fn to_f64(self) -> f64;
+}
+
+// A macro saves writing out the same impl for every type.
+macro_rules! impl_sample {
+    ($($t:ty),*) => {
+        $(
+            impl Sample for $t {
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_sample!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatsError {
+    // There were no values to begin with.
+    Empty,
+    // Fewer values than the statistic needs, like a sample variance of one value.
+    TooFew { needed: usize, got: usize },
+    // A NaN, which has no place in any ordering.
+    NotANumber,
+    // Quantiles go from 0 to 1.
+    InvalidQuantile(f64),
+}
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StatsError::Empty => write!(f, "no values"),
+            StatsError::TooFew { needed, got } => write!(f, "needs at least {} values, got {}", needed, got),
+            StatsError::NotANumber => write!(f, "NaN is not a number"),
+            StatsError::InvalidQuantile(q) => write!(f, "quantile must be between 0 and 1, got {}", q),
+        }
     }
-    let mean = mean(&ints);
-    let median = median(&ints);
-    let mode = mode(&ints);
-    (mean, median, mode)
 }
 
-fn mean(ints: &Vec<i32>) -> f32 {
-    let mut sum = 0; // chances of overflow?
-    for v in ints {
-        // Following works:
-        sum = sum + v;
-        // However, following does not work. WHY is that?
-        // sum += v;
+// Turns NaNs away. Integers come out of to_f64 as ordinary numbers, so only floats can fail.
+fn check<T: Sample>(value: T) -> Result<T, StatsError> {
+    if value.to_f64().is_nan() {
+        Err(StatsError::NotANumber)
+    } else {
+        Ok(value)
     }
-    println!("Sum is {}", sum);
-    sum as f32 / ints.len() as f32
 }
 
-fn median(ints: &Vec<i32>) -> i32 {
-    let mut ints_copy = ints.clone();
-    {
-        let int_slice = &mut ints_copy[..];
-        int_slice.sort();
+// Sorts a copy of the values. Once NaNs have been turned away partial_cmp always has an answer.
+fn sorted<T: Sample>(values: &[T]) -> Result<Vec<T>, StatsError> {
+    if values.is_empty() {
+        return Err(StatsError::Empty);
     }
-    println!("Sorted vector: {:?}", &ints_copy);
-    if ints.len() % 2 == 1 {
-        ints_copy[ints.len() / 2]
-    } else {
-        (ints_copy[ints.len() / 2] + ints_copy[(ints.len() / 2) - 1]) / 2
+    let mut sorted = values.iter().map(|&v| check(v)).collect::<Result<Vec<T>, _>>()?;
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    Ok(sorted)
+}
+
+// Statistics that can be kept up to date one value at a time, without holding on to the values.
+#[derive(Debug, Clone)]
+pub struct Running<T: Sample> {
+    count: usize,
+    mean: f64,
+    // Sum of squared differences from the mean, which Welford's method updates as it goes.
+    m2: f64,
+    min: Option<T>,
+    max: Option<T>,
+}
+
+impl<T: Sample> Default for Running<T> {
+    fn default() -> Running<T> {
+        Running::new()
+    }
+}
+
+impl<T: Sample> Running<T> {
+    pub fn new() -> Running<T> {
+        Running { count: 0, mean: 0.0, m2: 0.0, min: None, max: None }
+    }
+
+    pub fn push(&mut self, value: T) -> Result<(), StatsError> {
+        let value = check(value)?;
+        self.count += 1;
+        let x = value.to_f64();
+        // Nudge the mean by this value's share of the difference, rather than keeping a sum.
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+        if self.min.is_none_or(|min| value < min) {
+            self.min = Some(value);
+        }
+        if self.max.is_none_or(|max| value > max) {
+            self.max = Some(value);
+        }
+        Ok(())
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mean(&self) -> Result<f64, StatsError> {
+        if self.count == 0 {
+            return Err(StatsError::Empty);
+        }
+        Ok(self.mean)
+    }
+
+    // The variance of exactly these values.
+    pub fn variance(&self) -> Result<f64, StatsError> {
+        if self.count == 0 {
+            return Err(StatsError::Empty);
+        }
+        Ok(self.m2 / self.count as f64)
+    }
+
+    // The variance estimated for whatever these values are a sample of, which divides by one less
+    // than the count.
+    pub fn sample_variance(&self) -> Result<f64, StatsError> {
+        if self.count < 2 {
+            return Err(StatsError::TooFew { needed: 2, got: self.count });
+        }
+        Ok(self.m2 / (self.count - 1) as f64)
+    }
+
+    pub fn std_dev(&self) -> Result<f64, StatsError> {
+        self.variance().map(f64::sqrt)
+    }
+
+    pub fn min(&self) -> Result<T, StatsError> {
+        self.min.ok_or(StatsError::Empty)
+    }
+
+    pub fn max(&self) -> Result<T, StatsError> {
+        self.max.ok_or(StatsError::Empty)
+    }
+
+    // Taken in f64, since the difference of two i8s might not fit in an i8.
+    pub fn range(&self) -> Result<f64, StatsError> {
+        Ok(self.max()?.to_f64() - self.min()?.to_f64())
+    }
+}
+
+// The value below which the share `q` of the values lie, interpolating between the two nearest
+// values when it falls in between (the same method spreadsheets use). Needs all the values.
+pub fn quantile<T: Sample>(values: &[T], q: f64) -> Result<f64, StatsError> {
+    if !(0.0..=1.0).contains(&q) {
+        return Err(StatsError::InvalidQuantile(q));
+    }
+    Ok(quantile_of_sorted(&sorted(values)?, q))
+}
+
+fn quantile_of_sorted<T: Sample>(sorted: &[T], q: f64) -> f64 {
+    let position = (sorted.len() - 1) as f64 * q;
+    let below = position.floor() as usize;
+    let above = position.ceil() as usize;
+    let low = sorted[below].to_f64();
+    low + (position - below as f64) * (sorted[above].to_f64() - low)
+}
+
+// The middle value, or halfway between the two middle values of an even-length list.
+pub fn median<T: Sample>(values: &[T]) -> Result<f64, StatsError> {
+    quantile(values, 0.5)
+}
+
+// Every value that occurs most often, smallest first. When nothing repeats they all tie.
+pub fn modes<T: Sample>(values: &[T]) -> Result<Vec<T>, StatsError> {
+    Ok(modes_of_sorted(&sorted(values)?))
+}
+
+// Equal values sit next to each other once sorted, so counting runs does what a HashMap of counts
+// would, and works for floats, which can't be hashed.
+fn modes_of_sorted<T: Sample>(sorted: &[T]) -> Vec<T> {
+    let mut modes = Vec::new();
+    let mut best = 0;
+    let mut start = 0;
+    while start < sorted.len() {
+        let run = sorted[start..].iter().take_while(|&&v| v == sorted[start]).count();
+        if run > best {
+            best = run;
+            modes.clear();
+        }
+        if run == best {
+            modes.push(sorted[start]);
+        }
+        start += run;
     }
+    modes
+}
+
+// Estimates a quantile in constant memory as values stream past, with the P² algorithm of Jain
+// and Chlamtac. It tracks five markers: the minimum, the maximum, the quantile itself and one
+// halfway to each side, nudging their heights along a parabola as values arrive.
+#[derive(Debug, Clone)]
+pub struct P2Quantile {
+    q: f64,
+    // The first five values, until there are enough to place the markers.
+    initial: Vec<f64>,
+    heights: [f64; 5],
+    // Where each marker actually is, counting values from 1.
+    positions: [f64; 5],
+    // Where each marker ought to be, and how far that moves with every value.
+    desired: [f64; 5],
+    increments: [f64; 5],
 }
 
-fn mode(ints: &Vec<i32>) -> i32 {
-    // First, get count of each element.
-    let mut counts = HashMap::new();
-    for v in ints {
-        let count = counts.entry(*v).or_insert(0);
-        *count += 1;
+impl P2Quantile {
+    pub fn new(q: f64) -> Result<P2Quantile, StatsError> {
+        if !(0.0..=1.0).contains(&q) {
+            return Err(StatsError::InvalidQuantile(q));
+        }
+        Ok(P2Quantile {
+            q,
+            initial: Vec::with_capacity(5),
+            heights: [0.0; 5],
+            positions: [1.0, 2.0, 3.0, 4.0, 5.0],
+            desired: [1.0, 1.0 + 2.0 * q, 1.0 + 4.0 * q, 3.0 + 2.0 * q, 5.0],
+            increments: [0.0, q / 2.0, q, (1.0 + q) / 2.0, 1.0],
+        })
+    }
+
+    pub fn push<T: Sample>(&mut self, value: T) -> Result<(), StatsError> {
+        let x = check(value)?.to_f64();
+        if self.initial.len() < 5 {
+            self.initial.push(x);
+            if self.initial.len() == 5 {
+                self.initial.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+                self.heights.copy_from_slice(&self.initial);
+            }
+            return Ok(());
+        }
+
+        // Which cell between markers the value lands in, stretching the ends if need be.
+        let cell = if x < self.heights[0] {
+            self.heights[0] = x;
+            0
+        } else if x >= self.heights[4] {
+            self.heights[4] = x;
+            3
+        } else {
+            (0..4).find(|&i| x < self.heights[i + 1]).unwrap_or(3)
+        };
+        for position in &mut self.positions[cell + 1..] {
+            *position += 1.0;
+        }
+        for (desired, increment) in self.desired.iter_mut().zip(&self.increments) {
+            *desired += increment;
+        }
+
+        // Move the middle markers a step towards where they should be, when they're off by at
+        // least one and there's room to move.
+        for i in 1..4 {
+            let off = self.desired[i] - self.positions[i];
+            let room_right = self.positions[i + 1] - self.positions[i] > 1.0;
+            let room_left = self.positions[i - 1] - self.positions[i] < -1.0;
+            if (off >= 1.0 && room_right) || (off <= -1.0 && room_left) {
+                let step = off.signum();
+                let height = self.parabolic(i, step);
+                self.heights[i] = if self.heights[i - 1] < height && height < self.heights[i + 1] {
+                    height
+                } else {
+                    self.linear(i, step)
+                };
+                self.positions[i] += step;
+            }
+        }
+        Ok(())
+    }
+
+    fn parabolic(&self, i: usize, step: f64) -> f64 {
+        let (h, n) = (&self.heights, &self.positions);
+        h[i] + step / (n[i + 1] - n[i - 1])
+            * ((n[i] - n[i - 1] + step) * (h[i + 1] - h[i]) / (n[i + 1] - n[i])
+                + (n[i + 1] - n[i] - step) * (h[i] - h[i - 1]) / (n[i] - n[i - 1]))
+    }
+
+    fn linear(&self, i: usize, step: f64) -> f64 {
+        let j = if step > 0.0 { i + 1 } else { i - 1 };
+        self.heights[i] + step * (self.heights[j] - self.heights[i]) / (self.positions[j] - self.positions[i])
     }
-    let mut max: (i32, usize) = (0, 0);
-    for (k, v) in counts {
-        if v > max.1 {
-            max = (k, v);
+
+    // The estimate so far. Until there are five values it's simply exact.
+    pub fn estimate(&self) -> Result<f64, StatsError> {
+        if self.initial.len() < 5 {
+            return quantile(&self.initial, self.q);
         }
+        Ok(self.heights[2])
+    }
+}
+
+// Everything about a list of numbers at once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary<T: Sample> {
+    pub count: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub modes: Vec<T>,
+    pub variance: f64,
+    pub std_dev: f64,
+    pub min: T,
+    pub max: T,
+    pub range: f64,
+}
+
+pub fn summarize<T: Sample>(values: &[T]) -> Result<Summary<T>, StatsError> {
+    let sorted = sorted(values)?;
+    let mut running = Running::new();
+    for &value in values {
+        running.push(value)?;
+    }
+    Ok(Summary {
+        count: running.count(),
+        mean: running.mean()?,
+        median: quantile_of_sorted(&sorted, 0.5),
+        modes: modes_of_sorted(&sorted),
+        variance: running.variance()?,
+        std_dev: running.std_dev()?,
+        min: running.min()?,
+        max: running.max()?,
+        range: running.range()?,
+    })
+}
+
+// This is the public function from this module.
+pub fn ls<T: Sample>(values: Vec<T>) -> Result<Summary<T>, StatsError> {
+    summarize(&values)
+}
+
+// One bar of a histogram: how many values fell in `low..high` (the last bin includes `high`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bin {
+    pub low: f64,
+    pub high: f64,
+    pub count: usize,
+}
+
+// Splits the span from the smallest to the largest value into `bins` equal bins and counts the
+// values in each. If every value is the same there's nothing to split, so there's just one bin.
+pub fn histogram<T: Sample>(values: &[T], bins: usize) -> Result<Vec<Bin>, StatsError> {
+    if bins == 0 {
+        return Err(StatsError::TooFew { needed: 1, got: 0 });
+    }
+    let sorted = sorted(values)?;
+    let min = sorted[0].to_f64();
+    let max = sorted[sorted.len() - 1].to_f64();
+    if min == max {
+        return Ok(vec![Bin { low: min, high: max, count: sorted.len() }]);
+    }
+    let width = (max - min) / bins as f64;
+    let mut histogram: Vec<Bin> = (0..bins)
+        .map(|i| Bin {
+            low: min + width * i as f64,
+            high: if i + 1 == bins { max } else { min + width * (i + 1) as f64 },
+            count: 0,
+        })
+        .collect();
+    for value in sorted {
+        // The maximum would land one past the end; it belongs in the last bin.
+        let i = (((value.to_f64() - min) / width) as usize).min(bins - 1);
+        histogram[i].count += 1;
     }
-    println!("Count of {} is {}", max.0, max.1);
-    max.0
+    Ok(histogram)
 }
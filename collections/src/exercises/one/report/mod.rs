@@ -0,0 +1,195 @@
+// What the `stats` binary does, kept here so it can be tested: read its arguments, pull numbers
+// out of lines of text (or one column of them), and print a summary and histogram as text or JSON.
+
+use std::io::{self, BufRead, Write};
+
+use super::{histogram, quantile, summarize, Bin, StatsError, Summary};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    // Which field of each line to read, by position (from 1) or by header name. Without one,
+    // every line is a single number.
+    pub column: Option<String>,
+    pub delimiter: char,
+    pub bins: usize,
+    pub json: bool,
+    pub files: Vec<String>,
+}
+
+impl Config {
+    pub fn new<I: Iterator<Item = String>>(mut args: I) -> Result<Config, String> {
+        let mut config = Config { column: None, delimiter: ',', bins: 10, json: false, files: Vec::new() };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--column" | "-c" => config.column = Some(args.next().ok_or("--column needs a value")?),
+                "--delimiter" | "-d" => {
+                    let delimiter = args.next().ok_or("--delimiter needs a value")?;
+                    config.delimiter = match delimiter.as_str() {
+                        "\\t" | "tab" => '\t',
+                        d if d.chars().count() == 1 => d.chars().next().unwrap(),
+                        _ => return Err(String::from("--delimiter must be a single character")),
+                    };
+                }
+                "--bins" | "-b" => {
+                    config.bins = match args.next().and_then(|n| n.parse().ok()) {
+                        Some(n) if n > 0 => n,
+                        _ => return Err(String::from("--bins needs a number above 0")),
+                    }
+                }
+                "--json" => config.json = true,
+                _ if arg.starts_with('-') && arg != "-" => return Err(format!("Unknown argument {}", arg)),
+                _ => config.files.push(arg),
+            }
+        }
+        Ok(config)
+    }
+}
+
+#[derive(Debug)]
+pub enum ReadError {
+    Io(io::Error),
+    // The header has no column by the name asked for.
+    NoColumn(String),
+}
+
+impl From<io::Error> for ReadError {
+    fn from(e: io::Error) -> ReadError {
+        ReadError::Io(e)
+    }
+}
+
+// Adds the numbers in `input` to `numbers`. Lines that aren't numbers are skipped and counted
+// rather than stopping everything; a header line in a CSV is the usual culprit. So are "inf" and
+// "NaN", which parse but can't be summed or sorted into anything useful. Returns how many lines
+// were skipped.
+pub fn read_numbers<R: BufRead>(input: R, config: &Config, numbers: &mut Vec<f64>) -> Result<usize, ReadError> {
+    let mut skipped = 0;
+    // A numeric column is known straight away; a named one once the header has been read.
+    let mut index = match config.column {
+        Some(ref column) => column.parse::<usize>().ok().map(|n| n.saturating_sub(1)),
+        None => Some(0),
+    };
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let field = match config.column {
+            None => Some(line.trim()),
+            Some(ref name) => {
+                let fields: Vec<&str> = line.split(config.delimiter).map(|f| f.trim().trim_matches('"')).collect();
+                // Only a named column leaves the index unknown, until the first line that isn't
+                // blank: the header.
+                if index.is_none() {
+                    index = fields.iter().position(|f| f == name);
+                    if index.is_none() {
+                        return Err(ReadError::NoColumn(name.clone()));
+                    }
+                    continue;
+                }
+                index.and_then(|i| fields.get(i).cloned())
+            }
+        };
+        match field.and_then(|f| f.parse::<f64>().ok()).filter(|n| n.is_finite()) {
+            Some(n) => numbers.push(n),
+            None => skipped += 1,
+        }
+    }
+    Ok(skipped)
+}
+
+// Whole numbers print without a fraction, anything else with at most four decimals.
+fn number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        return format!("{}", n as i64);
+    }
+    let text = format!("{:.4}", n);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub summary: Summary<f64>,
+    // p50, p90 and p99, in that order.
+    pub percentiles: Vec<(u32, f64)>,
+    pub histogram: Vec<Bin>,
+}
+
+impl Report {
+    pub fn new(numbers: &[f64], bins: usize) -> Result<Report, StatsError> {
+        // Once summarize has accepted the numbers, the rest can only fail on a bad `bins`.
+        let summary = summarize(numbers)?;
+        let percentiles = [50, 90, 99]
+            .iter()
+            .map(|&p| quantile(numbers, f64::from(p) / 100.0).map(|value| (p, value)))
+            .collect::<Result<_, _>>()?;
+        Ok(Report { summary, percentiles, histogram: histogram(numbers, bins)? })
+    }
+}
+
+pub fn print_text<W: Write>(report: &Report, mut out: W) -> io::Result<()> {
+    let summary = &report.summary;
+    let modes: Vec<String> = summary.modes.iter().map(|&m| number(m)).collect();
+    writeln!(out, "count   {}", summary.count)?;
+    writeln!(out, "mean    {}", number(summary.mean))?;
+    writeln!(out, "median  {}", number(summary.median))?;
+    // When nothing repeats every value ties, which is no help to print.
+    if summary.count > 1 && modes.len() == summary.count {
+        writeln!(out, "modes   none, no value repeats")?;
+    } else {
+        writeln!(out, "mode{}   {}", if modes.len() == 1 { " " } else { "s" }, modes.join(", "))?;
+    }
+    writeln!(out, "stddev  {}", number(summary.std_dev))?;
+    writeln!(out, "min     {}", number(summary.min))?;
+    writeln!(out, "max     {}", number(summary.max))?;
+    for &(p, value) in &report.percentiles {
+        writeln!(out, "p{:<6} {}", p, number(value))?;
+    }
+    writeln!(out)?;
+
+    // Bars are scaled so the tallest one is 50 characters, and every non-empty bin gets at least
+    // one, so that stray values still show.
+    let labels: Vec<(String, String)> = report.histogram.iter().map(|b| (number(b.low), number(b.high))).collect();
+    let low_width = labels.iter().map(|l| l.0.len()).max().unwrap_or(0);
+    let high_width = labels.iter().map(|l| l.1.len()).max().unwrap_or(0);
+    let tallest = report.histogram.iter().map(|b| b.count).max().unwrap_or(0).max(1);
+    for (bin, (low, high)) in report.histogram.iter().zip(&labels) {
+        let mut bar = bin.count * 50 / tallest;
+        if bin.count > 0 && bar == 0 {
+            bar = 1;
+        }
+        writeln!(
+            out,
+            "{:>lw$} .. {:>hw$} | {} {}",
+            low,
+            high,
+            "#".repeat(bar),
+            bin.count,
+            lw = low_width,
+            hw = high_width
+        )?;
+    }
+    Ok(())
+}
+
+pub fn print_json<W: Write>(report: &Report, mut out: W) -> io::Result<()> {
+    let summary = &report.summary;
+    let mut value = json!({
+        "count": summary.count,
+        "mean": summary.mean,
+        "median": summary.median,
+        "modes": summary.modes,
+        "stddev": summary.std_dev,
+        "min": summary.min,
+        "max": summary.max,
+        "histogram": report.histogram.iter().map(|b| json!({
+            "low": b.low,
+            "high": b.high,
+            "count": b.count,
+        })).collect::<Vec<_>>(),
+    });
+    for &(p, v) in &report.percentiles {
+        value[format!("p{}", p)] = json!(v);
+    }
+    writeln!(out, "{}", value)
+}
@@ -6,32 +6,523 @@
 // alphabetically.
 
 use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+pub mod org;
+pub mod storage;
 
 pub struct Company {
     // Map from dept. to employees in the dept.
     pub departments: HashMap<String, Vec<String>>,
 }
 
+// Names and departments match whatever their case: "sally" in "engineering" is Sally in
+// Engineering. They keep the spelling they were first given.
+fn same(a: &str, b: &str) -> bool {
+    a == b || a.to_lowercase() == b.to_lowercase()
+}
+
+// Letters that are their own letter in Unicode rather than another letter with a mark added, so
+// decomposing leaves them alone, and what they sort with.
+const UNMARKED_LETTERS: &[(char, &str)] = &[
+    ('æ', "ae"), ('đ', "d"), ('ħ', "h"), ('ı', "i"), ('ł', "l"), ('ø', "o"), ('œ', "oe"), ('ß', "ss"), ('þ', "th"),
+];
+
+// What names sort by. Plain `sort` compares code points, which puts every lowercase name after
+// every capitalised one and "Émile" after "Zoe". Sorting on the lowercased name with accents
+// taken off puts Émile among the Es, and the name itself breaks any ties so the order is always
+// the same.
+//
+// Taking accents off is done by decomposing (NFKD), which splits "é" into "e" and a combining
+// acute accent (and "ﬁ" into "fi"), then dropping the combining marks. That works for any
+// script: "Ōsaka" sorts as "osaka", "Ștefan" as "stefan" and "Άννα" as "αννα".
+pub fn sort_key(name: &str) -> (String, &str) {
+    let mut key = String::with_capacity(name.len());
+    for c in name.nfkd().filter(|&c| !is_combining_mark(c)).flat_map(char::to_lowercase) {
+        match UNMARKED_LETTERS.iter().find(|&&(letter, _)| letter == c) {
+            Some(&(_, base)) => key.push_str(base),
+            None => key.push(c),
+        }
+    }
+    (key, name)
+}
+
+fn sort_names(names: &mut [&str]) {
+    names.sort_by(|a, b| sort_key(a).cmp(&sort_key(b)));
+}
+
+// Every way the company can change. Going through `Company::apply` for all of them is what makes
+// undo possible: each one knows how to produce the changes that take it back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Add { name: String, dept: String },
+    Remove { name: String, dept: String },
+    Transfer { name: String, from: String, to: String },
+    RenameDept { from: String, to: String },
+    // Everyone in `from` moves to `into`, and `from` is gone.
+    MergeDepts { from: String, into: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompanyError {
+    NoSuchDept(String),
+    NotInDept { name: String, dept: String },
+    AlreadyInDept { name: String, dept: String },
+    DeptExists(String),
+    SameDept(String),
+    NothingToUndo,
+    NothingToRedo,
+}
+
+impl fmt::Display for CompanyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CompanyError::NoSuchDept(ref dept) => write!(f, "There's no department called {}.", dept),
+            CompanyError::NotInDept { ref name, ref dept } => write!(f, "{} isn't in {}.", name, dept),
+            CompanyError::AlreadyInDept { ref name, ref dept } => write!(f, "{} is already in {}.", name, dept),
+            CompanyError::DeptExists(ref dept) => write!(f, "There's already a department called {}.", dept),
+            CompanyError::SameDept(ref dept) => write!(f, "That's {} both times.", dept),
+            CompanyError::NothingToUndo => write!(f, "Nothing to undo."),
+            CompanyError::NothingToRedo => write!(f, "Nothing to redo."),
+        }
+    }
+}
+
 impl Company {
-    pub fn add_to_dept(&mut self, dept: &str, name: &str) {
-        let entry = self.departments.entry(String::from(dept)).or_insert(vec![]);
-        entry.push(String::from(name));
+    pub fn new() -> Company {
+        Company { departments: HashMap::new() }
+    }
+
+    pub fn add_to_dept(&mut self, dept: &str, name: &str) -> Result<(), CompanyError> {
+        self.apply(&Change::Add { name: String::from(name), dept: String::from(dept) }).map(|_| ())
     }
 
     pub fn get_all_from_dept(&self, dept: &str) -> Option<&Vec<String>> {
-        if let Some(ref employees) = self.departments.get(dept) {
-            Some(employees)
-        } else {
-            None
-        }
+        self.dept_name(dept).map(|dept| &self.departments[dept])
     }
 
     pub fn get_all_sorted(&self) -> Vec<String> {
-        let mut all = self.departments.values().fold(Vec::new(), |mut acc, x| {
-            acc.extend_from_slice(&x);
-            acc
-        });
-        all.sort();
-        all
+        let mut all: Vec<&str> = self.departments.values().flatten().map(|name| name.as_str()).collect();
+        sort_names(&mut all);
+        all.into_iter().map(String::from).collect()
+    }
+
+    // The people in `dept`, sorted, or None if there's no such department.
+    pub fn sorted_dept(&self, dept: &str) -> Option<Vec<&str>> {
+        self.get_all_from_dept(dept).map(|employees| {
+            let mut sorted: Vec<&str> = employees.iter().map(|e| e.as_str()).collect();
+            sort_names(&mut sorted);
+            sorted
+        })
+    }
+
+    // Every department with its people, departments and people both sorted.
+    pub fn sorted_by_dept(&self) -> Vec<(&str, Vec<&str>)> {
+        let mut depts: Vec<&str> = self.departments.keys().map(|d| d.as_str()).collect();
+        sort_names(&mut depts);
+        depts.into_iter().map(|d| (d, self.sorted_dept(d).unwrap_or_default())).collect()
+    }
+
+    // How `dept` is actually spelled here, if it's here at all.
+    pub fn dept_name(&self, dept: &str) -> Option<&str> {
+        if let Some((key, _)) = self.departments.get_key_value(dept) {
+            return Some(key);
+        }
+        self.departments.keys().find(|key| same(key, dept)).map(|key| key.as_str())
+    }
+
+    // Where `name` is in `dept`, and the department's own spelling.
+    fn find(&self, name: &str, dept: &str) -> Result<(String, usize), CompanyError> {
+        let dept = self.dept_name(dept).ok_or_else(|| CompanyError::NoSuchDept(dept.to_string()))?;
+        let at = self.departments[dept]
+            .iter()
+            .position(|n| same(n, name))
+            .ok_or_else(|| CompanyError::NotInDept { name: name.to_string(), dept: dept.to_string() })?;
+        Ok((dept.to_string(), at))
+    }
+
+    fn check_not_in(&self, name: &str, dept: &str) -> Result<(), CompanyError> {
+        match self.find(name, dept) {
+            Ok((dept, at)) => Err(CompanyError::AlreadyInDept { name: self.departments[&dept][at].clone(), dept }),
+            Err(_) => Ok(()),
+        }
+    }
+
+    // Takes someone out of a department, which goes away once it's empty. Returns the name as it
+    // was spelled and the department's spelling.
+    fn take(&mut self, name: &str, dept: &str) -> Result<(String, String), CompanyError> {
+        let (dept, at) = self.find(name, dept)?;
+        let employees = self.departments.get_mut(&dept).unwrap();
+        let name = employees.remove(at);
+        if employees.is_empty() {
+            self.departments.remove(&dept);
+        }
+        Ok((name, dept))
+    }
+
+    fn put(&mut self, name: String, dept: &str) -> String {
+        let dept = self.dept_name(dept).unwrap_or(dept).to_string();
+        self.departments.entry(dept.clone()).or_default().push(name);
+        dept
+    }
+
+    // Makes the change, or explains why it can't without changing anything. On success returns
+    // the changes that would undo it, to be applied in order.
+    pub fn apply(&mut self, change: &Change) -> Result<Vec<Change>, CompanyError> {
+        match *change {
+            Change::Add { ref name, ref dept } => {
+                self.check_not_in(name, dept)?;
+                let dept = self.put(name.clone(), dept);
+                Ok(vec![Change::Remove { name: name.clone(), dept }])
+            }
+            Change::Remove { ref name, ref dept } => {
+                let (name, dept) = self.take(name, dept)?;
+                Ok(vec![Change::Add { name, dept }])
+            }
+            Change::Transfer { ref name, ref from, ref to } => {
+                self.find(name, from)?;
+                if same(from, to) {
+                    return Err(CompanyError::SameDept(from.clone()));
+                }
+                self.check_not_in(name, to)?;
+                let (name, from) = self.take(name, from)?;
+                let to = self.put(name.clone(), to);
+                Ok(vec![Change::Transfer { name, from: to, to: from }])
+            }
+            Change::RenameDept { ref from, ref to } => {
+                let from = self.dept_name(from).ok_or_else(|| CompanyError::NoSuchDept(from.clone()))?.to_string();
+                // Changing only the case of a name is fine; taking another department's isn't.
+                if !same(&from, to) {
+                    if let Some(existing) = self.dept_name(to) {
+                        return Err(CompanyError::DeptExists(existing.to_string()));
+                    }
+                }
+                let employees = self.departments.remove(&from).unwrap();
+                self.departments.insert(to.clone(), employees);
+                Ok(vec![Change::RenameDept { from: to.clone(), to: from }])
+            }
+            Change::MergeDepts { ref from, ref into } => {
+                let from = self.dept_name(from).ok_or_else(|| CompanyError::NoSuchDept(from.clone()))?.to_string();
+                let into = self.dept_name(into).ok_or_else(|| CompanyError::NoSuchDept(into.clone()))?.to_string();
+                if from == into {
+                    return Err(CompanyError::SameDept(from));
+                }
+                // People in both end up in `into` once. Undoing moves the others back and puts
+                // them back in `from` as well.
+                let mut undo = Vec::new();
+                for name in self.departments.remove(&from).unwrap() {
+                    if self.check_not_in(&name, &into).is_ok() {
+                        self.put(name.clone(), &into);
+                        undo.push(Change::Transfer { name, from: into.clone(), to: from.clone() });
+                    } else {
+                        undo.push(Change::Add { name, dept: from.clone() });
+                    }
+                }
+                Ok(undo)
+            }
+        }
+    }
+}
+
+impl Default for Company {
+    fn default() -> Company {
+        Company::new()
+    }
+}
+
+// One undoable step: the changes made and the changes that take them back.
+#[derive(Debug, Clone)]
+struct Step {
+    changes: Vec<Change>,
+    undo: Vec<Change>,
+}
+
+// Undo and redo for changes made through it.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    done: Vec<Step>,
+    undone: Vec<Step>,
+}
+
+impl History {
+    pub fn new() -> History {
+        History::default()
+    }
+
+    pub fn apply(&mut self, company: &mut Company, change: Change) -> Result<(), CompanyError> {
+        self.apply_all(company, vec![change])
+    }
+
+    // Applies the changes as a single step, which undo takes back all at once. If one of them
+    // fails the ones before it are undone, so it's all or nothing.
+    pub fn apply_all(&mut self, company: &mut Company, changes: Vec<Change>) -> Result<(), CompanyError> {
+        let undo = apply_all(company, &changes)?;
+        self.done.push(Step { changes, undo });
+        // A new change starts a new future; what was undone can't be redone on top of it.
+        self.undone.clear();
+        Ok(())
+    }
+
+    // Takes back the last step and returns the changes it had made.
+    pub fn undo(&mut self, company: &mut Company) -> Result<Vec<Change>, CompanyError> {
+        let step = self.done.pop().ok_or(CompanyError::NothingToUndo)?;
+        apply_all(company, &step.undo)?;
+        let changes = step.changes.clone();
+        self.undone.push(step);
+        Ok(changes)
+    }
+
+    pub fn redo(&mut self, company: &mut Company) -> Result<Vec<Change>, CompanyError> {
+        let step = self.undone.pop().ok_or(CompanyError::NothingToRedo)?;
+        let undo = apply_all(company, &step.changes)?;
+        let changes = step.changes.clone();
+        self.done.push(Step { changes: step.changes, undo });
+        Ok(changes)
+    }
+
+    // Forgets everything, for when the company is replaced wholesale.
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+    }
+}
+
+// Applies `changes` in order and returns what undoes all of them, or rolls back and fails.
+fn apply_all(company: &mut Company, changes: &[Change]) -> Result<Vec<Change>, CompanyError> {
+    let mut undo: Vec<Vec<Change>> = Vec::new();
+    for change in changes {
+        match company.apply(change) {
+            Ok(steps) => undo.push(steps),
+            Err(e) => {
+                for steps in undo.iter().rev() {
+                    for step in steps {
+                        company.apply(step).expect("undoing a change that was just made");
+                    }
+                }
+                return Err(e);
+            }
+        }
+    }
+    // Later changes have to be undone first.
+    Ok(undo.into_iter().rev().flatten().collect())
+}
+
+// The text interface the question asks for. Keywords are case-insensitive; names and departments
+// are taken as typed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    // "Add Sally to Engineering", "Remove Sally from Engineering",
+    // "Move Sally from Engineering to Sales", "Rename Sales to Revenue" and
+    // "Merge Marketing into Sales"
+    Change(Change),
+    Undo,
+    Redo,
+    // "List Engineering"
+    List(String),
+    // "List all", or just "List"
+    ListAll,
+    // "Save company.json", "Load company.csv" and "Import more.csv"
+    Save(String),
+    Load(String),
+    Import(String),
+    Help,
+    Quit,
+}
+
+pub const HELP: &str = "Commands:
+  Add <name> to <department>               add someone to a department
+  Remove <name> from <department>          take someone out of a department
+  Move <name> from <department> to <other> move someone to another department
+  Rename <department> to <new name>        rename a department
+  Merge <department> into <other>          move everyone over and close the first department
+  Undo, Redo                               take back the last change, or put it back
+  List <department>                        everyone in a department
+  List all                                 everyone, by department
+  Save <file>                              save everyone, as CSV if the file ends in .csv, JSON otherwise
+  Load <file>                              replace everyone with what's in a saved file
+  Import <file>                            add everyone in a file, skipping duplicates and conflicts
+  help                                     this message
+  quit                                     leave";
+
+// Splits `text` at the first `word` (" to ", say), trimming both sides; None unless both sides
+// have something in them. Names and departments may have spaces, so "Add Mary Jo to Research and
+// Development" works, though a name with " to " in it doesn't.
+fn split_at_word<'a>(text: &'a str, word: &str) -> Option<(&'a str, &'a str)> {
+    let at = text
+        .char_indices()
+        .map(|(at, _)| at)
+        .find(|&at| text[at..].get(..word.len()).is_some_and(|s| s.eq_ignore_ascii_case(word)))?;
+    let (left, right) = (text[..at].trim(), text[at + word.len()..].trim());
+    if left.is_empty() || right.is_empty() {
+        None
+    } else {
+        Some((left, right))
+    }
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Command, String> {
+        let line = line.trim();
+        let mut words = line.splitn(2, char::is_whitespace);
+        let keyword = words.next().unwrap_or("").to_lowercase();
+        let rest = words.next().unwrap_or("").trim();
+        let expected = |usage: &str| format!("Expected '{}'.", usage);
+        let change = match keyword.as_str() {
+            "add" => split_at_word(rest, " to ")
+                .map(|(name, dept)| Change::Add { name: name.to_string(), dept: dept.to_string() })
+                .ok_or_else(|| expected("Add <name> to <department>', like 'Add Sally to Engineering")),
+            "remove" => split_at_word(rest, " from ")
+                .map(|(name, dept)| Change::Remove { name: name.to_string(), dept: dept.to_string() })
+                .ok_or_else(|| expected("Remove <name> from <department>")),
+            "move" | "transfer" => split_at_word(rest, " from ")
+                .and_then(|(name, rest)| split_at_word(rest, " to ").map(|(from, to)| (name, from, to)))
+                .map(|(name, from, to)| Change::Transfer { name: name.to_string(), from: from.to_string(), to: to.to_string() })
+                .ok_or_else(|| expected("Move <name> from <department> to <department>")),
+            "rename" => split_at_word(rest, " to ")
+                .map(|(from, to)| Change::RenameDept { from: from.to_string(), to: to.to_string() })
+                .ok_or_else(|| expected("Rename <department> to <new name>")),
+            "merge" => split_at_word(rest, " into ")
+                .map(|(from, into)| Change::MergeDepts { from: from.to_string(), into: into.to_string() })
+                .ok_or_else(|| expected("Merge <department> into <department>")),
+            _ => {
+                return match keyword.as_str() {
+                    "undo" if rest.is_empty() => Ok(Command::Undo),
+                    "redo" if rest.is_empty() => Ok(Command::Redo),
+                    "list" if rest.is_empty() || rest.eq_ignore_ascii_case("all") => Ok(Command::ListAll),
+                    "list" => Ok(Command::List(rest.to_string())),
+                    "save" | "load" | "import" if rest.is_empty() => Err(expected(&format!("{} <file>", capitalized(&keyword)))),
+                    "save" => Ok(Command::Save(rest.to_string())),
+                    "load" => Ok(Command::Load(rest.to_string())),
+                    "import" => Ok(Command::Import(rest.to_string())),
+                    "help" | "?" if rest.is_empty() => Ok(Command::Help),
+                    "quit" | "exit" if rest.is_empty() => Ok(Command::Quit),
+                    "" => Err(String::from("Type a command, or 'help' to see them all.")),
+                    _ => Err(format!("Don't know how to '{}'. Type 'help' to see the commands.", line)),
+                }
+            }
+        };
+        change.map(Command::Change)
+    }
+}
+
+fn capitalized(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
+
+fn describe(change: &Change) -> String {
+    match *change {
+        Change::Add { ref name, ref dept } => format!("added {} to {}", name, dept),
+        Change::Remove { ref name, ref dept } => format!("removed {} from {}", name, dept),
+        Change::Transfer { ref name, ref from, ref to } => format!("moved {} from {} to {}", name, from, to),
+        Change::RenameDept { ref from, ref to } => format!("renamed {} to {}", from, to),
+        Change::MergeDepts { ref from, ref into } => format!("merged {} into {}", from, into),
+    }
+}
+
+// Carries out `command`, writing what happened to `output`. Returns false once it's time to quit.
+pub fn execute<O: Write>(company: &mut Company, history: &mut History, command: Command, mut output: O) -> io::Result<bool> {
+    match command {
+        Command::Change(change) => match history.apply(company, change.clone()) {
+            Ok(()) => writeln!(output, "{}.", capitalized(&describe(&change)))?,
+            Err(e) => writeln!(output, "{}", e)?,
+        },
+        Command::Undo => match history.undo(company) {
+            Ok(changes) => writeln!(output, "Undone: {}.", changes.iter().map(describe).collect::<Vec<_>>().join(", "))?,
+            Err(e) => writeln!(output, "{}", e)?,
+        },
+        Command::Redo => match history.redo(company) {
+            Ok(changes) => writeln!(output, "Redone: {}.", changes.iter().map(describe).collect::<Vec<_>>().join(", "))?,
+            Err(e) => writeln!(output, "{}", e)?,
+        },
+        Command::List(dept) => match company.sorted_dept(&dept) {
+            Some(employees) => print_dept(company.dept_name(&dept).unwrap_or(&dept), &employees, &mut output)?,
+            None => writeln!(output, "{}", CompanyError::NoSuchDept(dept))?,
+        },
+        Command::ListAll => {
+            let all = company.sorted_by_dept();
+            if all.is_empty() {
+                writeln!(output, "Nobody works here yet.")?;
+            }
+            for (dept, employees) in all {
+                print_dept(dept, &employees, &mut output)?;
+            }
+        }
+        Command::Save(file) => match storage::save(company, Path::new(&file)) {
+            Ok(()) => writeln!(output, "Saved to {}.", file)?,
+            Err(e) => writeln!(output, "Couldn't save to {}: {}", file, e)?,
+        },
+        Command::Load(file) => match storage::load(Path::new(&file)) {
+            Ok(loaded) => {
+                // Undoing past a load would apply changes to a company they were never made to.
+                *company = loaded;
+                history.clear();
+                writeln!(output, "Loaded {}.", file)?;
+            }
+            Err(e) => writeln!(output, "Couldn't load {}", e)?,
+        },
+        // The whole import is one step, so a single undo takes it all back.
+        Command::Import(file) => match storage::read_import(company, Path::new(&file)) {
+            Ok(report) => match history.apply_all(company, report.changes.clone()) {
+                Ok(()) => print_import(&file, &report, &mut output)?,
+                Err(e) => writeln!(output, "Couldn't import {}: {}", file, e)?,
+            },
+            Err(e) => writeln!(output, "Couldn't import {}", e)?,
+        },
+        Command::Help => writeln!(output, "{}", HELP)?,
+        Command::Quit => return Ok(false),
+    }
+    Ok(true)
+}
+
+fn print_import<O: Write>(file: &str, report: &storage::ImportReport, output: &mut O) -> io::Result<()> {
+    writeln!(output, "Imported {} from {}.", report.changes.len(), file)?;
+    for row in &report.duplicates {
+        writeln!(output, "  row {}: {} is already in {}, skipped", row.row, row.name, row.dept)?;
+    }
+    for conflict in &report.conflicts {
+        let row = &conflict.row;
+        writeln!(output, "  row {}: {} is in {}, not {}, skipped", row.row, row.name, conflict.existing.join(" and "), row.dept)?;
+    }
+    for invalid in &report.invalid {
+        writeln!(output, "  row {}: {}, skipped", invalid.row, invalid.error)?;
+    }
+    Ok(())
+}
+
+fn print_dept<O: Write>(dept: &str, employees: &[&str], output: &mut O) -> io::Result<()> {
+    writeln!(output, "{}:", dept)?;
+    for employee in employees {
+        writeln!(output, "  {}", employee)?;
+    }
+    Ok(())
+}
+
+// Reads commands from `input` until it runs out or says quit. Mistakes are explained and the
+// session carries on.
+pub fn repl<I: BufRead, O: Write>(company: &mut Company, mut input: I, mut output: O) -> io::Result<()> {
+    let mut history = History::new();
+    loop {
+        write!(output, "> ")?;
+        output.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let keep_going = match Command::parse(&line) {
+            Ok(command) => execute(company, &mut history, command, &mut output)?,
+            Err(error) => {
+                writeln!(output, "{}", error)?;
+                true
+            }
+        };
+        if !keep_going {
+            return Ok(());
+        }
     }
 }
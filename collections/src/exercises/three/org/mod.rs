@@ -0,0 +1,361 @@
+// The company as an org chart: departments inside departments, and people with an id, a title and
+// a manager. `Company` stays as it is, a flat map from department to names, and the two convert
+// into each other; a flat company becomes a chart of top-level departments with nobody managing
+// anybody.
+//
+// Departments are kept by name, each with the name of the department it sits in, if any. Names
+// match whatever their case, as in `Company`. Both parents and managers are links upwards, so
+// nothing can be put under itself: setting either checks the chain above first.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use super::{same, sort_key, Company};
+
+pub type Id = u32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Employee {
+    pub id: Id,
+    pub name: String,
+    pub title: String,
+    pub dept: String,
+    pub manager: Option<Id>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrgError {
+    NoSuchEmployee(Id),
+    NoSuchDept(String),
+    DeptExists(String),
+    // A department that still has people or departments in it.
+    DeptNotEmpty(String),
+    // Making `manager` manage `employee` would have `employee` managing themselves, directly or
+    // further up.
+    ManagerCycle { employee: Id, manager: Id },
+    DeptCycle { dept: String, parent: String },
+}
+
+impl fmt::Display for OrgError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            OrgError::NoSuchEmployee(id) => write!(f, "There's nobody with id {}.", id),
+            OrgError::NoSuchDept(ref dept) => write!(f, "There's no department called {}.", dept),
+            OrgError::DeptExists(ref dept) => write!(f, "There's already a department called {}.", dept),
+            OrgError::DeptNotEmpty(ref dept) => write!(f, "{} still has people or departments in it.", dept),
+            OrgError::ManagerCycle { employee, manager } => {
+                write!(f, "{} can't manage {}, who is above them.", manager, employee)
+            }
+            OrgError::DeptCycle { ref dept, ref parent } => write!(f, "{} can't go inside {}, which is inside it.", dept, parent),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OrgChart {
+    // Map from dept. to the dept. it's part of.
+    departments: HashMap<String, Option<String>>,
+    employees: HashMap<Id, Employee>,
+    next_id: Id,
+}
+
+fn sort_employees(employees: &mut Vec<&Employee>) {
+    employees.sort_by(|a, b| sort_key(&a.name).cmp(&sort_key(&b.name)).then(a.id.cmp(&b.id)));
+}
+
+impl OrgChart {
+    pub fn new() -> OrgChart {
+        OrgChart::default()
+    }
+
+    // Everyone in `company` gets an id, in department then name order, and an empty title.
+    // Departments whose names differ only in case, which a `Company` built by hand can have, are
+    // merged into one, spelled the way the first of them sorts.
+    pub fn from_company(company: &Company) -> OrgChart {
+        let mut chart = OrgChart::new();
+        for (dept, employees) in company.sorted_by_dept() {
+            if chart.dept_name(dept).is_none() {
+                chart.add_dept(dept, None).expect("the department isn't there yet");
+            }
+            for name in employees {
+                chart.hire(name, "", dept).expect("the department was just added");
+            }
+        }
+        chart
+    }
+
+    // Flattens the chart back into a `Company`, which has no room for titles, managers or
+    // where departments sit. Empty departments are left out, since a `Company` can't have them.
+    pub fn to_company(&self) -> Company {
+        let mut company = Company::new();
+        for employee in self.employees.values() {
+            company.departments.entry(employee.dept.clone()).or_default().push(employee.name.clone());
+        }
+        company
+    }
+
+    // How `dept` is actually spelled here, if it's here at all.
+    pub fn dept_name(&self, dept: &str) -> Option<&str> {
+        self.departments.keys().find(|key| same(key, dept)).map(|key| key.as_str())
+    }
+
+    fn check_dept(&self, dept: &str) -> Result<String, OrgError> {
+        self.dept_name(dept).map(String::from).ok_or_else(|| OrgError::NoSuchDept(dept.to_string()))
+    }
+
+    pub fn add_dept(&mut self, dept: &str, parent: Option<&str>) -> Result<(), OrgError> {
+        if let Some(existing) = self.dept_name(dept) {
+            return Err(OrgError::DeptExists(existing.to_string()));
+        }
+        let parent = match parent {
+            Some(parent) => Some(self.check_dept(parent)?),
+            None => None,
+        };
+        self.departments.insert(dept.to_string(), parent);
+        Ok(())
+    }
+
+    // Moves `dept`, with everything in it, inside `parent`, or to the top with None.
+    pub fn move_dept(&mut self, dept: &str, parent: Option<&str>) -> Result<(), OrgError> {
+        let dept = self.check_dept(dept)?;
+        let parent = match parent {
+            Some(parent) => Some(self.check_dept(parent)?),
+            None => None,
+        };
+        if let Some(ref parent) = parent {
+            if self.dept_path(parent).iter().any(|d| *d == dept) {
+                return Err(OrgError::DeptCycle { dept, parent: parent.clone() });
+            }
+        }
+        self.departments.insert(dept, parent);
+        Ok(())
+    }
+
+    // Only empty departments can go, so nobody is left without one.
+    pub fn remove_dept(&mut self, dept: &str) -> Result<(), OrgError> {
+        let dept = self.check_dept(dept)?;
+        if !self.subdepts(&dept).is_empty() || self.employees.values().any(|e| e.dept == dept) {
+            return Err(OrgError::DeptNotEmpty(dept));
+        }
+        self.departments.remove(&dept);
+        Ok(())
+    }
+
+    pub fn parent(&self, dept: &str) -> Option<&str> {
+        self.dept_name(dept).and_then(|dept| self.departments[dept].as_deref())
+    }
+
+    // `dept` and the departments it's inside, innermost first: ["Platform", "Engineering"].
+    pub fn dept_path(&self, dept: &str) -> Vec<&str> {
+        let mut path = Vec::new();
+        let mut next = self.dept_name(dept);
+        while let Some(dept) = next {
+            path.push(dept);
+            next = self.departments[dept].as_deref();
+        }
+        path
+    }
+
+    // The departments directly inside `dept`, sorted; the top-level ones for None.
+    pub fn subdepts(&self, dept: &str) -> Vec<&str> {
+        self.children(Some(dept))
+    }
+
+    fn children(&self, dept: Option<&str>) -> Vec<&str> {
+        let mut children: Vec<&str> = self
+            .departments
+            .iter()
+            .filter(|&(_, parent)| match (parent.as_deref(), dept) {
+                (Some(parent), Some(dept)) => same(parent, dept),
+                (None, None) => true,
+                _ => false,
+            })
+            .map(|(child, _)| child.as_str())
+            .collect();
+        children.sort_by(|a, b| sort_key(a).cmp(&sort_key(b)));
+        children
+    }
+
+    pub fn hire(&mut self, name: &str, title: &str, dept: &str) -> Result<Id, OrgError> {
+        let dept = self.check_dept(dept)?;
+        self.next_id += 1;
+        let id = self.next_id;
+        let employee = Employee { id, name: name.to_string(), title: title.to_string(), dept, manager: None };
+        self.employees.insert(id, employee);
+        Ok(id)
+    }
+
+    // Whoever `id` managed now reports to `id`'s own manager instead.
+    pub fn remove(&mut self, id: Id) -> Result<Employee, OrgError> {
+        let employee = self.employees.remove(&id).ok_or(OrgError::NoSuchEmployee(id))?;
+        for report in self.employees.values_mut().filter(|e| e.manager == Some(id)) {
+            report.manager = employee.manager;
+        }
+        Ok(employee)
+    }
+
+    pub fn get(&self, id: Id) -> Option<&Employee> {
+        self.employees.get(&id)
+    }
+
+    // Everyone called `name`, whatever the case. There may be more than one.
+    pub fn find(&self, name: &str) -> Vec<&Employee> {
+        let mut found: Vec<&Employee> = self.employees.values().filter(|e| same(&e.name, name)).collect();
+        sort_employees(&mut found);
+        found
+    }
+
+    pub fn transfer(&mut self, id: Id, dept: &str) -> Result<(), OrgError> {
+        let dept = self.check_dept(dept)?;
+        let employee = self.employees.get_mut(&id).ok_or(OrgError::NoSuchEmployee(id))?;
+        employee.dept = dept;
+        Ok(())
+    }
+
+    pub fn set_title(&mut self, id: Id, title: &str) -> Result<(), OrgError> {
+        let employee = self.employees.get_mut(&id).ok_or(OrgError::NoSuchEmployee(id))?;
+        employee.title = title.to_string();
+        Ok(())
+    }
+
+    // Has `manager` manage `id`, or nobody with None. Refused if `id` is `manager` or anywhere
+    // above them, which would make a loop with nobody at the top.
+    pub fn set_manager(&mut self, id: Id, manager: Option<Id>) -> Result<(), OrgError> {
+        if !self.employees.contains_key(&id) {
+            return Err(OrgError::NoSuchEmployee(id));
+        }
+        if let Some(manager) = manager {
+            if !self.employees.contains_key(&manager) {
+                return Err(OrgError::NoSuchEmployee(manager));
+            }
+            if manager == id || self.chain_of_command(manager)?.iter().any(|e| e.id == id) {
+                return Err(OrgError::ManagerCycle { employee: id, manager });
+            }
+        }
+        self.employees.get_mut(&id).unwrap().manager = manager;
+        Ok(())
+    }
+
+    // `id`'s manager, their manager and so on up to someone with no manager.
+    pub fn chain_of_command(&self, id: Id) -> Result<Vec<&Employee>, OrgError> {
+        let mut chain = Vec::new();
+        let mut next = self.employees.get(&id).ok_or(OrgError::NoSuchEmployee(id))?.manager;
+        while let Some(manager) = next {
+            let manager = &self.employees[&manager];
+            chain.push(manager);
+            next = manager.manager;
+        }
+        Ok(chain)
+    }
+
+    // The people `id` manages directly, sorted by name.
+    pub fn direct_reports(&self, id: Id) -> Vec<&Employee> {
+        let mut reports: Vec<&Employee> = self.employees.values().filter(|e| e.manager == Some(id)).collect();
+        sort_employees(&mut reports);
+        reports
+    }
+
+    // Everyone under `id`, however far down, level by level.
+    pub fn all_reports(&self, id: Id) -> Result<Vec<&Employee>, OrgError> {
+        if !self.employees.contains_key(&id) {
+            return Err(OrgError::NoSuchEmployee(id));
+        }
+        let mut reports = self.direct_reports(id);
+        let mut i = 0;
+        while i < reports.len() {
+            let below = self.direct_reports(reports[i].id);
+            reports.extend(below);
+            i += 1;
+        }
+        Ok(reports)
+    }
+
+    // The people in `dept` itself, sorted by name.
+    pub fn members(&self, dept: &str) -> Result<Vec<&Employee>, OrgError> {
+        let dept = self.check_dept(dept)?;
+        let mut members: Vec<&Employee> = self.employees.values().filter(|e| e.dept == dept).collect();
+        sort_employees(&mut members);
+        Ok(members)
+    }
+
+    // The people in `dept` and every department inside it.
+    pub fn all_members(&self, dept: &str) -> Result<Vec<&Employee>, OrgError> {
+        let mut members = self.members(dept)?;
+        for sub in self.subdepts(dept) {
+            members.extend(self.all_members(sub)?);
+        }
+        Ok(members)
+    }
+
+    // Every department as a tree, with its people first and then the departments inside it.
+    //
+    //     Engineering
+    //     ├── Abhishek, Engineer
+    //     └── Platform
+    //         └── Sally, Lead
+    pub fn render_depts(&self) -> String {
+        let mut out = String::new();
+        for dept in self.children(None) {
+            draw(&self.dept_tree(dept), &mut out);
+        }
+        out
+    }
+
+    fn dept_tree(&self, dept: &str) -> Tree {
+        let people = self.members(dept).unwrap_or_default().into_iter().map(|e| Tree::leaf(label(e)));
+        let subdepts = self.subdepts(dept).into_iter().map(|sub| self.dept_tree(sub));
+        Tree { label: dept.to_string(), children: people.chain(subdepts).collect() }
+    }
+
+    // `id` and everyone under them, as a tree.
+    pub fn render_reports(&self, id: Id) -> Result<String, OrgError> {
+        let employee = self.employees.get(&id).ok_or(OrgError::NoSuchEmployee(id))?;
+        let mut out = String::new();
+        draw(&self.report_tree(employee), &mut out);
+        Ok(out)
+    }
+
+    fn report_tree(&self, employee: &Employee) -> Tree {
+        let children = self.direct_reports(employee.id).into_iter().map(|e| self.report_tree(e)).collect();
+        Tree { label: label(employee), children }
+    }
+}
+
+fn label(employee: &Employee) -> String {
+    if employee.title.is_empty() {
+        employee.name.clone()
+    } else {
+        format!("{}, {}", employee.name, employee.title)
+    }
+}
+
+struct Tree {
+    label: String,
+    children: Vec<Tree>,
+}
+
+impl Tree {
+    fn leaf(label: String) -> Tree {
+        Tree { label, children: Vec::new() }
+    }
+}
+
+fn draw(tree: &Tree, out: &mut String) {
+    out.push_str(&tree.label);
+    out.push('\n');
+    draw_children(&tree.children, "", out);
+}
+
+// Each child hangs off a branch; the prefix carries the lines of the branches above it that
+// still have children to come.
+fn draw_children(children: &[Tree], prefix: &str, out: &mut String) {
+    for (i, child) in children.iter().enumerate() {
+        let last = i + 1 == children.len();
+        out.push_str(prefix);
+        out.push_str(if last { "└── " } else { "├── " });
+        out.push_str(&child.label);
+        out.push('\n');
+        let prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        draw_children(&child.children, &prefix, out);
+    }
+}
@@ -0,0 +1,361 @@
+// Saving a `Company` to disk and reading it back, as JSON or as CSV.
+//
+// JSON files carry a schema version next to the data:
+//
+//     {"version": 1, "departments": {"Engineering": ["Abhishek", "Sally"], "Sales": ["Amir"]}}
+//
+// A file without a version is taken to be version 0, which was just the bare departments object;
+// it loads fine and is written back as version 1. A version newer than this code knows about is
+// refused rather than half understood.
+//
+// CSV files are one person per row under a `name,department` header, with the version in a
+// comment line above it:
+//
+//     # version 1
+//     name,department
+//     Abhishek,Engineering
+//
+// Saves are atomic: the new contents go to a temporary file next to the real one, which is then
+// renamed over it, so a crash halfway leaves the old file intact rather than a truncated one.
+
+use serde_json::{self, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::{Change, Company};
+
+pub const VERSION: u64 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Csv,
+}
+
+impl Format {
+    // Going by the file extension, with JSON for anything that isn't .csv.
+    pub fn from_path(path: &Path) -> Format {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(e) if e.eq_ignore_ascii_case("csv") => Format::Csv,
+            _ => Format::Json,
+        }
+    }
+}
+
+// One person in one department, as read from a file. `row` counts from 1: the line number in a
+// CSV file, the position in a JSON one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub row: usize,
+    pub name: String,
+    pub dept: String,
+}
+
+// A row that couldn't be read, and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invalid {
+    pub row: usize,
+    pub error: String,
+}
+
+pub fn to_json(company: &Company) -> Value {
+    // serde_json's Map keeps its keys sorted, so only the names need sorting for the file to
+    // come out the same every time.
+    let departments: Map<String, Value> = company
+        .sorted_by_dept()
+        .into_iter()
+        .map(|(dept, employees)| (dept.to_string(), Value::from(employees)))
+        .collect();
+    json!({ "version": VERSION, "departments": departments })
+}
+
+pub fn write_json<W: Write>(company: &Company, mut out: W) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut out, &to_json(company))?;
+    writeln!(out)
+}
+
+pub fn read_json<R: Read>(input: R) -> Result<Vec<Row>, String> {
+    let value: Value = serde_json::from_reader(input).map_err(|e| format!("not valid JSON: {}", e))?;
+    let version = match value.get("version") {
+        None => 0,
+        Some(v) => v.as_u64().ok_or_else(|| format!("version should be a number, got {}", v))?,
+    };
+    if version > VERSION {
+        return Err(format!("written by a newer version (schema {}, this reads up to {})", version, VERSION));
+    }
+    let departments = if version == 0 { Some(&value) } else { value.get("departments") };
+    let departments = departments
+        .and_then(|d| d.as_object())
+        .ok_or_else(|| String::from("expected an object of departments"))?;
+
+    let mut rows = Vec::new();
+    for (dept, employees) in departments {
+        let employees = employees.as_array().ok_or_else(|| format!("{} should be a list of names", dept))?;
+        for employee in employees {
+            let name = employee.as_str().ok_or_else(|| format!("{} has {} where a name should be", dept, employee))?;
+            rows.push(Row { row: rows.len() + 1, name: name.to_string(), dept: dept.clone() });
+        }
+    }
+    Ok(rows)
+}
+
+// Quotes a field if it needs it, doubling any quotes inside. Besides the characters CSV itself
+// cares about, that's a field starting with '#', which would otherwise read as a comment, and
+// one with spaces at either end, which reading trims from unquoted fields.
+pub(crate) fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) || field.starts_with('#') || field.trim() != field {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub fn write_csv<W: Write>(company: &Company, mut out: W) -> io::Result<()> {
+    writeln!(out, "# version {}", VERSION)?;
+    writeln!(out, "name,department")?;
+    for (dept, employees) in company.sorted_by_dept() {
+        for name in employees {
+            writeln!(out, "{},{}", csv_field(name), csv_field(dept))?;
+        }
+    }
+    Ok(())
+}
+
+// Splits one line of CSV into fields. Names never span lines, so quoted newlines aren't handled.
+// Unquoted fields are trimmed; quoted ones are kept exactly as they are inside the quotes.
+pub(crate) fn split_csv(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    // Whether the field started with a quote, whether or not it has been closed yet.
+    let mut was_quoted = false;
+    let finish = |field: &mut String, was_quoted: bool| {
+        let whole = field.split_off(0);
+        if was_quoted {
+            whole
+        } else {
+            whole.trim().to_string()
+        }
+    };
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if !was_quoted && field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+                was_quoted = true;
+            }
+            ',' if !quoted => {
+                fields.push(finish(&mut field, was_quoted));
+                was_quoted = false;
+            }
+            // Spaces between the closing quote and the comma.
+            c if was_quoted && !quoted && c.is_whitespace() => {}
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(String::from("unterminated quote"));
+    }
+    fields.push(finish(&mut field, was_quoted));
+    Ok(fields)
+}
+
+// Reads rows from CSV. Rows that can't be read are returned separately, with their line number
+// and what's wrong, so that an import can report them and carry on.
+pub fn read_csv<R: BufRead>(input: R) -> Result<(Vec<Row>, Vec<Invalid>), String> {
+    let mut rows = Vec::new();
+    let mut invalid = Vec::new();
+    let mut columns: Option<(usize, usize)> = None;
+    for (i, line) in input.lines().enumerate() {
+        let number = i + 1;
+        let line = line.map_err(|e| format!("line {}: {}", number, e))?;
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        // Comments only come before the header: after it, a line starting with '#' is a name. (A
+        // name that starts with '#' is quoted when written, but files written before that
+        // weren't.)
+        if let Some(comment) = trimmed.strip_prefix('#').filter(|_| columns.is_none()) {
+            if let Some(version) = comment.trim().strip_prefix("version") {
+                let version: u64 = version.trim().parse().map_err(|_| format!("line {}: unreadable version", number))?;
+                if version > VERSION {
+                    return Err(format!("written by a newer version (schema {}, this reads up to {})", version, VERSION));
+                }
+            }
+            continue;
+        }
+        let fields = match split_csv(&line) {
+            Ok(fields) => fields,
+            Err(e) => {
+                invalid.push(Invalid { row: number, error: e });
+                continue;
+            }
+        };
+        let (name, dept) = match columns {
+            // The header says which column is which, so they may come in either order.
+            None => {
+                let find = |column: &str| fields.iter().position(|f| f.trim().eq_ignore_ascii_case(column));
+                match (find("name"), find("department")) {
+                    (Some(name), Some(dept)) => columns = Some((name, dept)),
+                    _ => return Err(format!("line {}: expected a name,department header", number)),
+                }
+                continue;
+            }
+            Some(columns) => columns,
+        };
+        match (fields.get(name), fields.get(dept)) {
+            (Some(name), Some(dept)) if !name.trim().is_empty() && !dept.trim().is_empty() => rows.push(Row {
+                row: number,
+                name: name.clone(),
+                dept: dept.clone(),
+            }),
+            _ => invalid.push(Invalid { row: number, error: String::from("needs both a name and a department") }),
+        }
+    }
+    if columns.is_none() {
+        return Err(String::from("no name,department header"));
+    }
+    Ok((rows, invalid))
+}
+
+// Counts temporary files, so two saves in one process never pick the same name.
+static TEMPORARIES: AtomicUsize = AtomicUsize::new(0);
+
+// A new, empty file beside `path` that nothing else is using. The name has the process id and a
+// counter in it, and `create_new` refuses a name that's already taken (left behind by a crash, or
+// made by another program), so it tries the next one.
+fn create_temporary(path: &Path) -> io::Result<(PathBuf, File)> {
+    let file_name = path.file_name().ok_or_else(|| io::Error::other(format!("{} isn't a file name", path.display())))?;
+    loop {
+        let count = TEMPORARIES.fetch_add(1, Ordering::Relaxed);
+        let mut temporary = PathBuf::from(path);
+        temporary.set_file_name(format!(".{}.{}.{}.tmp", file_name.to_string_lossy(), process::id(), count));
+        match OpenOptions::new().write(true).create_new(true).open(&temporary) {
+            Ok(file) => return Ok((temporary, file)),
+            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// Writes to a temporary file beside `path` and renames it into place once everything is on disk.
+pub fn save(company: &Company, path: &Path) -> io::Result<()> {
+    let (temporary, mut file) = create_temporary(path)?;
+    let written = match Format::from_path(path) {
+        Format::Json => write_json(company, &mut file),
+        Format::Csv => write_csv(company, &mut file),
+    }
+    .and_then(|()| file.sync_all());
+    drop(file);
+    match written {
+        Ok(()) => fs::rename(&temporary, path),
+        Err(e) => {
+            let _ = fs::remove_file(&temporary);
+            Err(e)
+        }
+    }
+}
+
+fn read_rows(path: &Path) -> Result<(Vec<Row>, Vec<Invalid>), String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let read = match Format::from_path(path) {
+        Format::Json => read_json(file).map(|rows| (rows, Vec::new())),
+        Format::Csv => read_csv(BufReader::new(file)),
+    };
+    read.map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// Loads a company saved by `save`, exactly as it was. Unlike `import`, any problem with the file
+// is an error.
+pub fn load(path: &Path) -> Result<Company, String> {
+    let (rows, invalid) = read_rows(path)?;
+    if let Some(invalid) = invalid.first() {
+        return Err(format!("{}: line {}: {}", path.display(), invalid.row, invalid.error));
+    }
+    let mut company = Company::new();
+    for row in rows {
+        company
+            .add_to_dept(&row.dept, &row.name)
+            .map_err(|e| format!("{}: row {}: {}", path.display(), row.row, e))?;
+    }
+    Ok(company)
+}
+
+// A row naming somebody who is already in other departments, but not that one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub row: Row,
+    // Sorted.
+    pub existing: Vec<String>,
+}
+
+// What an import does. Only `changes` change anything; the other rows are left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    pub changes: Vec<Change>,
+    // Already in that department, in the company or earlier in the file.
+    pub duplicates: Vec<Row>,
+    pub conflicts: Vec<Conflict>,
+    pub invalid: Vec<Invalid>,
+}
+
+// Works out what importing the rows would do, without doing it. An import only adds people the
+// company doesn't have yet: someone turning up in another department might be a transfer or a
+// mistake, and guessing which isn't an import's job, so those rows are reported instead.
+pub fn plan_import(company: &Company, rows: Vec<Row>) -> ImportReport {
+    let mut report = ImportReport::default();
+    // Everyone as (name, department), and every department each name is in, lowercased since
+    // names and departments match whatever their case. One person can be in several departments.
+    let mut members: HashSet<(String, String)> = HashSet::new();
+    let mut departments_of: HashMap<String, Vec<String>> = HashMap::new();
+    for (dept, employees) in &company.departments {
+        for name in employees {
+            members.insert((name.to_lowercase(), dept.to_lowercase()));
+            departments_of.entry(name.to_lowercase()).or_default().push(dept.clone());
+        }
+    }
+    for row in rows {
+        let key = (row.name.to_lowercase(), row.dept.to_lowercase());
+        if members.contains(&key) {
+            report.duplicates.push(row);
+        } else if let Some(depts) = departments_of.get(&key.0) {
+            let mut existing = depts.clone();
+            existing.sort();
+            report.conflicts.push(Conflict { row, existing });
+        } else {
+            members.insert(key.clone());
+            departments_of.entry(key.0).or_default().push(row.dept.clone());
+            report.changes.push(Change::Add { name: row.name, dept: row.dept });
+        }
+    }
+    report
+}
+
+// Reads the file at `path` and plans importing it into `company`.
+pub fn read_import(company: &Company, path: &Path) -> Result<ImportReport, String> {
+    let (rows, invalid) = read_rows(path)?;
+    let mut report = plan_import(company, rows);
+    report.invalid = invalid;
+    Ok(report)
+}
+
+// Adds everything in the file at `path` to `company`, reporting what didn't fit.
+pub fn import(company: &mut Company, path: &Path) -> Result<ImportReport, String> {
+    let report = read_import(company, path)?;
+    for change in &report.changes {
+        // The plan only has people the company doesn't have, so these can't fail.
+        company.apply(change).map_err(|e| e.to_string())?;
+    }
+    Ok(report)
+}
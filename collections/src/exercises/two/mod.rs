@@ -2,38 +2,213 @@
 // word is moved to the end of the word with an added “ay”, so “first” becomes
 // “irst-fay”. Words that start with a vowel get “hay” added to the end instead
 // (“apple” becomes “apple-hay”). Remember about UTF-8 encoding!
+//
+// The first version moved only the first char ("three" became "hree-tay"), knew only lowercase
+// ASCII vowels, and rebuilt the sentence from split_whitespace, losing punctuation spacing. Now:
+// - the whole leading consonant cluster moves ("three" -> "ee-thray"), with "qu" kept together
+//   ("queen" -> "een-quay");
+// - 'y' is a consonant at the start of a word and a vowel after it ("yes" -> "es-yay",
+//   "rhythm" -> "ythm-rhay");
+// - "First" -> "Irst-fay" and "FIRST" -> "IRST-FAY";
+// - hyphenated words are translated part by part ("well-known" -> "ell-way-own-knay");
+// - anything that isn't a word, spaces and punctuation included, is copied over untouched.
+//
+// A word with no vowels at all has no cluster to speak of, so just its first consonant moves
+// ("psst" -> "sst-pay"), and a lone consonant stays where it is ("s" -> "s-ay").
+//
+// `convert_to_piglatin` can't always be undone: "hello" and "ello" both become "ello-hay". So
+// there's a second translation, `convert_to_piglatin_reversible`, that `decode_piglatin` undoes.
+// It only differs in two places: a word whose cluster is just "h" is treated like a vowel word
+// ("hello" -> "hello-hay", much as "hour" sounds), and so is a word with no vowels ("psst" ->
+// "psst-hay"). A "hay" suffix then always means the stem is the whole word.
+//
+// Case can't always survive: mixed-case words are moved letter for letter, and one like "fIrst"
+// comes out as "Irst-fay", which reads back as "First".
 
-pub fn convert_to_piglatin(input: &str) -> String {
-    use std::collections::HashSet;
-    // This is magic to create a hashset from a vector.
-    let vowels: HashSet<char> = ['a', 'e', 'i', 'o', 'u'].iter().cloned().collect();
-    // We'll collect all the modified words in this vector of strings.
-    let mut result: Vec<String> = Vec::new();
-    for word in input.split_whitespace() {
-        // Question for later: Can we improve this?
-        if let Some(c) = word.chars().next() {
-            let mut modified_word = String::new();
-            if vowels.contains(&c) {
-                append_hay(word, &mut modified_word);
-            } else {
-                obfuscate(word, c, &mut modified_word);
-            }
-            result.push(modified_word);
+// Vowels in either case, accented ones included. 'y' is dealt with separately.
+fn is_vowel(c: char) -> bool {
+    c.to_lowercase().all(|c| "aeiouáàâäåæéèêëíìîïóòôöøúùûü".contains(c))
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '’'
+}
+
+// Splits text into words and the stretches between them. Apostrophes and hyphens count as part of
+// a word only between two letters, so "don't" and "well-known" are single words but the quotes
+// in "'tis" and the dash in "pre- and post-war" are not.
+fn tokens(text: &str) -> Vec<(bool, &str)> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let in_word = |i: usize| {
+        let c = chars[i].1;
+        if c.is_alphabetic() {
+            return true;
+        }
+        (is_apostrophe(c) || c == '-')
+            && i > 0
+            && chars[i - 1].1.is_alphabetic()
+            && chars.get(i + 1).is_some_and(|&(_, next)| next.is_alphabetic())
+    };
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    for i in 0..chars.len() {
+        if i + 1 == chars.len() || in_word(i) != in_word(i + 1) {
+            let end = chars.get(i + 1).map_or(text.len(), |&(at, _)| at);
+            tokens.push((in_word(i), &text[chars[start].0..end]));
+            start = i + 1;
+        }
+    }
+    tokens
+}
+
+#[derive(PartialEq)]
+enum Case {
+    Lower,
+    // First letter upper case, the rest lower.
+    Title,
+    // Every letter upper case (and more than one of them, or "I" would count).
+    Upper,
+    Mixed,
+}
+
+fn case_of(word: &str) -> Case {
+    let letters: Vec<char> = word.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.iter().all(|c| !c.is_uppercase()) {
+        Case::Lower
+    } else if letters.len() > 1 && letters.iter().all(|c| !c.is_lowercase()) {
+        Case::Upper
+    } else if letters[0].is_uppercase() && letters[1..].iter().all(|c| !c.is_uppercase()) {
+        Case::Title
+    } else {
+        Case::Mixed
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn uncapitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// How many bytes of `word` are its leading consonant cluster.
+fn cluster_len(word: &str) -> usize {
+    let mut previous = None;
+    for (i, c) in word.char_indices() {
+        let consonant = if c == 'y' || c == 'Y' {
+            i == 0
+        } else if c == 'u' || c == 'U' {
+            // The u of "qu" goes with the q.
+            previous == Some('q')
         } else {
-            panic!("We should never reach here");
+            !is_vowel(c)
+        };
+        if !consonant {
+            return i;
         }
+        previous = c.to_lowercase().next();
     }
-    result.join(" ")
+    word.len()
 }
 
-fn append_hay(word: &str, result: &mut String) {
-    result.push_str(word);
-    result.push_str("-hay");
+// Translates a single word with no hyphens in it.
+fn encode_part(part: &str, reversible: bool) -> String {
+    let case = case_of(part);
+    let suffix = |cluster: &str| {
+        let suffix = format!("{}ay", cluster);
+        if case == Case::Upper {
+            suffix.to_uppercase()
+        } else {
+            suffix
+        }
+    };
+    let mut split = cluster_len(part);
+    if split == part.len() && !reversible {
+        split = part.chars().next().map_or(0, char::len_utf8);
+    }
+    let (cluster, stem) = part.split_at(split);
+    if cluster.is_empty() || (reversible && (stem.is_empty() || cluster.to_lowercase() == "h")) {
+        return format!("{}-{}", part, suffix("h"));
+    }
+    if stem.is_empty() {
+        return format!("{}-{}", cluster, suffix(""));
+    }
+    if case == Case::Title {
+        format!("{}-{}", capitalize(stem), suffix(&uncapitalize(cluster)))
+    } else {
+        format!("{}-{}", stem, suffix(cluster))
+    }
+}
+
+fn convert(input: &str, reversible: bool) -> String {
+    tokens(input)
+        .into_iter()
+        .map(|(is_word, text)| {
+            if is_word {
+                text.split('-').map(|part| encode_part(part, reversible)).collect::<Vec<_>>().join("-")
+            } else {
+                text.to_string()
+            }
+        })
+        .collect()
+}
+
+pub fn convert_to_piglatin(input: &str) -> String {
+    convert(input, false)
+}
+
+// The same, except for words starting with "h" and words without vowels, so that
+// `decode_piglatin` can always tell what it was made from.
+pub fn convert_to_piglatin_reversible(input: &str) -> String {
+    convert(input, true)
 }
 
-fn obfuscate(word: &str, c: char, result: &mut String) {
-    result.push_str(&word[1..]);
-    result.push('-');
-    result.push(c);
-    result.push_str("ay");
+// Undoes the reversible `encode_part`, given the stem and the suffix it was split into.
+fn decode_part(stem: &str, suffix: &str) -> Result<String, String> {
+    let cluster = match suffix.strip_suffix("ay").or_else(|| suffix.strip_suffix("AY")) {
+        Some(cluster) if !cluster.is_empty() => cluster,
+        _ => return Err(format!("'{}-{}' isn't Pig Latin", stem, suffix)),
+    };
+    if cluster.to_lowercase() == "h" {
+        return Ok(stem.to_string());
+    }
+    let word = format!("{}{}", cluster, stem);
+    let looks_title = case_of(stem) == Case::Title && case_of(cluster) == Case::Lower && suffix.ends_with("ay");
+    if looks_title {
+        Ok(capitalize(&format!("{}{}", cluster, uncapitalize(stem))))
+    } else {
+        Ok(word)
+    }
+}
+
+// Turns Pig Latin produced by `convert_to_piglatin_reversible` back into what it was made from. Every word
+// translates to two hyphen-separated parts, the stem and the moved cluster with "ay", so a
+// hyphenated word comes back as pairs of them.
+pub fn decode_piglatin(input: &str) -> Result<String, String> {
+    let mut decoded = String::with_capacity(input.len());
+    for (is_word, text) in tokens(input) {
+        if !is_word {
+            decoded.push_str(text);
+            continue;
+        }
+        let parts: Vec<&str> = text.split('-').collect();
+        if !parts.len().is_multiple_of(2) {
+            return Err(format!("'{}' isn't Pig Latin", text));
+        }
+        let words = parts
+            .chunks(2)
+            .map(|pair| decode_part(pair[0], pair[1]))
+            .collect::<Result<Vec<_>, _>>()?;
+        decoded.push_str(&words.join("-"));
+    }
+    Ok(decoded)
 }
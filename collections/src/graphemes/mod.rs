@@ -0,0 +1,109 @@
+// Working with strings the way they look rather than the way they're stored.
+//
+// The strings notes show that `&hello[..2]` panics when 2 isn't a char boundary, and that even a
+// char isn't what a reader would call a character: "नमस्ते" is six chars, and "שָׁלוֹם" seven,
+// with the marks that sit on the letters counted separately. What a reader sees as one
+// character is a grapheme cluster. The functions here slice, measure, shorten and reverse by
+// grapheme (through the unicode-segmentation crate), and none of them panics: out of range is
+// None.
+//
+// Display width is how many terminal columns a string takes up (through the unicode-width
+// crate): one for most letters, two for CJK and emoji, none for combining marks. It is worked
+// out grapheme by grapheme, so that the width of a string is the sum of the widths of its parts
+// and truncating never lands halfway through a grapheme.
+
+use std::iter;
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+pub const ELLIPSIS: &str = "…";
+
+pub fn graphemes(s: &str) -> impl Iterator<Item = &str> {
+    s.graphemes(true)
+}
+
+pub fn grapheme_count(s: &str) -> usize {
+    s.graphemes(true).count()
+}
+
+// Slices `s` between the `range.start`th and `range.end`th of the positions in `starts`.
+fn slice_at<I: Iterator<Item = usize>>(s: &str, starts: I, range: Range<usize>) -> Option<&str> {
+    if range.start > range.end {
+        return None;
+    }
+    let mut bounds = starts.chain(iter::once(s.len()));
+    let from = bounds.nth(range.start)?;
+    let to = if range.end == range.start { from } else { bounds.nth(range.end - range.start - 1)? };
+    Some(&s[from..to])
+}
+
+// The graphemes from `range.start` up to `range.end`, or None if the range runs past the end.
+pub fn slice_graphemes(s: &str, range: Range<usize>) -> Option<&str> {
+    slice_at(s, s.grapheme_indices(true).map(|(at, _)| at), range)
+}
+
+// The same by char, for when chars are what's wanted.
+pub fn slice_chars(s: &str, range: Range<usize>) -> Option<&str> {
+    slice_at(s, s.char_indices().map(|(at, _)| at), range)
+}
+
+// Columns a single grapheme takes up.
+pub fn grapheme_width(grapheme: &str) -> usize {
+    grapheme.width()
+}
+
+// Columns `s` takes up in a terminal.
+pub fn width(s: &str) -> usize {
+    s.graphemes(true).map(grapheme_width).sum()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+    // Any odd column left over goes on the right.
+    Center,
+}
+
+// Pads `s` with spaces to `columns` wide. Strings already that wide or wider are left alone.
+pub fn pad(s: &str, columns: usize, align: Align) -> String {
+    let spare = columns.saturating_sub(width(s));
+    let (left, right) = match align {
+        Align::Left => (0, spare),
+        Align::Right => (spare, 0),
+        Align::Center => (spare / 2, spare - spare / 2),
+    };
+    format!("{}{}{}", " ".repeat(left), s, " ".repeat(right))
+}
+
+// Shortens `s` to at most `columns` wide, ending it with an ellipsis if anything had to go. A
+// wide grapheme that won't fit in the space left is dropped whole, so the result can come out a
+// column short.
+pub fn truncate(s: &str, columns: usize) -> String {
+    if width(s) <= columns {
+        return s.to_string();
+    }
+    let room = match columns.checked_sub(width(ELLIPSIS)) {
+        Some(room) => room,
+        None => return String::new(),
+    };
+    let mut used = 0;
+    let mut truncated = String::new();
+    for grapheme in s.graphemes(true) {
+        used += grapheme_width(grapheme);
+        if used > room {
+            break;
+        }
+        truncated.push_str(grapheme);
+    }
+    truncated.push_str(ELLIPSIS);
+    truncated
+}
+
+// `s` back to front, keeping each grapheme's marks on it: reversing by char would move the
+// marks in "שָׁלוֹם" onto the wrong letters.
+pub fn reverse(s: &str) -> String {
+    s.graphemes(true).rev().collect()
+}
@@ -1,6 +1,17 @@
+#![allow(clippy::useless_vec)]
+
+#[macro_use]
+extern crate serde_json;
+extern crate unicode_normalization;
+extern crate unicode_segmentation;
+extern crate unicode_width;
+
 pub mod vectors;
 pub mod strings;
 pub mod hash_maps;
+pub mod graphemes;
+pub mod spreadsheet;
+pub mod words;
 
 pub mod exercises;
 
@@ -0,0 +1,381 @@
+// Cell addresses and formulas.
+//
+// A formula is what follows the '=' in a cell:
+//
+//     =A1 + B2 * 2
+//     =SUM(A1:A5) / COUNT(A1:A5)
+//     =-(C3 - 1.5)
+//
+// with the usual precedence (* and / before + and -), parentheses, numbers, "quoted text",
+// references like A1 or aa10 (columns are letters, rows count from 1) and ranges like A1:B3,
+// which only make sense as a function argument. Function names, like column letters, can be in
+// either case. Nesting, of brackets or of operations, stops at MAX_DEPTH.
+
+use std::fmt;
+
+use vectors::SpreadsheetCell;
+
+// A cell's position, counting from 0: A1 is column 0, row 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Addr {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Addr {
+    pub fn new(col: usize, row: usize) -> Addr {
+        Addr { row, col }
+    }
+
+    // "B3" is column 1, row 2. Columns run A..Z, AA..AZ, BA and so on.
+    pub fn parse(text: &str) -> Option<Addr> {
+        let digits = text.find(|c: char| c.is_ascii_digit())?;
+        let (letters, digits) = text.split_at(digits);
+        if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_alphabetic()) || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let mut col: usize = 0;
+        for c in letters.chars() {
+            col = col.checked_mul(26)?.checked_add(c.to_ascii_uppercase() as usize - 'A' as usize + 1)?;
+        }
+        let row: usize = digits.parse().ok()?;
+        if row == 0 {
+            return None;
+        }
+        Some(Addr { row: row - 1, col: col - 1 })
+    }
+
+    pub fn column_name(col: usize) -> String {
+        let mut name = Vec::new();
+        let mut n = col + 1;
+        while n > 0 {
+            n -= 1;
+            name.push((b'A' + (n % 26) as u8) as char);
+            n /= 26;
+        }
+        name.iter().rev().collect()
+    }
+}
+
+impl fmt::Display for Addr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", Addr::column_name(self.col), self.row + 1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Sum,
+    Min,
+    Max,
+    Average,
+    Count,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Function> {
+        match name.to_uppercase().as_str() {
+            "SUM" => Some(Function::Sum),
+            "MIN" => Some(Function::Min),
+            "MAX" => Some(Function::Max),
+            "AVERAGE" => Some(Function::Average),
+            "COUNT" => Some(Function::Count),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(SpreadsheetCell),
+    Ref(Addr),
+    // Both corners, top left first.
+    Range(Addr, Addr),
+    Neg(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+// Something a formula reads: one cell, or every cell in a range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Reference {
+    Cell(Addr),
+    // Both corners, top left first.
+    Range(Addr, Addr),
+}
+
+impl Reference {
+    pub fn contains(&self, addr: Addr) -> bool {
+        match *self {
+            Reference::Cell(cell) => cell == addr,
+            Reference::Range(from, to) => {
+                (from.row..=to.row).contains(&addr.row) && (from.col..=to.col).contains(&addr.col)
+            }
+        }
+    }
+}
+
+impl Expr {
+    // Everything the formula reads. Ranges stay ranges: spelling out A1:ZZ100000 would take
+    // millions of cells.
+    pub fn references(&self) -> Vec<Reference> {
+        let mut refs = Vec::new();
+        self.collect_references(&mut refs);
+        refs.sort();
+        refs.dedup();
+        refs
+    }
+
+    fn collect_references(&self, refs: &mut Vec<Reference>) {
+        match *self {
+            Expr::Literal(_) => {}
+            Expr::Ref(addr) => refs.push(Reference::Cell(addr)),
+            Expr::Range(from, to) => refs.push(Reference::Range(from, to)),
+            Expr::Neg(ref e) => e.collect_references(refs),
+            Expr::Binary(_, ref a, ref b) => {
+                a.collect_references(refs);
+                b.collect_references(refs);
+            }
+            Expr::Call(_, ref args) => args.iter().for_each(|a| a.collect_references(refs)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(SpreadsheetCell),
+    Text(String),
+    // A reference or a function name; which one depends on what follows.
+    Word(String),
+    Symbol(char),
+}
+
+fn tokenize(formula: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = formula.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut end = start;
+            while let Some(&(at, c)) = chars.peek() {
+                if !(c.is_ascii_digit() || c == '.') {
+                    break;
+                }
+                end = at + c.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Number(number(&formula[start..end]).ok_or_else(|| format!("'{}' isn't a number", &formula[start..end]))?));
+        } else if c.is_ascii_alphabetic() {
+            let mut end = start;
+            while let Some(&(at, c)) = chars.peek() {
+                if !c.is_ascii_alphanumeric() {
+                    break;
+                }
+                end = at + c.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Word(formula[start..end].to_string()));
+        } else if c == '"' {
+            chars.next();
+            let mut text = String::new();
+            loop {
+                match chars.next() {
+                    // "" inside quotes is a quote, as in CSV.
+                    Some((_, '"')) if chars.peek().map(|&(_, c)| c) == Some('"') => {
+                        chars.next();
+                        text.push('"');
+                    }
+                    Some((_, '"')) => break,
+                    Some((_, c)) => text.push(c),
+                    None => return Err(String::from("unterminated text")),
+                }
+            }
+            tokens.push(Token::Text(text));
+        } else if "+-*/():,".contains(c) {
+            chars.next();
+            tokens.push(Token::Symbol(c));
+        } else {
+            return Err(format!("unexpected '{}'", c));
+        }
+    }
+    Ok(tokens)
+}
+
+// Whole numbers that fit are Ints, anything else a Float.
+pub fn number(text: &str) -> Option<SpreadsheetCell> {
+    match text.parse::<i32>() {
+        Ok(n) => Some(SpreadsheetCell::Int(n)),
+        Err(_) => text.parse::<f64>().ok().filter(|n| n.is_finite()).map(SpreadsheetCell::Float),
+    }
+}
+
+// How deeply a formula may nest, counting both brackets and the operations it's built out of.
+// Parsing and evaluating both recurse, so without a limit "=((((..." a few hundred thousand
+// deep would overflow the stack.
+pub const MAX_DEPTH: usize = 256;
+
+// A recursive descent parser, one function per level of precedence. Each returns the expression
+// it read along with how deep it is, so that chains like 1+1+1+... count too: they come out of
+// a loop, not recursion, but still make a tree as deep as they are long.
+struct Parser {
+    tokens: Vec<Token>,
+    at: usize,
+    // How many brackets, calls and signs deep the parser is.
+    nesting: usize,
+}
+
+fn deeper(depth: usize) -> Result<usize, String> {
+    if depth >= MAX_DEPTH {
+        Err(format!("the formula nests more than {} deep", MAX_DEPTH))
+    } else {
+        Ok(depth + 1)
+    }
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.at)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.at).cloned();
+        self.at += 1;
+        token
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.at += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), String> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(format!("expected '{}'", symbol))
+        }
+    }
+
+    // sum := product (('+' | '-') product)*
+    fn sum(&mut self) -> Result<(Expr, usize), String> {
+        let (mut left, mut depth) = self.product()?;
+        loop {
+            let op = if self.eat('+') {
+                Op::Add
+            } else if self.eat('-') {
+                Op::Sub
+            } else {
+                return Ok((left, depth));
+            };
+            let (right, right_depth) = self.product()?;
+            depth = deeper(depth.max(right_depth))?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    // product := unary (('*' | '/') unary)*
+    fn product(&mut self) -> Result<(Expr, usize), String> {
+        let (mut left, mut depth) = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                Op::Mul
+            } else if self.eat('/') {
+                Op::Div
+            } else {
+                return Ok((left, depth));
+            };
+            let (right, right_depth) = self.unary()?;
+            depth = deeper(depth.max(right_depth))?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    // unary := ('-' | '+') unary | primary
+    //
+    // Every way of nesting comes back through here, so this is where the parser's own recursion
+    // is limited.
+    fn unary(&mut self) -> Result<(Expr, usize), String> {
+        self.nesting = deeper(self.nesting)?;
+        let parsed = if self.eat('-') {
+            self.unary().and_then(|(e, depth)| Ok((Expr::Neg(Box::new(e)), deeper(depth)?)))
+        } else if self.eat('+') {
+            self.unary()
+        } else {
+            self.primary()
+        };
+        self.nesting -= 1;
+        parsed
+    }
+
+    // primary := number | text | reference [':' reference] | name '(' [sum (',' sum)*] ')' | '(' sum ')'
+    fn primary(&mut self) -> Result<(Expr, usize), String> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok((Expr::Literal(n), 1)),
+            Some(Token::Text(text)) => Ok((Expr::Literal(SpreadsheetCell::Text(text)), 1)),
+            Some(Token::Symbol('(')) => {
+                let inner = self.sum()?;
+                self.expect(')')?;
+                Ok(inner)
+            }
+            Some(Token::Word(word)) => {
+                if self.eat('(') {
+                    let function = Function::from_name(&word).ok_or_else(|| format!("no function called {}", word))?;
+                    let mut args = Vec::new();
+                    let mut depth = 0;
+                    if !self.eat(')') {
+                        loop {
+                            let (arg, arg_depth) = self.sum()?;
+                            args.push(arg);
+                            depth = depth.max(arg_depth);
+                            if self.eat(')') {
+                                break;
+                            }
+                            if !self.eat(',') {
+                                return Err(String::from("expected ',' or ')'"));
+                            }
+                        }
+                    }
+                    return Ok((Expr::Call(function, args), deeper(depth)?));
+                }
+                let from = Addr::parse(&word).ok_or_else(|| format!("'{}' isn't a cell", word))?;
+                if !self.eat(':') {
+                    return Ok((Expr::Ref(from), 1));
+                }
+                let to = match self.next() {
+                    Some(Token::Word(ref word)) => Addr::parse(word),
+                    _ => None,
+                };
+                let to = to.ok_or_else(|| String::from("expected a cell after ':'"))?;
+                // Either pair of opposite corners will do.
+                let top_left = Addr { row: from.row.min(to.row), col: from.col.min(to.col) };
+                let bottom_right = Addr { row: from.row.max(to.row), col: from.col.max(to.col) };
+                Ok((Expr::Range(top_left, bottom_right), 1))
+            }
+            Some(Token::Symbol(c)) => Err(format!("unexpected '{}'", c)),
+            None => Err(String::from("the formula ends too soon")),
+        }
+    }
+}
+
+// Parses a formula, without its leading '='.
+pub fn parse(formula: &str) -> Result<Expr, String> {
+    let mut parser = Parser { tokens: tokenize(formula)?, at: 0, nesting: 0 };
+    let (expr, _) = parser.sum()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(_) => Err(String::from("unexpected text after the formula")),
+    }
+}
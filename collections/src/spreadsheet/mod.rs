@@ -0,0 +1,393 @@
+// A spreadsheet built out of the `SpreadsheetCell` enum from the vectors notes.
+//
+// Each cell holds what was typed into it: a number, some text, or a formula starting with '='
+// (see `formula` for what formulas can say). The sheet keeps every cell's evaluated value up to
+// date. Setting a cell recalculates that cell and whatever reads it, directly or through other
+// cells, and nothing else; the rest of the sheet keeps the values it had.
+//
+// To know what reads what, the sheet keeps, for every cell, the set of formula cells that refer
+// to it, and for every range used in a formula, the formula cells that use it: a cell is read by
+// whatever names it and whatever reads a range containing it. Ranges aren't spelled out cell by
+// cell, so SUM(A1:ZZ100000) costs no more than SUM(A1:B2), and summing one only looks at the
+// cells in it that hold something. Recalculation walks all that from the changed cell and
+// evaluates what it finds in an order where every cell comes after the cells it reads. Cells
+// that can't be put in such an order are part of a cycle (or read from one) and get the value
+// #CYCLE.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+use std::io::{self, BufRead, Write};
+
+use exercises::three::storage::{csv_field, split_csv};
+use vectors::SpreadsheetCell;
+
+pub mod formula;
+
+use self::formula::{Addr, Expr, Function, Op, Reference};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellError {
+    // The formula couldn't be read.
+    Parse,
+    // The cell is part of a cycle of references, or reads from one.
+    Cycle,
+    DivZero,
+    // Text, or a range, where a number was needed.
+    Value,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Empty,
+    Cell(SpreadsheetCell),
+    Error(CellError),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Empty => Ok(()),
+            Value::Cell(SpreadsheetCell::Int(n)) => write!(f, "{}", n),
+            Value::Cell(SpreadsheetCell::Float(n)) => write!(f, "{}", n),
+            Value::Cell(SpreadsheetCell::Text(ref text)) => write!(f, "{}", text),
+            Value::Error(CellError::Parse) => write!(f, "#PARSE!"),
+            Value::Error(CellError::Cycle) => write!(f, "#CYCLE"),
+            Value::Error(CellError::DivZero) => write!(f, "#DIV/0!"),
+            Value::Error(CellError::Value) => write!(f, "#VALUE!"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Content {
+    Constant(SpreadsheetCell),
+    // The parse error is kept for `Sheet::error`.
+    Formula(Result<Expr, String>),
+}
+
+#[derive(Debug, Clone)]
+struct Input {
+    // Exactly as typed, so it can be shown for editing.
+    text: String,
+    content: Content,
+}
+
+impl Input {
+    fn references(&self) -> Vec<Reference> {
+        match self.content {
+            Content::Formula(Ok(ref expr)) => expr.references(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Sheet {
+    inputs: HashMap<Addr, Input>,
+    // Only non-empty values are kept.
+    values: HashMap<Addr, Value>,
+    // Map from a cell to the formula cells that refer to it by name.
+    dependents: HashMap<Addr, HashSet<Addr>>,
+    // Map from a range to the formula cells that read it.
+    range_dependents: HashMap<(Addr, Addr), HashSet<Addr>>,
+}
+
+// Takes `formula` off the cells that read `key`, and drops the entry once nothing does.
+fn forget<K: Eq + Hash>(dependents: &mut HashMap<K, HashSet<Addr>>, key: K, formula: Addr) {
+    if let Some(readers) = dependents.get_mut(&key) {
+        readers.remove(&formula);
+        if readers.is_empty() {
+            dependents.remove(&key);
+        }
+    }
+}
+
+fn as_f64(n: &SpreadsheetCell) -> f64 {
+    match *n {
+        SpreadsheetCell::Int(n) => n as f64,
+        SpreadsheetCell::Float(n) => n,
+        SpreadsheetCell::Text(_) => f64::NAN,
+    }
+}
+
+// Ints stay Ints for as long as the answer is a whole number that fits; past that the answer is
+// a Float.
+fn arithmetic(op: Op, a: &SpreadsheetCell, b: &SpreadsheetCell) -> Result<SpreadsheetCell, CellError> {
+    if let (&SpreadsheetCell::Int(a), &SpreadsheetCell::Int(b)) = (a, b) {
+        let exact = match op {
+            Op::Add => a.checked_add(b),
+            Op::Sub => a.checked_sub(b),
+            Op::Mul => a.checked_mul(b),
+            Op::Div if b == 0 => return Err(CellError::DivZero),
+            Op::Div => a.checked_rem(b).filter(|&r| r == 0).and_then(|_| a.checked_div(b)),
+        };
+        if let Some(n) = exact {
+            return Ok(SpreadsheetCell::Int(n));
+        }
+    }
+    let (a, b) = (as_f64(a), as_f64(b));
+    let n = match op {
+        Op::Add => a + b,
+        Op::Sub => a - b,
+        Op::Mul => a * b,
+        Op::Div if b == 0.0 => return Err(CellError::DivZero),
+        Op::Div => a / b,
+    };
+    if n.is_finite() {
+        Ok(SpreadsheetCell::Float(n))
+    } else {
+        Err(CellError::Value)
+    }
+}
+
+impl Sheet {
+    pub fn new() -> Sheet {
+        Sheet::default()
+    }
+
+    // Puts `text` in the cell, as if typed into it; an empty string clears it. Returns every cell
+    // that was recalculated as a result, in order.
+    pub fn set(&mut self, addr: Addr, text: &str) -> Vec<Addr> {
+        if let Some(old) = self.inputs.remove(&addr) {
+            for reference in old.references() {
+                match reference {
+                    Reference::Cell(cell) => forget(&mut self.dependents, cell, addr),
+                    Reference::Range(from, to) => forget(&mut self.range_dependents, (from, to), addr),
+                }
+            }
+        }
+        let trimmed = text.trim();
+        if !trimmed.is_empty() {
+            let content = if let Some(source) = trimmed.strip_prefix('=') {
+                Content::Formula(formula::parse(source))
+            } else {
+                Content::Constant(formula::number(trimmed).unwrap_or_else(|| SpreadsheetCell::Text(text.to_string())))
+            };
+            let input = Input { text: text.to_string(), content };
+            for reference in input.references() {
+                match reference {
+                    Reference::Cell(cell) => self.dependents.entry(cell).or_default().insert(addr),
+                    Reference::Range(from, to) => self.range_dependents.entry((from, to)).or_default().insert(addr),
+                };
+            }
+            self.inputs.insert(addr, input);
+        }
+        self.recalculate(addr)
+    }
+
+    // What was typed into the cell.
+    pub fn input(&self, addr: Addr) -> Option<&str> {
+        self.inputs.get(&addr).map(|input| input.text.as_str())
+    }
+
+    pub fn value(&self, addr: Addr) -> Value {
+        self.values.get(&addr).cloned().unwrap_or(Value::Empty)
+    }
+
+    // Why the cell's formula couldn't be read, if it couldn't.
+    pub fn error(&self, addr: Addr) -> Option<&str> {
+        match self.inputs.get(&addr).map(|input| &input.content) {
+            Some(Content::Formula(Err(error))) => Some(error),
+            _ => None,
+        }
+    }
+
+    // The formula cells that read `addr`, by name or through a range.
+    fn dependents_of(&self, addr: Addr) -> HashSet<Addr> {
+        let mut dependents: HashSet<Addr> = self.dependents.get(&addr).into_iter().flatten().copied().collect();
+        for (&(from, to), readers) in &self.range_dependents {
+            if Reference::Range(from, to).contains(addr) {
+                dependents.extend(readers);
+            }
+        }
+        dependents
+    }
+
+    fn recalculate(&mut self, changed: Addr) -> Vec<Addr> {
+        // Everything that reads `changed`, however indirectly.
+        let mut affected: HashSet<Addr> = HashSet::new();
+        let mut stack = vec![changed];
+        while let Some(addr) = stack.pop() {
+            if affected.insert(addr) {
+                stack.extend(self.dependents_of(addr));
+            }
+        }
+
+        // Kahn's algorithm: a cell is ready once every affected cell it reads has been done.
+        let mut waiting_on: HashMap<Addr, usize> = affected
+            .iter()
+            .map(|&addr| {
+                let references = self.inputs.get(&addr).map(Input::references).unwrap_or_default();
+                let reads = |cell: &&Addr| references.iter().any(|r| r.contains(**cell));
+                (addr, affected.iter().filter(reads).count())
+            })
+            .collect();
+        // A BTreeSet so that cells that are ready together go in address order.
+        let mut ready: BTreeSet<Addr> = waiting_on.iter().filter(|&(_, &n)| n == 0).map(|(&addr, _)| addr).collect();
+        let mut order = Vec::new();
+        while let Some(addr) = ready.pop_first() {
+            waiting_on.remove(&addr);
+            let value = self.evaluate(addr);
+            self.store(addr, value);
+            order.push(addr);
+            for dependent in self.dependents_of(addr) {
+                if let Some(n) = waiting_on.get_mut(&dependent) {
+                    *n -= 1;
+                    if *n == 0 {
+                        ready.insert(dependent);
+                    }
+                }
+            }
+        }
+        let mut cycle: Vec<Addr> = waiting_on.into_keys().collect();
+        cycle.sort();
+        for &addr in &cycle {
+            self.store(addr, Value::Error(CellError::Cycle));
+        }
+        order.extend(cycle);
+        order
+    }
+
+    fn store(&mut self, addr: Addr, value: Value) {
+        if value == Value::Empty {
+            self.values.remove(&addr);
+        } else {
+            self.values.insert(addr, value);
+        }
+    }
+
+    fn evaluate(&self, addr: Addr) -> Value {
+        match self.inputs.get(&addr).map(|input| &input.content) {
+            None => Value::Empty,
+            Some(Content::Constant(cell)) => Value::Cell(cell.clone()),
+            Some(Content::Formula(Err(_))) => Value::Error(CellError::Parse),
+            Some(Content::Formula(Ok(expr))) => match self.eval(expr) {
+                Ok(cell) => Value::Cell(cell),
+                Err(error) => Value::Error(error),
+            },
+        }
+    }
+
+    fn eval(&self, expr: &Expr) -> Result<SpreadsheetCell, CellError> {
+        match *expr {
+            Expr::Literal(ref cell) => Ok(cell.clone()),
+            // An empty cell reads as 0.
+            Expr::Ref(addr) => match self.value(addr) {
+                Value::Empty => Ok(SpreadsheetCell::Int(0)),
+                Value::Cell(cell) => Ok(cell),
+                Value::Error(error) => Err(error),
+            },
+            Expr::Range(..) => Err(CellError::Value),
+            Expr::Neg(ref e) => {
+                let n = self.number(e)?;
+                arithmetic(Op::Sub, &SpreadsheetCell::Int(0), &n)
+            }
+            Expr::Binary(op, ref a, ref b) => {
+                let a = self.number(a)?;
+                let b = self.number(b)?;
+                arithmetic(op, &a, &b)
+            }
+            Expr::Call(function, ref args) => self.call(function, args),
+        }
+    }
+
+    fn number(&self, expr: &Expr) -> Result<SpreadsheetCell, CellError> {
+        match self.eval(expr)? {
+            SpreadsheetCell::Text(_) => Err(CellError::Value),
+            n => Ok(n),
+        }
+    }
+
+    // The numbers a function is given. Cells, on their own or in a range, count only if they
+    // hold numbers, so a column with a heading can still be summed; anything else has to be a
+    // number.
+    fn arguments(&self, args: &[Expr]) -> Result<Vec<SpreadsheetCell>, CellError> {
+        let mut numbers = Vec::new();
+        for arg in args {
+            let cells: Vec<Addr> = match *arg {
+                Expr::Ref(addr) => vec![addr],
+                Expr::Range(from, to) => self.filled(Reference::Range(from, to)),
+                ref arg => {
+                    numbers.push(self.number(arg)?);
+                    continue;
+                }
+            };
+            for addr in cells {
+                match self.value(addr) {
+                    Value::Cell(SpreadsheetCell::Text(_)) | Value::Empty => {}
+                    Value::Cell(n) => numbers.push(n),
+                    Value::Error(error) => return Err(error),
+                }
+            }
+        }
+        Ok(numbers)
+    }
+
+    // The cells in `range` that hold something, row by row. Empty cells count for nothing, so
+    // there's no need to visit them.
+    fn filled(&self, range: Reference) -> Vec<Addr> {
+        let mut cells: Vec<Addr> = self.values.keys().copied().filter(|&addr| range.contains(addr)).collect();
+        cells.sort();
+        cells
+    }
+
+    fn call(&self, function: Function, args: &[Expr]) -> Result<SpreadsheetCell, CellError> {
+        let numbers = self.arguments(args)?;
+        let sum = || numbers.iter().try_fold(SpreadsheetCell::Int(0), |sum, n| arithmetic(Op::Add, &sum, n));
+        let pick = |better: fn(f64, f64) -> bool| {
+            let mut best = numbers.first().cloned().unwrap_or(SpreadsheetCell::Int(0));
+            for n in &numbers {
+                if better(as_f64(n), as_f64(&best)) {
+                    best = n.clone();
+                }
+            }
+            best
+        };
+        match function {
+            Function::Sum => sum(),
+            Function::Min => Ok(pick(|a, b| a < b)),
+            Function::Max => Ok(pick(|a, b| a > b)),
+            Function::Average if numbers.is_empty() => Err(CellError::DivZero),
+            Function::Average => arithmetic(Op::Div, &sum()?, &SpreadsheetCell::Int(numbers.len() as i32)),
+            Function::Count => Ok(SpreadsheetCell::Int(numbers.len() as i32)),
+        }
+    }
+
+    // The bottom right corner of the cells in use, or None for an empty sheet.
+    pub fn extent(&self) -> Option<Addr> {
+        let row = self.inputs.keys().map(|a| a.row).max()?;
+        let col = self.inputs.keys().map(|a| a.col).max()?;
+        Some(Addr { row, col })
+    }
+
+    // Writes every cell's value, not its formula, as CSV: one line per row from row 1 to the
+    // last one in use, with empty fields for empty cells.
+    pub fn write_csv<W: Write>(&self, mut out: W) -> io::Result<()> {
+        let extent = match self.extent() {
+            Some(extent) => extent,
+            None => return Ok(()),
+        };
+        for row in 0..=extent.row {
+            let fields: Vec<String> = (0..=extent.col).map(|col| csv_field(&self.value(Addr { row, col }).to_string())).collect();
+            writeln!(out, "{}", fields.join(","))?;
+        }
+        Ok(())
+    }
+
+    // Reads a sheet from CSV, each field as if typed into its cell, so a field starting with '='
+    // is a formula.
+    pub fn read_csv<R: BufRead>(input: R) -> Result<Sheet, String> {
+        let mut sheet = Sheet::new();
+        for (row, line) in input.lines().enumerate() {
+            let line = line.map_err(|e| format!("line {}: {}", row + 1, e))?;
+            let fields = split_csv(&line).map_err(|e| format!("line {}: {}", row + 1, e))?;
+            for (col, field) in fields.iter().enumerate() {
+                if !field.trim().is_empty() {
+                    sheet.set(Addr { row, col }, field);
+                }
+            }
+        }
+        Ok(sheet)
+    }
+}
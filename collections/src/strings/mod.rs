@@ -108,6 +108,8 @@ pub fn foo() {
     //
     // Apart from string slicing, rust also provides a way to iterator over the bytes or the
     // characters. Iteration over grapheme clusters is not provided through the standard library.
+    // The graphemes module does it with the unicode-segmentation crate. (Newer Unicode rules keep
+    // a conjunct like स्ते together, so it finds 3 clusters in "नमस्ते", not the 4 above.)
     //
     let hello = "नमस्ते";
     for b in hello.bytes() {
@@ -28,11 +28,11 @@ fn it_works() {
         let mut comp: Company = Company {
             departments: HashMap::new(),
         };
-        comp.add_to_dept("Marketing", "Scott");
-        comp.add_to_dept("Marketing", "Kaushik");
-        comp.add_to_dept("Sales", "Brian");
-        comp.add_to_dept("Engineering", "Abhishek");
-        comp.add_to_dept("Coffee Makers", "Ajeet");
+        comp.add_to_dept("Marketing", "Scott").unwrap();
+        comp.add_to_dept("Marketing", "Kaushik").unwrap();
+        comp.add_to_dept("Sales", "Brian").unwrap();
+        comp.add_to_dept("Engineering", "Abhishek").unwrap();
+        comp.add_to_dept("Coffee Makers", "Ajeet").unwrap();
         println!(
             "All in marketing: {:?}",
             comp.get_all_from_dept("Marketing")
@@ -45,3 +45,813 @@ fn it_works() {
         println!("All in company: {:?}", comp.get_all_sorted());
     }
 }
+
+mod statistics {
+    use exercises::one::{histogram, ls, median, modes, quantile, Bin, P2Quantile, Running, StatsError, Summary};
+
+    #[test]
+    fn summary_fixes_the_old_surprises() {
+        assert_eq!(
+            Ok(Summary {
+                count: 4,
+                mean: 2.5,
+                median: 2.5,
+                modes: vec![1, 2, 3, 4],
+                variance: 1.25,
+                std_dev: 1.25f64.sqrt(),
+                min: 1,
+                max: 4,
+                range: 3.0,
+            }),
+            ls(vec![4, 3, 2, 1])
+        );
+        // Would have overflowed the i32 sum.
+        let summary = ls(vec![i32::MAX, i32::MAX]).unwrap();
+        assert_eq!(i32::MAX as f64, summary.mean);
+        assert_eq!(vec![i32::MAX], summary.modes);
+        // Ties are all reported, in order, whatever order they came in.
+        assert_eq!(Ok(vec![3, 4]), modes(&[4, 3, 5, 3, 2, 4, 1, 0]));
+        assert_eq!(Err(StatsError::Empty), ls(Vec::<i32>::new()));
+    }
+
+    #[test]
+    fn generic_and_streaming() {
+        let mut running = Running::new();
+        assert_eq!(Err(StatsError::Empty), running.mean());
+        for &x in &[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+            running.push(x).unwrap();
+        }
+        assert_eq!(Ok(5.0), running.mean());
+        assert_eq!(Ok(2.0), running.std_dev());
+        assert_eq!(Ok(32.0 / 7.0), running.sample_variance());
+        assert_eq!(Ok(7.0), running.range());
+        assert_eq!(Err(StatsError::NotANumber), running.push(f64::NAN));
+
+        let mut small = Running::new();
+        small.push(200u8).unwrap();
+        small.push(10u8).unwrap();
+        // 190 would still fit in a u8, but -190 wouldn't, so ranges are f64.
+        assert_eq!(Ok(190.0), small.range());
+        assert_eq!(Err(StatsError::TooFew { needed: 2, got: 1 }), {
+            let mut one = Running::new();
+            one.push(1i64).unwrap();
+            one.sample_variance()
+        });
+    }
+
+    #[test]
+    fn quantiles() {
+        assert_eq!(Ok(2.5), median(&[1u32, 2, 3, 4]));
+        assert_eq!(Ok(1.0), quantile(&[3, 1, 2], 0.0));
+        assert_eq!(Ok(1.5), quantile(&[3, 1, 2], 0.25));
+        assert_eq!(Err(StatsError::InvalidQuantile(1.5)), quantile(&[1], 1.5));
+        assert_eq!(Err(StatsError::NotANumber), median(&[1.0, f32::NAN]));
+
+        // The estimate is exact while it has no more than five values...
+        let mut p90 = P2Quantile::new(0.9).unwrap();
+        for x in &[5, 1, 3] {
+            p90.push(*x).unwrap();
+        }
+        assert_eq!(quantile(&[5, 1, 3], 0.9), p90.estimate());
+        // ...and close on a lot of them, here 1 to 10000 in a scrambled order.
+        let mut p90 = P2Quantile::new(0.9).unwrap();
+        let mut median = P2Quantile::new(0.5).unwrap();
+        for i in 0..10_000u32 {
+            let x = (i * 7919) % 10_000 + 1;
+            p90.push(x).unwrap();
+            median.push(x).unwrap();
+        }
+        assert!((p90.estimate().unwrap() - 9000.0).abs() < 100.0, "{:?}", p90.estimate());
+        assert!((median.estimate().unwrap() - 5000.0).abs() < 100.0, "{:?}", median.estimate());
+    }
+
+    #[test]
+    fn histograms() {
+        assert_eq!(
+            Ok(vec![
+                Bin { low: 0.0, high: 2.5, count: 3 },
+                Bin { low: 2.5, high: 5.0, count: 2 },
+                Bin { low: 5.0, high: 7.5, count: 0 },
+                Bin { low: 7.5, high: 10.0, count: 1 },
+            ]),
+            // The largest value goes in the last bin, not one past it.
+            histogram(&[0, 1, 2, 3, 4, 10], 4)
+        );
+        assert_eq!(Ok(vec![Bin { low: 3.0, high: 3.0, count: 2 }]), histogram(&[3, 3], 5));
+        assert_eq!(Err(StatsError::Empty), histogram::<i32>(&[], 5));
+    }
+}
+
+mod stats {
+    use std::io::Cursor;
+
+    use exercises::one::report::{print_json, print_text, read_numbers, Config, ReadError, Report};
+
+    fn config(args: &[&str]) -> Result<Config, String> {
+        Config::new(args.iter().map(|a| a.to_string()))
+    }
+
+    fn numbers(input: &str, args: &[&str]) -> (Vec<f64>, usize) {
+        let mut numbers = Vec::new();
+        let skipped = read_numbers(Cursor::new(input), &config(args).unwrap(), &mut numbers).unwrap();
+        (numbers, skipped)
+    }
+
+    #[test]
+    fn arguments() {
+        let parsed = config(&["-c", "price", "--delimiter", "tab", "--bins", "3", "--json", "a.csv", "-"]).unwrap();
+        assert_eq!(
+            Config {
+                column: Some(String::from("price")),
+                delimiter: '\t',
+                bins: 3,
+                json: true,
+                files: vec![String::from("a.csv"), String::from("-")],
+            },
+            parsed
+        );
+        assert_eq!(Config { column: None, delimiter: ',', bins: 10, json: false, files: vec![] }, config(&[]).unwrap());
+        assert_eq!(Err(String::from("--bins needs a number above 0")), config(&["--bins", "0"]));
+        assert_eq!(Err(String::from("--delimiter must be a single character")), config(&["-d", ";;"]));
+        assert_eq!(Err(String::from("--column needs a value")), config(&["--column"]));
+        assert_eq!(Err(String::from("Unknown argument --verbose")), config(&["--verbose"]));
+    }
+
+    #[test]
+    fn reads_one_number_a_line() {
+        // Words, NaN and the infinities are all skipped, and blank lines aren't counted at all.
+        assert_eq!((vec![1.0, 2.5, -3.0], 5), numbers("1\n\n 2.5 \nabc\n-3\nNaN\ninf\n-inf\ninfinity\n", &[]));
+    }
+
+    #[test]
+    fn picks_a_column() {
+        let csv = "name,price\n\"a\",1\nb,two\nc,3\n";
+        assert_eq!((vec![1.0, 3.0], 1), numbers(csv, &["--column", "price"]));
+        // By position the header is just a line that isn't a number.
+        assert_eq!((vec![1.0, 3.0], 2), numbers(csv, &["--column", "2"]));
+        assert_eq!((vec![4.0], 0), numbers("x;y\n3;4\n", &["-c", "y", "-d", ";"]));
+        // The header is the first line with anything on it.
+        assert_eq!((vec![1.0], 0), numbers("\n  \nname,price\na,1\n", &["-c", "price"]));
+
+        let mut numbers = Vec::new();
+        match read_numbers(Cursor::new("name,cost\na,1\n"), &config(&["-c", "price"]).unwrap(), &mut numbers) {
+            Err(ReadError::NoColumn(name)) => assert_eq!("price", name),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn prints_a_report() {
+        let report = Report::new(&[1.0, 2.0, 2.0, 5.0], 2).unwrap();
+        let mut text = Vec::new();
+        print_text(&report, &mut text).unwrap();
+        assert_eq!(
+            "count   4\nmean    2.5\nmedian  2\nmode    2\nstddev  1.5\nmin     1\nmax     5\np50     2\np90     4.1\np99     4.91\n\n\
+             1 .. 3 | ################################################## 3\n3 .. 5 | ################ 1\n",
+            String::from_utf8(text).unwrap()
+        );
+
+        let mut json = Vec::new();
+        print_json(&report, &mut json).unwrap();
+        let value: ::serde_json::Value = ::serde_json::from_slice(&json).unwrap();
+        assert_eq!(4, value["count"]);
+        assert_eq!(2.5, value["mean"]);
+        assert_eq!(json!([2.0]), value["modes"]);
+        assert!((value["p90"].as_f64().unwrap() - 4.1).abs() < 1e-9, "{}", value["p90"]);
+        assert_eq!(json!([{"low": 1.0, "high": 3.0, "count": 3}, {"low": 3.0, "high": 5.0, "count": 1}]), value["histogram"]);
+
+        assert!(Report::new(&[], 2).is_err());
+    }
+}
+
+mod piglatin {
+    use exercises::two::{convert_to_piglatin, convert_to_piglatin_reversible, decode_piglatin};
+
+    #[test]
+    fn translates() {
+        let cases = [
+            ("first", "irst-fay"),
+            ("apple", "apple-hay"),
+            ("three", "ee-thray"),
+            ("queen square", "een-quay are-squay"),
+            ("yes rhythm my", "es-yay ythm-rhay y-may"),
+            ("First FIRST", "Irst-fay IRST-FAY"),
+            ("well-known", "ell-way-own-knay"),
+            ("Don't  stop,\tÉcole!", "On't-day  op-stay,\tÉcole-hay!"),
+            ("pre- and post-war", "e-pray- and-hay ost-pay-ar-way"),
+        ];
+        for &(english, piglatin) in &cases {
+            assert_eq!(piglatin, convert_to_piglatin(english));
+            assert_eq!(piglatin, convert_to_piglatin_reversible(english));
+            assert_eq!(Ok(String::from(english)), decode_piglatin(piglatin));
+        }
+        assert!(decode_piglatin("not pig latin").is_err());
+    }
+
+    #[test]
+    fn plain_rules_for_h_and_no_vowels() {
+        assert_eq!("ello-hay our-hay sst-pay Sst-pay s-ay", convert_to_piglatin("hello hour psst Psst s"));
+        assert_eq!("ello-hay", convert_to_piglatin("ello"));
+        // The reversible translation keeps those words whole, so they can be told apart again.
+        let reversible = convert_to_piglatin_reversible("hello hour psst Psst s ello");
+        assert_eq!("hello-hay hour-hay psst-hay Psst-hay s-hay ello-hay", reversible);
+        assert_eq!(Ok(String::from("hello hour psst Psst s ello")), decode_piglatin(&reversible));
+    }
+
+    // Random sentences, from a tiny generator of our own so the test needs no extra crates and
+    // fails the same way every time.
+    #[test]
+    fn round_trips() {
+        let mut state: u64 = 2024;
+        let mut next = |n: u64| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 33) % n
+        };
+        let letters: Vec<char> = "abcdefghijklmnopqrstuvwxyzéü".chars().collect();
+        let gaps = [" ", "  ", "\t", ", ", "! ", "\n", " - ", "' ", " \"", "-"];
+        for _ in 0..2000 {
+            let mut sentence = String::new();
+            for w in 0..next(6) + 1 {
+                if w > 0 {
+                    sentence.push_str(gaps[next(gaps.len() as u64) as usize]);
+                }
+                let mut word: String = (0..next(7) + 1).map(|_| letters[next(letters.len() as u64) as usize]).collect();
+                match next(4) {
+                    0 => word = word.to_uppercase(),
+                    1 => {
+                        let mut chars = word.chars();
+                        word = chars.next().unwrap().to_uppercase().chain(chars).collect();
+                    }
+                    _ => {}
+                }
+                // Now and then an apostrophe or hyphen inside the word.
+                if word.chars().count() > 2 && next(5) == 0 {
+                    let at = word.char_indices().nth(1).unwrap().0;
+                    word.insert(at, if next(2) == 0 { '\'' } else { '-' });
+                }
+                sentence.push_str(&word);
+            }
+            let piglatin = convert_to_piglatin_reversible(&sentence);
+            assert_eq!(Ok(sentence.clone()), decode_piglatin(&piglatin), "via {:?}", piglatin);
+        }
+    }
+}
+
+mod company {
+    use std::io::Cursor;
+
+    use exercises::three::{repl, sort_key, Change, Command, Company, CompanyError, History};
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(
+            Ok(Command::Change(Change::Add { name: String::from("Mary Jo"), dept: String::from("Research and Development") })),
+            Command::parse("add Mary Jo TO Research and Development\n")
+        );
+        assert_eq!(
+            Ok(Command::Change(Change::Transfer {
+                name: String::from("Sally"),
+                from: String::from("Engineering"),
+                to: String::from("Sales"),
+            })),
+            Command::parse("Move Sally from Engineering to Sales")
+        );
+        assert_eq!(
+            Ok(Command::Change(Change::MergeDepts { from: String::from("Marketing"), into: String::from("Sales") })),
+            Command::parse("merge Marketing INTO Sales")
+        );
+        assert_eq!(Ok(Command::Undo), Command::parse("undo"));
+        assert!(Command::parse("Move Sally to Sales").is_err());
+        assert_eq!(Ok(Command::List(String::from("Sales"))), Command::parse("List Sales"));
+        assert_eq!(Ok(Command::ListAll), Command::parse("LIST ALL"));
+        assert_eq!(Ok(Command::ListAll), Command::parse("list"));
+        assert_eq!(Ok(Command::Help), Command::parse(" help "));
+        assert!(Command::parse("Add Sally").unwrap_err().starts_with("Expected 'Add <name> to <department>'"));
+        assert!(Command::parse("Add to Sales").is_err());
+        assert!(Command::parse("Fire Sally").unwrap_err().contains("Type 'help'"));
+    }
+
+    #[test]
+    fn repl_session() {
+        let script = "Add Sally to Engineering\nAdd Amir to Sales\nAdd Abhishek to Engineering\n\
+                      List Engineering\nList Marketing\nList all\nsing\nquit\nList all\n";
+        let mut output = Vec::new();
+        let mut company = Company::new();
+        repl(&mut company, Cursor::new(script), &mut output).unwrap();
+        assert_eq!(
+            "> Added Sally to Engineering.\n\
+             > Added Amir to Sales.\n\
+             > Added Abhishek to Engineering.\n\
+             > Engineering:\n  Abhishek\n  Sally\n\
+             > There's no department called Marketing.\n\
+             > Engineering:\n  Abhishek\n  Sally\nSales:\n  Amir\n\
+             > Don't know how to 'sing'. Type 'help' to see the commands.\n\
+             > ",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    fn change(text: &str) -> Change {
+        match Command::parse(text) {
+            Ok(Command::Change(change)) => change,
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn lifecycle() {
+        let mut company = Company::new();
+        company.add_to_dept("Engineering", "Zoe").unwrap();
+        company.add_to_dept("engineering", "Émile").unwrap();
+        company.add_to_dept("Engineering", "adam").unwrap();
+        assert_eq!(Some(vec!["adam", "Émile", "Zoe"]), company.sorted_dept("ENGINEERING"));
+        // Accents sort with their letters in any script, not just the common Latin ones.
+        let mut names = vec!["Zoe", "Ștefan", "Ōsaka", "Łukasz", "Ωmega", "Άννα", "ūna", "Anna", "Βήτα"];
+        names.sort_by(|a, b| sort_key(a).cmp(&sort_key(b)));
+        assert_eq!(vec!["Anna", "Łukasz", "Ōsaka", "Ștefan", "ūna", "Zoe", "Άννα", "Βήτα", "Ωmega"], names);
+        assert_eq!(
+            Err(CompanyError::AlreadyInDept { name: String::from("Zoe"), dept: String::from("Engineering") }),
+            company.add_to_dept("Engineering", "ZOE")
+        );
+
+        company.apply(&change("Move zoe from Engineering to Sales")).unwrap();
+        assert_eq!(Err(CompanyError::SameDept(String::from("Sales"))), company.apply(&change("Move Zoe from Sales to sales")));
+        company.apply(&change("Rename Sales to Revenue")).unwrap();
+        assert_eq!(
+            Err(CompanyError::DeptExists(String::from("Revenue"))),
+            company.apply(&change("Rename Engineering to revenue"))
+        );
+        company.apply(&change("Merge Revenue into Engineering")).unwrap();
+        assert_eq!(vec![("Engineering", vec!["adam", "Émile", "Zoe"])], company.sorted_by_dept());
+
+        company.apply(&change("Remove Adam from Engineering")).unwrap();
+        assert_eq!(
+            Err(CompanyError::NotInDept { name: String::from("Adam"), dept: String::from("Engineering") }),
+            company.apply(&change("Remove Adam from Engineering"))
+        );
+        // The last person out closes the department.
+        company.apply(&change("Remove Émile from Engineering")).unwrap();
+        company.apply(&change("Remove Zoe from Engineering")).unwrap();
+        assert_eq!(None, company.sorted_dept("Engineering"));
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut company = Company::new();
+        let mut history = History::new();
+        for line in &["Add Sally to Engineering", "Add Amir to Sales", "Add Kim to Sales", "Add Kim to Engineering"] {
+            history.apply(&mut company, change(line)).unwrap();
+        }
+        let before = format!("{:?}", company.sorted_by_dept());
+
+        // Kim is in both, so after the merge Kim is in Engineering once; undoing puts Kim back
+        // in Sales as well as moving Amir back.
+        history.apply(&mut company, change("Merge Sales into Engineering")).unwrap();
+        assert_eq!(vec![("Engineering", vec!["Amir", "Kim", "Sally"])], company.sorted_by_dept());
+        history.undo(&mut company).unwrap();
+        assert_eq!(before, format!("{:?}", company.sorted_by_dept()));
+        history.redo(&mut company).unwrap();
+        assert_eq!(Err(CompanyError::NothingToRedo), history.redo(&mut company));
+
+        // A batch that fails partway leaves nothing behind, and nothing to undo.
+        history.undo(&mut company).unwrap();
+        let batch = vec![change("Add Jo to Support"), change("Move Jo from Support to Sales"), change("Add Amir to Sales")];
+        assert!(history.apply_all(&mut company, batch).is_err());
+        assert_eq!(before, format!("{:?}", company.sorted_by_dept()));
+        // A change that failed doesn't throw away what can be redone.
+        history.redo(&mut company).unwrap();
+
+        for _ in 0..5 {
+            history.undo(&mut company).unwrap();
+        }
+        assert!(company.departments.is_empty());
+        assert_eq!(Err(CompanyError::NothingToUndo), history.undo(&mut company));
+    }
+
+    mod org {
+        use exercises::three::org::{Employee, OrgChart, OrgError};
+        use exercises::three::Company;
+
+        fn names(employees: Vec<&Employee>) -> Vec<&str> {
+            employees.into_iter().map(|e| e.name.as_str()).collect()
+        }
+
+        #[test]
+        fn reports_and_chain_of_command() {
+            let mut chart = OrgChart::new();
+            chart.add_dept("Engineering", None).unwrap();
+            chart.add_dept("Platform", Some("engineering")).unwrap();
+            chart.add_dept("Sales", None).unwrap();
+            let cto = chart.hire("Grace", "CTO", "Engineering").unwrap();
+            let lead = chart.hire("Sally", "Lead", "Platform").unwrap();
+            let dev = chart.hire("Abhishek", "Engineer", "Platform").unwrap();
+            let sales = chart.hire("Amir", "Account Executive", "Sales").unwrap();
+            chart.set_manager(lead, Some(cto)).unwrap();
+            chart.set_manager(dev, Some(lead)).unwrap();
+
+            assert_eq!(vec!["Sally", "Abhishek"], names(chart.all_reports(cto).unwrap()));
+            assert_eq!(vec!["Sally", "Grace"], names(chart.chain_of_command(dev).unwrap()));
+            assert_eq!(vec!["Grace", "Abhishek", "Sally"], names(chart.all_members("Engineering").unwrap()));
+            assert_eq!(vec!["Platform", "Engineering"], chart.dept_path("platform"));
+
+            // Nobody can end up managing themselves, however far round.
+            assert_eq!(Err(OrgError::ManagerCycle { employee: cto, manager: dev }), chart.set_manager(cto, Some(dev)));
+            assert!(chart.set_manager(cto, Some(cto)).is_err());
+            assert!(chart.move_dept("Engineering", Some("Platform")).is_err());
+            assert_eq!(Err(OrgError::DeptNotEmpty(String::from("Engineering"))), chart.remove_dept("Engineering"));
+
+            assert_eq!(
+                "Engineering\n\
+                 ├── Grace, CTO\n\
+                 └── Platform\n    \
+                     ├── Abhishek, Engineer\n    \
+                     └── Sally, Lead\n\
+                 Sales\n\
+                 └── Amir, Account Executive\n",
+                chart.render_depts()
+            );
+            assert_eq!("Grace, CTO\n└── Sally, Lead\n    └── Abhishek, Engineer\n", chart.render_reports(cto).unwrap());
+
+            // Sally's report moves up to Grace.
+            chart.remove(lead).unwrap();
+            assert_eq!(vec!["Abhishek"], names(chart.direct_reports(cto)));
+            assert_eq!(Err(OrgError::NoSuchEmployee(lead)), chart.set_manager(sales, Some(lead)));
+        }
+
+        #[test]
+        fn converts_to_and_from_company() {
+            let mut company = Company::new();
+            company.add_to_dept("Engineering", "Sally").unwrap();
+            company.add_to_dept("Sales", "Amir").unwrap();
+            let chart = OrgChart::from_company(&company);
+            assert_eq!(vec!["Sally"], names(chart.members("engineering").unwrap()));
+            assert!(chart.chain_of_command(chart.find("Sally")[0].id).unwrap().is_empty());
+            assert_eq!(company.sorted_by_dept(), chart.to_company().sorted_by_dept());
+        }
+
+        #[test]
+        fn merges_departments_differing_in_case() {
+            let mut company = Company::new();
+            company.departments.insert(String::from("sales"), vec![String::from("Jo")]);
+            company.departments.insert(String::from("Sales"), vec![String::from("Amir")]);
+            let chart = OrgChart::from_company(&company);
+            assert_eq!(Some("Sales"), chart.dept_name("SALES"));
+            assert_eq!(vec!["Amir", "Jo"], names(chart.members("sales").unwrap()));
+            assert_eq!(vec![("Sales", vec!["Amir", "Jo"])], chart.to_company().sorted_by_dept());
+        }
+    }
+
+    mod storage {
+        use std::env;
+        use std::fs;
+        use std::io::Cursor;
+        use std::path::PathBuf;
+        use std::thread;
+
+        use exercises::three::storage::{self, Conflict, Invalid, Row};
+        use exercises::three::Company;
+
+        // A fresh file per test, so tests running in parallel don't share one.
+        fn scratch(name: &str) -> PathBuf {
+            let path = env::temp_dir().join(format!("collections_{}_{}", std::process::id(), name));
+            let _ = fs::remove_file(&path);
+            path
+        }
+
+        fn sample() -> Company {
+            let mut company = Company::new();
+            company.add_to_dept("Engineering", "Sally").unwrap();
+            company.add_to_dept("Engineering", "Abhishek").unwrap();
+            company.add_to_dept("Sales, EMEA", "Amir \"the closer\"").unwrap();
+            company
+        }
+
+        #[test]
+        fn saves_and_loads() {
+            for name in &["company.json", "company.csv"] {
+                let path = scratch(name);
+                storage::save(&sample(), &path).unwrap();
+                assert_eq!(sample().sorted_by_dept(), storage::load(&path).unwrap().sorted_by_dept());
+                // Nothing left lying around from the atomic save.
+                let prefix = format!(".{}.", path.file_name().unwrap().to_string_lossy());
+                let temporaries = fs::read_dir(env::temp_dir()).unwrap().filter(|entry| {
+                    entry.as_ref().unwrap().file_name().to_string_lossy().starts_with(&prefix)
+                });
+                assert_eq!(0, temporaries.count());
+                fs::remove_file(&path).unwrap();
+            }
+            let mut csv = Vec::new();
+            storage::write_csv(&sample(), &mut csv).unwrap();
+            assert_eq!(
+                "# version 1\nname,department\nAbhishek,Engineering\nSally,Engineering\n\"Amir \"\"the closer\"\"\",\"Sales, EMEA\"\n",
+                String::from_utf8(csv).unwrap()
+            );
+        }
+
+        #[test]
+        fn keeps_awkward_names() {
+            let mut company = Company::new();
+            company.add_to_dept("Engineering", "#1 Fan").unwrap();
+            company.add_to_dept("Engineering", " Spacey ").unwrap();
+            company.add_to_dept("# Hashtags", "Tess").unwrap();
+            let path = scratch("awkward.csv");
+            storage::save(&company, &path).unwrap();
+            let loaded = storage::load(&path).unwrap();
+            fs::remove_file(&path).unwrap();
+            assert_eq!(company.sorted_by_dept(), loaded.sorted_by_dept());
+
+            // Files written before names like these were quoted still load them after the header.
+            let (rows, _) = storage::read_csv(Cursor::new("# version 1\nname,department\n#1 Fan, Engineering \n")).unwrap();
+            assert_eq!(vec![Row { row: 3, name: String::from("#1 Fan"), dept: String::from("Engineering") }], rows);
+        }
+
+        #[test]
+        fn schema_versions() {
+            // Version 0 had no version, just the departments.
+            let rows = storage::read_json(Cursor::new(r#"{"Sales": ["Amir"]}"#)).unwrap();
+            assert_eq!(vec![Row { row: 1, name: String::from("Amir"), dept: String::from("Sales") }], rows);
+            let newer = storage::read_json(Cursor::new(r#"{"version": 2, "staff": []}"#)).unwrap_err();
+            assert!(newer.starts_with("written by a newer version"), "{}", newer);
+            assert!(storage::read_csv(Cursor::new("# version 9\nname,department\n")).is_err());
+        }
+
+        #[test]
+        fn saves_at_once_use_their_own_temporary_files() {
+            let path = scratch("busy.json");
+            // One left behind by a save that crashed is no obstacle either.
+            let stale = path.with_file_name(format!(".{}.tmp", path.file_name().unwrap().to_string_lossy()));
+            fs::write(&stale, "").unwrap();
+            let saves: Vec<_> = (0..8)
+                .map(|_| {
+                    let path = path.clone();
+                    thread::spawn(move || storage::save(&sample(), &path))
+                })
+                .collect();
+            for save in saves {
+                save.join().unwrap().unwrap();
+            }
+            assert_eq!(sample().sorted_by_dept(), storage::load(&path).unwrap().sorted_by_dept());
+            fs::remove_file(&path).unwrap();
+            fs::remove_file(&stale).unwrap();
+        }
+
+        #[test]
+        fn import_reports_what_it_skipped() {
+            let path = scratch("import.csv");
+            fs::write(&path, "department,name\nEngineering,Sally\nSales,Sally\nSupport,Jo\nSupport,Jo\nSupport\n\"Kim,Support\n").unwrap();
+            let mut company = sample();
+            let report = storage::import(&mut company, &path).unwrap();
+            fs::remove_file(&path).unwrap();
+
+            assert_eq!(1, report.changes.len());
+            let row = |row: usize, name: &str, dept: &str| Row { row, name: name.to_string(), dept: dept.to_string() };
+            assert_eq!(vec![row(2, "Sally", "Engineering"), row(5, "Jo", "Support")], report.duplicates);
+            assert_eq!(vec![Conflict { row: row(3, "Sally", "Sales"), existing: vec![String::from("Engineering")] }], report.conflicts);
+            assert_eq!(
+                vec![
+                    Invalid { row: 6, error: String::from("needs both a name and a department") },
+                    Invalid { row: 7, error: String::from("unterminated quote") },
+                ],
+                report.invalid
+            );
+            assert_eq!(Some(vec!["Jo"]), company.sorted_dept("Support"));
+            assert_eq!(None, company.sorted_dept("Sales"));
+        }
+
+        #[test]
+        fn import_knows_people_in_two_departments() {
+            let mut company = sample();
+            company.add_to_dept("Support", "Sally").unwrap();
+            let row = |row: usize, name: &str, dept: &str| Row { row, name: name.to_string(), dept: dept.to_string() };
+            let rows = vec![row(2, "Sally", "Engineering"), row(3, "sally", "support"), row(4, "Sally", "Sales")];
+            let report = storage::plan_import(&company, rows);
+
+            assert!(report.changes.is_empty());
+            assert_eq!(vec![row(2, "Sally", "Engineering"), row(3, "sally", "support")], report.duplicates);
+            let existing = vec![String::from("Engineering"), String::from("Support")];
+            assert_eq!(vec![Conflict { row: row(4, "Sally", "Sales"), existing }], report.conflicts);
+        }
+    }
+}
+
+mod spreadsheet {
+    use std::io::Cursor;
+
+    use spreadsheet::formula::{self, Addr, Expr, Op, Reference};
+    use spreadsheet::{CellError, Sheet, Value};
+    use vectors::SpreadsheetCell;
+
+    fn a(name: &str) -> Addr {
+        Addr::parse(name).unwrap()
+    }
+
+    fn int(n: i32) -> Value {
+        Value::Cell(SpreadsheetCell::Int(n))
+    }
+
+    #[test]
+    fn addresses_and_formulas() {
+        assert_eq!(Addr::new(0, 0), a("A1"));
+        assert_eq!(Addr::new(27, 9), a("ab10"));
+        assert_eq!("AB10", a("AB10").to_string());
+        assert_eq!("ZZ1", Addr::new(701, 0).to_string());
+        assert_eq!(None, Addr::parse("A0"));
+        assert_eq!(None, Addr::parse("1A"));
+
+        assert_eq!(
+            Ok(Expr::Binary(
+                Op::Add,
+                Box::new(Expr::Ref(a("A1"))),
+                Box::new(Expr::Binary(Op::Mul, Box::new(Expr::Ref(a("B2"))), Box::new(Expr::Literal(SpreadsheetCell::Int(2))))),
+            )),
+            formula::parse("A1 + B2 * 2")
+        );
+        assert_eq!(
+            vec![Reference::Cell(a("C3")), Reference::Range(a("A1"), a("B2"))],
+            formula::parse("SUM(B2:A1) + C3 * C3").unwrap().references()
+        );
+        assert!(formula::parse("SUM(A1").is_err());
+        assert!(formula::parse("NOPE(A1)").is_err());
+        assert!(formula::parse("1 +").is_err());
+
+        // Deep nesting is refused rather than overflowing the stack.
+        let too_deep = "the formula nests more than 256 deep";
+        for deep in &["(".repeat(200_000), "-".repeat(200_000), "1+".repeat(200_000) + "1", "SUM(".repeat(300)] {
+            assert_eq!(Err(String::from(too_deep)), formula::parse(deep));
+        }
+        let mut sheet = Sheet::new();
+        sheet.set(a("A1"), &format!("={}1{}", "(".repeat(100_000), ")".repeat(100_000)));
+        assert_eq!(Value::Error(CellError::Parse), sheet.value(a("A1")));
+        assert!(formula::parse(&format!("{}1{}", "(".repeat(200), ")".repeat(200))).is_ok());
+    }
+
+    #[test]
+    fn recalculates_what_depends_on_a_change() {
+        let mut sheet = Sheet::new();
+        for (i, n) in [1, 2, 3, 4, 5].iter().enumerate() {
+            sheet.set(Addr::new(0, i), &n.to_string());
+        }
+        sheet.set(a("A6"), "=SUM(A1:A5)");
+        sheet.set(a("B1"), "=A6 / COUNT(A1:A5)");
+        sheet.set(a("B2"), "=A1 * 10");
+        sheet.set(a("C1"), "Total");
+        assert_eq!(int(15), sheet.value(a("A6")));
+        assert_eq!(int(3), sheet.value(a("B1")));
+
+        // B2 reads A1, not A3, so it's left alone.
+        assert_eq!(vec![a("A3"), a("A6"), a("B1")], sheet.set(a("A3"), "4.5"));
+        assert_eq!(Value::Cell(SpreadsheetCell::Float(16.5)), sheet.value(a("A6")));
+        assert_eq!(Value::Cell(SpreadsheetCell::Float(3.3)), sheet.value(a("B1")));
+
+        sheet.set(a("D1"), "=C1 + 1");
+        assert_eq!(Value::Error(CellError::Value), sheet.value(a("D1")));
+        sheet.set(a("D2"), "=1 / (A1 - 1)");
+        assert_eq!("#DIV/0!", sheet.value(a("D2")).to_string());
+        sheet.set(a("D3"), "=SUM(A1");
+        assert_eq!(Value::Error(CellError::Parse), sheet.value(a("D3")));
+        assert_eq!(Some("expected ',' or ')'"), sheet.error(a("D3")));
+        // Ints that overflow carry on as Floats.
+        sheet.set(a("D4"), "=2147483647 + 1");
+        assert_eq!(Value::Cell(SpreadsheetCell::Float(2147483648.0)), sheet.value(a("D4")));
+    }
+
+    #[test]
+    fn huge_ranges_cost_only_what_they_hold() {
+        let mut sheet = Sheet::new();
+        sheet.set(a("A1"), "=SUM(B1:ZZ200000)");
+        sheet.set(a("ZZ200000"), "5");
+        assert_eq!(vec![a("C7"), a("A1")], sheet.set(a("C7"), "2"));
+        assert_eq!(int(7), sheet.value(a("A1")));
+        // Cells outside the range are none of its business.
+        assert_eq!(vec![a("A2")], sheet.set(a("A2"), "100"));
+        // A range that takes in its own cell is a cycle.
+        sheet.set(a("A3"), "=SUM(A1:A5)");
+        assert_eq!("#CYCLE", sheet.value(a("A3")).to_string());
+    }
+
+    #[test]
+    fn cycles() {
+        let mut sheet = Sheet::new();
+        sheet.set(a("A1"), "=B1 + 1");
+        sheet.set(a("B1"), "=A1 + 1");
+        sheet.set(a("C1"), "=B1");
+        sheet.set(a("D1"), "=D1");
+        for cell in &["A1", "B1", "C1", "D1"] {
+            assert_eq!("#CYCLE", sheet.value(a(cell)).to_string(), "{}", cell);
+        }
+        // Breaking the cycle mends everything downstream of it.
+        sheet.set(a("B1"), "5");
+        assert_eq!(int(6), sheet.value(a("A1")));
+        assert_eq!(int(5), sheet.value(a("C1")));
+    }
+
+    #[test]
+    fn csv() {
+        let sheet = Sheet::read_csv(Cursor::new("Item,Price\nTea,3\n\"Cake, large\",4.5\nTotal,=SUM(B2:B3)\n")).unwrap();
+        assert_eq!(Some("=SUM(B2:B3)"), sheet.input(a("B4")));
+        let mut out = Vec::new();
+        sheet.write_csv(&mut out).unwrap();
+        assert_eq!("Item,Price\nTea,3\n\"Cake, large\",4.5\nTotal,7.5\n", String::from_utf8(out).unwrap());
+    }
+}
+
+mod words {
+    use std::io::Cursor;
+
+    use words::{compare, stop_words, words, Counter, Frequencies, Options};
+
+    #[test]
+    fn counts_words() {
+        assert_eq!(vec!["Hello", "world", "Don't", "नमस्ते"], words("Hello, world! Don't... नमस्ते."));
+        let counted = Frequencies::of("hello world wonderful World. The world!", Options::default());
+        assert_eq!(3, counted.count("world"));
+        assert_eq!(vec![("world", 3), ("hello", 1)], counted.top(2));
+
+        let options = Options { fold_case: false, stop_words: stop_words(), ngram: 1 };
+        let counted = Frequencies::of("The world and THE World", options);
+        assert_eq!(vec![("World", 1), ("world", 1)], counted.top(5));
+    }
+
+    #[test]
+    fn streams_ngrams_across_lines() {
+        let options = Options { ngram: 2, ..Options::default() };
+        let mut counter = Counter::new(options.clone());
+        counter.read(Cursor::new("to be or\nnot to be\n")).unwrap();
+        let streamed = counter.finish();
+        assert_eq!(streamed, Frequencies::of("to be or not to be", options));
+        assert_eq!(vec![("to be", 2), ("be or", 1), ("not to", 1), ("or not", 1)], streamed.top(10));
+        assert_eq!(5, streamed.total);
+    }
+
+    #[test]
+    fn compares_profiles() {
+        let a = Frequencies::of("cats purr and cats nap", Options::default());
+        let b = Frequencies::of("dogs bark and dogs nap", Options::default());
+        let comparison = compare(&a, &b, 1);
+        assert!(comparison.similarity > 0.0 && comparison.similarity < 1.0);
+        assert_eq!("cats", comparison.distinctive_a[0].0);
+        assert_eq!("dogs", comparison.distinctive_b[0].0);
+        assert!((compare(&a, &a, 1).similarity - 1.0).abs() < 1e-9);
+        assert!(compare(&a, &a, 1).distinctive_a.is_empty());
+    }
+}
+
+mod graphemes {
+    use graphemes::{grapheme_count, pad, reverse, slice_chars, slice_graphemes, truncate, width, Align};
+
+    // The greetings from the strings notes, with how many chars and graphemes each has and how
+    // many columns it takes up.
+    const GREETINGS: &[(&str, usize, usize, usize)] = &[
+        ("السلام عليكم", 12, 12, 12),
+        ("Dobrý den", 9, 9, 9),
+        ("Hello", 5, 5, 5),
+        ("שָׁלוֹם", 7, 4, 4),
+        ("नमस्ते", 6, 3, 4),
+        ("こんにちは", 5, 5, 10),
+        ("안녕하세요", 5, 5, 10),
+        ("你好", 2, 2, 4),
+        ("Olá", 3, 3, 3),
+        ("Здравствуйте", 12, 12, 12),
+        ("Hola", 4, 4, 4),
+    ];
+
+    #[test]
+    fn measures_greetings() {
+        for &(greeting, chars, graphemes, columns) in GREETINGS {
+            assert_eq!(chars, greeting.chars().count(), "{}", greeting);
+            assert_eq!(graphemes, grapheme_count(greeting), "{}", greeting);
+            assert_eq!(columns, width(greeting), "{}", greeting);
+            assert_eq!(columns.max(12), width(&pad(greeting, 12, Align::Right)), "{}", greeting);
+            // Reversing twice gets back where it started, marks and all.
+            assert_eq!(greeting, reverse(&reverse(greeting)));
+        }
+        assert_eq!("👩‍🔬", reverse("👩‍🔬"));
+        assert_eq!(2, width("👩‍🔬"));
+        assert_eq!(" Hola  ", pad("Hola", 7, Align::Center));
+    }
+
+    #[test]
+    fn slices_safely() {
+        // &"नमस्ते"[..2] panics; these don't.
+        assert_eq!(Some("न"), slice_graphemes("नमस्ते", 0..1));
+        assert_eq!(Some("स्ते"), slice_graphemes("नमस्ते", 2..3));
+        assert_eq!(Some("नमस"), slice_chars("नमस्ते", 0..3));
+        assert_eq!(Some(""), slice_graphemes("नमस्ते", 3..3));
+        assert_eq!(None, slice_graphemes("नमस्ते", 2..4));
+        assert_eq!(None, slice_chars("Hola", 4..5));
+        assert_eq!(Some("שָׁל"), slice_graphemes("שָׁלוֹם", 0..2));
+        assert_eq!("םוֹלשָׁ", reverse("שָׁלוֹם"));
+    }
+
+    #[test]
+    fn truncates_to_a_width() {
+        assert_eq!("Hola", truncate("Hola", 4));
+        assert_eq!("Hol…", truncate("Hola!", 4));
+        assert_eq!("Здравст…", truncate("Здравствуйте", 8));
+        // Each kana is two columns, so only two fit beside the ellipsis in six.
+        assert_eq!("こん…", truncate("こんにちは", 6));
+        assert_eq!("こん…", truncate("こんにちは", 5));
+        assert_eq!("…", truncate("你好", 1));
+        assert_eq!("", truncate("你好", 0));
+    }
+}
@@ -59,16 +59,18 @@ pub fn foo() {
     // v.push(6); Not allowed by borrow checker!
 
     // When we want to store elements of different (but known) types in a vector, we can wrap them
-    // inside an enum and then create a vector of enums!!
-    enum SpreadsheetCell {
-        Int(i32),
-        Float(f64),
-        Text(String),
-    }
-
+    // inside an enum and then create a vector of enums!! (SpreadsheetCell is defined below, out
+    // here in the module, so the spreadsheet module can build on it.)
     let row = vec![
         SpreadsheetCell::Int(3),
         SpreadsheetCell::Text(String::from("blue")),
         SpreadsheetCell::Float(10.12),
     ];
 }
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpreadsheetCell {
+    Int(i32),
+    Float(f64),
+    Text(String),
+}
@@ -0,0 +1,191 @@
+// Word frequencies, grown out of the word count in the hash maps notes:
+//
+//     for word in text.split_whitespace() {
+//         let count = map.entry(word).or_insert(0);
+//         *count += 1;
+//     }
+//
+// which counts "world", "world." and "World" as three different words. Here words are found the
+// way the Unicode standard says to (UAX #29, through the unicode-segmentation crate), so
+// punctuation is left out, "don't" stays one word, and scripts without spaces between letters
+// and marks, like नमस्ते, come out whole. On top of that:
+// - case folding, on by default, so "World" and "world" are the same word;
+// - stop words, the likes of "the" and "of", can be left out;
+// - counts can be of n-grams, runs of n words in a row, instead of single words;
+// - `top` gives the k most common, and `compare` says how alike two texts are and which words
+//   set each apart.
+//
+// A `Counter` takes its text a line at a time, so only the counts are ever held in memory, never
+// the text.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::io::{self, BufRead};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+// The most common English words, which say little about what a text is about.
+pub const STOP_WORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "am", "an", "and", "any", "are", "as", "at", "be", "been", "but", "by",
+    "can", "could", "did", "do", "does", "for", "from", "had", "has", "have", "he", "her", "him", "his", "how",
+    "i", "if", "in", "into", "is", "it", "its", "just", "me", "my", "no", "not", "of", "on", "or", "our", "out",
+    "she", "so", "some", "than", "that", "the", "their", "them", "then", "there", "these", "they", "this", "to",
+    "up", "us", "was", "we", "were", "what", "when", "which", "who", "will", "with", "would", "you", "your",
+];
+
+pub fn stop_words() -> HashSet<String> {
+    STOP_WORDS.iter().map(|w| w.to_string()).collect()
+}
+
+// The words in `text`, in order, as they are spelled there.
+pub fn words(text: &str) -> Vec<&str> {
+    text.unicode_words().collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub fold_case: bool,
+    // Left out before anything is counted, n-grams included. Matched whatever their case.
+    pub stop_words: HashSet<String>,
+    // 1 counts words, 2 pairs of words and so on.
+    pub ngram: usize,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options { fold_case: true, stop_words: HashSet::new(), ngram: 1 }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Counter {
+    options: Options,
+    counts: HashMap<String, usize>,
+    total: usize,
+    // The last n words, so n-grams can run on from one line into the next.
+    window: VecDeque<String>,
+}
+
+impl Counter {
+    pub fn new(mut options: Options) -> Counter {
+        options.ngram = options.ngram.max(1);
+        options.stop_words = options.stop_words.iter().map(|w| w.to_lowercase()).collect();
+        Counter { options, counts: HashMap::new(), total: 0, window: VecDeque::new() }
+    }
+
+    // Counts the words in `text`. Text can be given in pieces, as long as no piece ends halfway
+    // through a word.
+    pub fn add(&mut self, text: &str) {
+        for word in text.unicode_words() {
+            if !self.options.stop_words.is_empty() && self.options.stop_words.contains(&word.to_lowercase()) {
+                continue;
+            }
+            let word = if self.options.fold_case { word.to_lowercase() } else { word.to_string() };
+            self.window.push_back(word);
+            if self.window.len() > self.options.ngram {
+                self.window.pop_front();
+            }
+            if self.window.len() == self.options.ngram {
+                let gram = self.window.iter().map(|w| w.as_str()).collect::<Vec<_>>().join(" ");
+                *self.counts.entry(gram).or_insert(0) += 1;
+                self.total += 1;
+            }
+        }
+    }
+
+    // Counts everything in `input`, a line at a time. Bytes that aren't UTF-8 are replaced rather
+    // than stopping the count.
+    pub fn read<R: BufRead>(&mut self, mut input: R) -> io::Result<()> {
+        let mut line = Vec::new();
+        while input.read_until(b'\n', &mut line)? > 0 {
+            self.add(&String::from_utf8_lossy(&line));
+            line.clear();
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> Frequencies {
+        Frequencies { counts: self.counts, total: self.total }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Frequencies {
+    pub counts: HashMap<String, usize>,
+    // Every occurrence counted, so the sum of `counts`.
+    pub total: usize,
+}
+
+impl Frequencies {
+    // Counts `text` in one go.
+    pub fn of(text: &str, options: Options) -> Frequencies {
+        let mut counter = Counter::new(options);
+        counter.add(text);
+        counter.finish()
+    }
+
+    pub fn count(&self, word: &str) -> usize {
+        self.counts.get(word).cloned().unwrap_or(0)
+    }
+
+    // How much of the text `word` is, from 0 to 1.
+    pub fn share(&self, word: &str) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.count(word) as f64 / self.total as f64
+        }
+    }
+
+    // The `k` most common, most common first, with ties in alphabetical order. Keeps only k
+    // candidates at a time rather than sorting every word.
+    pub fn top(&self, k: usize) -> Vec<(&str, usize)> {
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        for (word, &count) in &self.counts {
+            // The heap's top is the weakest candidate: the lowest count, then the last word.
+            heap.push(Reverse((count, Reverse(word.as_str()))));
+            if heap.len() > k {
+                heap.pop();
+            }
+        }
+        let mut top: Vec<(&str, usize)> = heap.into_iter().map(|Reverse((count, Reverse(word)))| (word, count)).collect();
+        top.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        top
+    }
+}
+
+// How two texts compare.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    // The cosine of the angle between the two frequency profiles: 1 when words are used in the
+    // same proportions, 0 when the texts have no words in common.
+    pub similarity: f64,
+    // Words the first text uses much more than the second, most telling first, each with how
+    // many times more often (log 2) it's used there.
+    pub distinctive_a: Vec<(String, f64)>,
+    pub distinctive_b: Vec<(String, f64)>,
+}
+
+// Compares the word profiles of two texts, giving the `k` words that most set each apart.
+pub fn compare(a: &Frequencies, b: &Frequencies, k: usize) -> Comparison {
+    let dot: f64 = a.counts.keys().map(|w| a.share(w) * b.share(w)).sum();
+    let norm = |f: &Frequencies| f.counts.keys().map(|w| f.share(w).powi(2)).sum::<f64>().sqrt();
+    let (norm_a, norm_b) = (norm(a), norm(b));
+    let similarity = if norm_a == 0.0 || norm_b == 0.0 { 0.0 } else { dot / (norm_a * norm_b) };
+
+    // Adding one to every count (Laplace smoothing) means a word missing from one text gives a
+    // large ratio rather than an infinite one, and a word used once in one text and never in the
+    // other doesn't outrank one used a hundred times against once.
+    let vocabulary: HashSet<&str> = a.counts.keys().chain(b.counts.keys()).map(|w| w.as_str()).collect();
+    let smoothed = |f: &Frequencies, w: &str| (f.count(w) + 1) as f64 / (f.total + vocabulary.len()) as f64;
+    let ratios: Vec<(&str, f64)> = vocabulary.iter().map(|&w| (w, (smoothed(a, w) / smoothed(b, w)).log2())).collect();
+    // `sign` picks the side: 1 for words that lean towards a, -1 for b.
+    let telling = |sign: f64| {
+        let mut telling: Vec<(String, f64)> =
+            ratios.iter().filter(|r| r.1 * sign > 0.0).map(|&(w, r)| (w.to_string(), r * sign)).collect();
+        telling.sort_by(|x, y| y.1.partial_cmp(&x.1).unwrap().then(x.0.cmp(&y.0)));
+        telling.truncate(k);
+        telling
+    };
+    Comparison { similarity, distinctive_a: telling(1.0), distinctive_b: telling(-1.0) }
+}
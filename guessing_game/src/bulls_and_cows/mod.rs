@@ -0,0 +1,107 @@
+use rand::Rng;
+
+use difficulty::Difficulty;
+use game::Status;
+use guess::Code;
+
+// The Mastermind-style variant: instead of a number, guess a code of distinct digits. Every guess
+// is answered with how many digits are right and in the right place (bulls) and how many are in
+// the code but somewhere else (cows).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Score {
+    pub bulls: usize,
+    pub cows: usize,
+}
+
+pub fn score(secret: &Code, guess: &Code) -> Score {
+    let secret = secret.digits();
+    let guess = guess.digits();
+    let bulls = secret.iter().zip(guess).filter(|&(s, g)| s == g).count();
+    // Digits never repeat within a code, so everything in common that isn't a bull is a cow.
+    let common = guess.iter().filter(|d| secret.contains(d)).count();
+    Score { bulls, cows: common - bulls }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    // Not there yet, and there are attempts left.
+    Score(Score),
+    Win,
+    Lose,
+    Invalid(String),
+}
+
+// Plays like `game::Game`, with the same difficulties deciding how long the code is and how many
+// attempts there are.
+pub struct BullsAndCows {
+    difficulty: Difficulty,
+    secret: Code,
+    attempts: u32,
+    status: Status,
+}
+
+impl BullsAndCows {
+    pub fn new<R: Rng>(rng: &mut R, difficulty: Difficulty) -> BullsAndCows {
+        let mut digits: Vec<u8> = (0..10).collect();
+        rng.shuffle(&mut digits);
+        digits.truncate(difficulty.code_length());
+        BullsAndCows::with_secret(Code::new(digits), difficulty)
+    }
+
+    pub fn with_secret(secret: Code, difficulty: Difficulty) -> BullsAndCows {
+        BullsAndCows {
+            difficulty,
+            secret,
+            attempts: 0,
+            status: Status::Playing,
+        }
+    }
+
+    pub fn submit(&mut self, input: &str) -> Outcome {
+        match Code::parse(input, self.difficulty.code_length()) {
+            Ok(code) => self.guess(&code),
+            Err(error) => Outcome::Invalid(error),
+        }
+    }
+
+    pub fn guess(&mut self, code: &Code) -> Outcome {
+        if self.status != Status::Playing {
+            return Outcome::Invalid(String::from("The game is already over"));
+        }
+        self.attempts += 1;
+        let score = score(&self.secret, code);
+        if score.bulls == self.secret.digits().len() {
+            self.status = Status::Won;
+            return Outcome::Win;
+        }
+        if self.attempts == self.difficulty.max_attempts() {
+            self.status = Status::Lost;
+            return Outcome::Lose;
+        }
+        Outcome::Score(score)
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    pub fn is_won(&self) -> bool {
+        self.status == Status::Won
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.status != Status::Playing
+    }
+
+    pub fn secret(&self) -> &Code {
+        &self.secret
+    }
+}
@@ -0,0 +1,196 @@
+use rand::Rng;
+use std::cmp::Ordering;
+use std::io::{self, BufRead, Write};
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
+
+use bulls_and_cows::{self, BullsAndCows};
+use difficulty::Difficulty;
+use game::{Game, Outcome};
+use guess::Guess;
+use hints::HintMode;
+use replay::{self, Event, EventLog};
+use solver::{Solver, Strategy};
+
+// How a game played through `play` ended.
+pub struct Summary {
+    pub game: Game,
+    pub elapsed: Duration,
+    // None unless the game was won.
+    pub score: Option<u32>,
+}
+
+// Asks for the next guess. Every variant reads its guesses through here; None means the input ran
+// out.
+fn next_guess<I: BufRead, O: Write>(input: &mut I, output: &mut O) -> io::Result<Option<String>> {
+    writeln!(output, "Please input your guess.")?;
+    let mut guess = String::new(); // String type is growable and UTF-8 encoded
+    // read_line returns a Result type, which is an Enum. Result variants are Ok and Err.
+    // Ok(0) means there is nothing left to read.
+    if input.read_line(&mut guess)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(guess))
+}
+
+// Plays one game, reading guesses from `input` and writing the conversation to `output`. The
+// binary passes stdin and stdout; tests pass a script of guesses and a Vec<u8> to look at later.
+// Running out of input ends the game early, neither won nor lost.
+pub fn play<R, I, O>(rng: &mut R, difficulty: Difficulty, input: I, output: O) -> io::Result<Summary>
+where
+    R: Rng,
+    I: BufRead,
+    O: Write,
+{
+    play_with_hints(rng, difficulty, &[], input, output)
+}
+
+// `play`, with a line for each of the hint `modes` after every wrong guess.
+pub fn play_with_hints<R, I, O>(rng: &mut R, difficulty: Difficulty, modes: &[HintMode], input: I, output: O) -> io::Result<Summary>
+where
+    R: Rng,
+    I: BufRead,
+    O: Write,
+{
+    play_game(Game::new(rng, difficulty), modes, input, output, &mut |_| Ok(()))
+}
+
+// `play_with_hints`, writing everything that happens to `log` as well. The secret comes from a
+// generator seeded with `seed` so that the log can be replayed later; see the `replay` module.
+pub fn play_logged<L, I, O>(seed: u64, difficulty: Difficulty, modes: &[HintMode], log: &mut EventLog<L>, input: I, output: O) -> io::Result<Summary>
+where
+    L: Write,
+    I: BufRead,
+    O: Write,
+{
+    let game = Game::new(&mut replay::rng(seed), difficulty);
+    log.record(&Event::Start { difficulty, hints: modes.to_vec(), seed, secret: game.secret() })?;
+    play_game(game, modes, input, output, &mut |event| log.record(&event))
+}
+
+fn play_game<I, O>(mut game: Game, modes: &[HintMode], mut input: I, mut output: O, log: &mut dyn FnMut(Event) -> io::Result<()>) -> io::Result<Summary>
+where
+    I: BufRead,
+    O: Write,
+{
+    let difficulty = game.difficulty();
+    let range = game.range();
+    writeln!(
+        output,
+        "I'm thinking of a number between {} and {}. You have {} attempts.",
+        range.start(),
+        range.end(),
+        difficulty.max_attempts()
+    )?;
+    let started = Instant::now();
+    while !game.is_over() {
+        let guess = match next_guess(&mut input, &mut output)? {
+            Some(guess) => guess,
+            None => break,
+        };
+        log(Event::Input(guess.clone()))?;
+        // What Game::submit does, in two steps so that both can be logged.
+        let outcome = match Guess::parse(&guess, &range) {
+            Ok(parsed) => {
+                log(Event::Guess(parsed.value()))?;
+                game.guess(parsed)
+            }
+            Err(error) => {
+                log(Event::Error(error.clone()))?;
+                writeln!(output, "Got error: {}", error)?;
+                continue;
+            }
+        };
+        log(Event::Response(outcome.clone()))?;
+        writeln!(output, "You guessed: {}", guess.trim())?;
+        match outcome {
+            Outcome::TooSmall => writeln!(output, "Too small!")?,
+            Outcome::TooBig => writeln!(output, "Too big!")?,
+            Outcome::Win => writeln!(output, "You win!")?,
+            Outcome::Lose => writeln!(output, "Out of attempts, you lose! The number was {}.", game.secret())?,
+            Outcome::Invalid(_) => unreachable!(),
+        }
+        for hint in game.hints(modes) {
+            writeln!(output, "{}", hint)?;
+        }
+    }
+    let elapsed = started.elapsed();
+    let score = game.score(elapsed);
+    if let Some(score) = score {
+        writeln!(
+            output,
+            "Your score: {} ({} attempts, {} seconds)",
+            score,
+            game.attempts(),
+            elapsed.as_secs()
+        )?;
+    }
+    Ok(Summary { game, elapsed, score })
+}
+
+// The bulls and cows variant. Returns the game, won, lost or abandoned when the input ran out.
+pub fn play_bulls_and_cows<R, I, O>(rng: &mut R, difficulty: Difficulty, mut input: I, mut output: O) -> io::Result<BullsAndCows>
+where
+    R: Rng,
+    I: BufRead,
+    O: Write,
+{
+    let mut game = BullsAndCows::new(rng, difficulty);
+    writeln!(
+        output,
+        "I'm thinking of a {}-digit code, no digit twice. You have {} attempts.",
+        difficulty.code_length(),
+        difficulty.max_attempts()
+    )?;
+    while !game.is_over() {
+        let guess = match next_guess(&mut input, &mut output)? {
+            Some(guess) => guess,
+            None => break,
+        };
+        match game.submit(&guess) {
+            bulls_and_cows::Outcome::Invalid(error) => writeln!(output, "Got error: {}", error)?,
+            bulls_and_cows::Outcome::Score(score) => writeln!(output, "{} bulls, {} cows.", score.bulls, score.cows)?,
+            bulls_and_cows::Outcome::Win => writeln!(output, "You win!")?,
+            bulls_and_cows::Outcome::Lose => writeln!(output, "Out of attempts, you lose! The code was {}.", game.secret())?,
+        }
+    }
+    Ok(game)
+}
+
+// The other way round: the player thinks of a number and the computer guesses it. Returns the
+// number once the player says a guess is correct, or None if the input runs out first.
+pub fn solve<S, I, O>(strategy: S, range: RangeInclusive<u32>, mut input: I, mut output: O) -> io::Result<Option<u32>>
+where
+    S: Strategy,
+    I: BufRead,
+    O: Write,
+{
+    writeln!(output, "Think of a number between {} and {}.", range.start(), range.end())?;
+    let mut solver = Solver::new(strategy, range);
+    let mut guess = solver.guess();
+    loop {
+        writeln!(output, "Is it {}? Answer (s)mall, (b)ig or (c)orrect.", guess)?;
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            return Ok(None);
+        }
+        let ordering = match answer.trim().to_lowercase().as_str() {
+            "s" | "small" | "too small" | "<" => Ordering::Less,
+            "b" | "big" | "too big" | ">" => Ordering::Greater,
+            "c" | "correct" | "yes" | "=" => Ordering::Equal,
+            other => {
+                writeln!(output, "Got error: expected small, big or correct, got '{}'", other)?;
+                continue;
+            }
+        };
+        if let Err(e) = solver.answer(guess, ordering) {
+            writeln!(output, "That can't be right: {}.", e)?;
+            continue;
+        }
+        if let Some(secret) = solver.solved() {
+            writeln!(output, "Got it in {} guesses: {}!", solver.guesses(), secret)?;
+            return Ok(Some(secret));
+        }
+        guess = solver.guess();
+    }
+}
@@ -0,0 +1,81 @@
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+// Presets for how big the range of numbers is and how many guesses you get. Each preset leaves a
+// few guesses to spare over what a perfect binary search needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        match name.to_lowercase().as_str() {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    pub fn range(&self) -> RangeInclusive<u32> {
+        match *self {
+            Difficulty::Easy => 1..=50,
+            Difficulty::Normal => 1..=100,
+            Difficulty::Hard => 1..=1000,
+        }
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        match *self {
+            Difficulty::Easy => 10,
+            Difficulty::Normal => 9,
+            Difficulty::Hard => 12,
+        }
+    }
+
+    // How many digits the code has in the bulls and cows variant.
+    pub fn code_length(&self) -> usize {
+        match *self {
+            Difficulty::Easy => 3,
+            Difficulty::Normal => 4,
+            Difficulty::Hard => 5,
+        }
+    }
+
+    // What a win is worth before any deductions.
+    fn base_points(&self) -> u32 {
+        match *self {
+            Difficulty::Easy => 100,
+            Difficulty::Normal => 200,
+            Difficulty::Hard => 400,
+        }
+    }
+}
+
+// Wins taking longer than this get no time bonus.
+const BONUS_SECONDS: u64 = 60;
+
+// Score for a won game. The base points are scaled by the share of attempts left over (counting
+// the winning guess as unused, so a first-guess win keeps all of them), and up to half as much
+// again is added for finishing quickly.
+pub fn score(difficulty: Difficulty, attempts: u32, elapsed: Duration) -> u32 {
+    let base = difficulty.base_points();
+    let max = difficulty.max_attempts();
+    let unused = max.saturating_sub(attempts) + 1;
+    let attempt_points = base * unused.min(max) / max;
+    let seconds_left = BONUS_SECONDS.saturating_sub(elapsed.as_secs()) as u32;
+    let time_bonus = base / 2 * seconds_left / BONUS_SECONDS as u32;
+    attempt_points + time_bonus
+}
@@ -0,0 +1,148 @@
+use rand::Rng; // Rng is a *Trait*
+use std::cmp::Ordering; // Ordering is an Enum. Ordering variants are Less, Greater, and Equal
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+use difficulty::{self, Difficulty};
+use guess::Guess;
+use hints::{self, Hint, HintMode};
+
+// What the game says back to a guess.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    TooSmall,
+    TooBig,
+    Win,
+    // That was the last attempt and it was wrong.
+    Lose,
+    // The input wasn't a usable guess (or the game is already over). It doesn't use up an attempt.
+    Invalid(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Playing,
+    Won,
+    Lost,
+}
+
+pub struct Game {
+    difficulty: Difficulty,
+    secret: u32,
+    attempts: u32,
+    status: Status,
+    // The last two valid guesses, newest first, for the hints that need them.
+    last: Option<u32>,
+    previous: Option<u32>,
+}
+
+impl Game {
+    // Taking the random number generator as an argument, instead of calling rand::thread_rng()
+    // in here, is what lets tests use a seeded generator and know what's coming.
+    pub fn new<R: Rng>(rng: &mut R, difficulty: Difficulty) -> Game {
+        let range = difficulty.range();
+        // gen_range's upper bound is exclusive, hence the + 1.
+        let secret = rng.gen_range(*range.start(), range.end() + 1); // gen_range function is defined in the Rng trait
+        Game::with_secret(secret, difficulty)
+    }
+
+    pub fn with_secret(secret: u32, difficulty: Difficulty) -> Game {
+        Game {
+            difficulty,
+            secret,
+            attempts: 0,
+            status: Status::Playing,
+            last: None,
+            previous: None,
+        }
+    }
+
+    // Takes a line of player input as typed.
+    pub fn submit(&mut self, input: &str) -> Outcome {
+        match Guess::parse(input, &self.range()) {
+            Ok(guess) => self.guess(guess),
+            Err(error) => Outcome::Invalid(error),
+        }
+    }
+
+    pub fn guess(&mut self, guess: Guess) -> Outcome {
+        if self.status != Status::Playing {
+            return Outcome::Invalid(String::from("The game is already over"));
+        }
+        self.attempts += 1;
+        self.previous = self.last;
+        self.last = Some(guess.value());
+        // match has various "arms", to which it matches the value it is provided,
+        // and executes the code associated with a matched arm.
+        let outcome = match guess.value().cmp(&self.secret) {
+            Ordering::Less => Outcome::TooSmall,
+            Ordering::Greater => Outcome::TooBig,
+            Ordering::Equal => {
+                self.status = Status::Won;
+                return Outcome::Win;
+            }
+        };
+        if self.attempts_left() == 0 {
+            self.status = Status::Lost;
+            return Outcome::Lose;
+        }
+        outcome
+    }
+
+    // Hints about the latest guess, one per mode that has something to say. There's nothing to
+    // hint at before the first guess or once the game is over.
+    pub fn hints(&self, modes: &[HintMode]) -> Vec<Hint> {
+        let last = match self.last {
+            Some(last) if !self.is_over() => last,
+            _ => return Vec::new(),
+        };
+        let range = self.range();
+        modes
+            .iter()
+            .filter_map(|&mode| hints::hint(mode, &range, self.secret, last, self.previous, self.attempts))
+            .collect()
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    pub fn range(&self) -> RangeInclusive<u32> {
+        self.difficulty.range()
+    }
+
+    // Valid guesses made so far.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    pub fn attempts_left(&self) -> u32 {
+        self.difficulty.max_attempts() - self.attempts
+    }
+
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    pub fn is_won(&self) -> bool {
+        self.status == Status::Won
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.status != Status::Playing
+    }
+
+    pub fn secret(&self) -> u32 {
+        self.secret
+    }
+
+    // Only a won game scores. The caller measures the time, so the game itself never has to
+    // look at a clock.
+    pub fn score(&self, elapsed: Duration) -> Option<u32> {
+        if self.is_won() {
+            Some(difficulty::score(self.difficulty, self.attempts, elapsed))
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+
+// From ch:09 on errors:
+// To ensure that the input is actually within the range of the game being played.
+pub struct Guess {
+    value: u32,
+}
+
+impl Guess {
+    pub fn new(value: u32, range: &RangeInclusive<u32>) -> Guess {
+        if !range.contains(&value) {
+            panic!(
+                "Guess value must be between {} and {}, got {}.",
+                range.start(),
+                range.end(),
+                value
+            );
+        }
+
+        Guess { value }
+    }
+
+    // Like `new`, but for input typed by a player, where a bad value should be reported rather
+    // than crash the game.
+    pub fn parse(input: &str, range: &RangeInclusive<u32>) -> Result<Guess, String> {
+        // trim() drops the newline read_line leaves at the end.
+        let value: u32 = input.trim().parse().map_err(|e| format!("{}", e))?;
+        if !range.contains(&value) {
+            return Err(format!(
+                "Guess value must be between {} and {}, got {}.",
+                range.start(),
+                range.end(),
+                value
+            ));
+        }
+        Ok(Guess { value })
+    }
+
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+}
+
+// A guess at a secret code in the bulls and cows variant: `length` digits, all different. It
+// goes through `Guess::parse` first, so a code gets exactly the same checks and error messages as
+// a number would, and only then the checks that are particular to codes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Code {
+    digits: Vec<u8>,
+}
+
+impl Code {
+    pub fn new(digits: Vec<u8>) -> Code {
+        if digits.iter().any(|&d| d > 9) {
+            panic!("Code digits must be between 0 and 9, got {:?}.", digits);
+        }
+        Code { digits }
+    }
+
+    pub fn parse(input: &str, length: usize) -> Result<Code, String> {
+        // The largest code of this length is all nines. Leading zeros are allowed, which parsing
+        // as a number happily ignores, so the length is checked on the text itself.
+        let largest = 10u32.pow(length as u32) - 1;
+        Guess::parse(input, &(0..=largest))?;
+        let input = input.trim();
+        // u32 parsing also takes a leading '+', which is no digit.
+        if !input.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(String::from("invalid digit found in string"));
+        }
+        if input.len() != length {
+            return Err(format!("Code must have {} digits, got {}.", length, input.len()));
+        }
+        let digits: Vec<u8> = input.bytes().map(|b| b - b'0').collect();
+        for (i, digit) in digits.iter().enumerate() {
+            if digits[..i].contains(digit) {
+                return Err(format!("Code digits must all be different, got {} twice.", digit));
+            }
+        }
+        Ok(Code { digits })
+    }
+
+    pub fn digits(&self) -> &[u8] {
+        &self.digits
+    }
+}
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for digit in &self.digits {
+            write!(f, "{}", digit)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+// Extra hints on top of "Too small!"/"Too big!". Each mode is optional and they can be combined;
+// the console prints one line per mode after every wrong guess.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintMode {
+    // How close the guess is, judged against the size of the range.
+    Temperature,
+    // Whether the guess is closer than the one before it.
+    Trend,
+    // Something about the number itself: is it even, divisible by 3, by 5, ...
+    Divisibility,
+}
+
+impl HintMode {
+    pub fn from_name(name: &str) -> Option<HintMode> {
+        match name.to_lowercase().as_str() {
+            "temperature" | "hot-cold" | "hotcold" => Some(HintMode::Temperature),
+            "trend" | "warmer-colder" => Some(HintMode::Trend),
+            "divisibility" | "parity" => Some(HintMode::Divisibility),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            HintMode::Temperature => "temperature",
+            HintMode::Trend => "trend",
+            HintMode::Divisibility => "divisibility",
+        }
+    }
+}
+
+// Parses a comma separated list like "temperature,trend".
+pub fn parse_modes(list: &str) -> Result<Vec<HintMode>, String> {
+    list.split(',')
+        .filter(|name| !name.trim().is_empty())
+        .map(|name| HintMode::from_name(name.trim()).ok_or_else(|| format!("Unknown hint mode {}", name.trim())))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Temperature {
+    Hot,
+    Warm,
+    Cool,
+    Cold,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+    Warmer,
+    Colder,
+    Same,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hint {
+    Temperature(Temperature),
+    Trend(Trend),
+    Divisible { by: u32, divisible: bool },
+}
+
+// Divisibility hints go through these in turn, one per wrong guess, so the first one is always
+// about parity.
+const DIVISORS: [u32; 5] = [2, 3, 5, 7, 11];
+
+// The hint `mode` gives for `guess`, the `attempt`th (counting from 1) valid guess at `secret`.
+// `previous` is the guess before it, if any; without one there is no trend to report.
+pub fn hint(mode: HintMode, range: &RangeInclusive<u32>, secret: u32, guess: u32, previous: Option<u32>, attempt: u32) -> Option<Hint> {
+    let distance = |n: u32| n.abs_diff(secret);
+    match mode {
+        HintMode::Temperature => {
+            // Percent of the range the guess is off by, so "hot" means the same on every
+            // difficulty.
+            let width = (range.end() - range.start()).max(1);
+            let percent = distance(guess) * 100 / width;
+            let temperature = match percent {
+                0..=5 => Temperature::Hot,
+                6..=15 => Temperature::Warm,
+                16..=35 => Temperature::Cool,
+                _ => Temperature::Cold,
+            };
+            Some(Hint::Temperature(temperature))
+        }
+        HintMode::Trend => previous.map(|previous| {
+            let trend = match distance(guess).cmp(&distance(previous)) {
+                Ordering::Less => Trend::Warmer,
+                Ordering::Greater => Trend::Colder,
+                Ordering::Equal => Trend::Same,
+            };
+            Hint::Trend(trend)
+        }),
+        HintMode::Divisibility => {
+            let by = DIVISORS[(attempt.max(1) as usize - 1) % DIVISORS.len()];
+            Some(Hint::Divisible { by, divisible: secret.is_multiple_of(by) })
+        }
+    }
+}
+
+impl fmt::Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Hint::Temperature(Temperature::Hot) => write!(f, "Hot!"),
+            Hint::Temperature(Temperature::Warm) => write!(f, "Warm."),
+            Hint::Temperature(Temperature::Cool) => write!(f, "Cool."),
+            Hint::Temperature(Temperature::Cold) => write!(f, "Cold!"),
+            Hint::Trend(Trend::Warmer) => write!(f, "Warmer than last time."),
+            Hint::Trend(Trend::Colder) => write!(f, "Colder than last time."),
+            Hint::Trend(Trend::Same) => write!(f, "Just as far off as last time."),
+            Hint::Divisible { by: 2, divisible } => write!(f, "The number is {}.", if divisible { "even" } else { "odd" }),
+            Hint::Divisible { by, divisible } => {
+                write!(f, "The number is {}divisible by {}.", if divisible { "" } else { "not " }, by)
+            }
+        }
+    }
+}
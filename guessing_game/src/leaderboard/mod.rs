@@ -0,0 +1,205 @@
+// Results that outlive a single run.
+//
+// Every finished game is appended to a plain text file as one tab separated line:
+//
+//     player  difficulty  won  attempts  score  seconds
+//
+// Nothing is ever rewritten, so the file doubles as a history, and rankings and statistics are
+// worked out from it whenever they're asked for. Several games may finish at the same moment
+// (tournaments run on shared machines), so every write holds an exclusive lock on the file and
+// every read a shared one. The OS drops the lock when the file is closed, even if we crash.
+
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use difficulty::Difficulty;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub player: String,
+    pub difficulty: Difficulty,
+    pub won: bool,
+    pub attempts: u32,
+    pub score: u32,
+    pub seconds: u64,
+}
+
+impl Record {
+    fn to_line(&self) -> String {
+        // A tab or newline in a name would break the format, so those become spaces.
+        let player: String = self
+            .player
+            .chars()
+            .map(|c| if c == '\t' || c == '\n' || c == '\r' { ' ' } else { c })
+            .collect();
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\n",
+            player.trim(),
+            self.difficulty.name(),
+            if self.won { 1 } else { 0 },
+            self.attempts,
+            self.score,
+            self.seconds
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Record> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 6 {
+            return None;
+        }
+        Some(Record {
+            player: fields[0].to_string(),
+            difficulty: Difficulty::from_name(fields[1])?,
+            won: fields[2] == "1",
+            attempts: fields[3].parse().ok()?,
+            score: fields[4].parse().ok()?,
+            seconds: fields[5].parse().ok()?,
+        })
+    }
+}
+
+pub struct Leaderboard {
+    path: PathBuf,
+}
+
+impl Leaderboard {
+    pub fn open<P: AsRef<Path>>(path: P) -> Leaderboard {
+        Leaderboard {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn record(&self, record: &Record) -> io::Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        file.lock()?;
+        // One write_all of the whole line, so even a reader that ignores the lock never sees
+        // half a record.
+        file.write_all(record.to_line().as_bytes())?;
+        file.flush()
+    }
+
+    // All results, oldest first. A missing file just means nobody has played yet.
+    pub fn records(&self) -> io::Result<Vec<Record>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        file.lock_shared()?;
+        let mut records = Vec::new();
+        for (i, line) in BufReader::new(&file).lines().enumerate() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            match Record::from_line(&line) {
+                Some(record) => records.push(record),
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{}: line {} is not a game result", self.path.display(), i + 1),
+                    ))
+                }
+            }
+        }
+        Ok(records)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Stats {
+    pub games: u32,
+    pub wins: u32,
+    // Over won games only; None until the player has won one.
+    pub average_attempts: Option<f64>,
+    // Longest run of consecutive wins.
+    pub best_streak: u32,
+}
+
+// Per-player statistics across every difficulty. A BTreeMap keeps the players sorted by name.
+pub fn stats(records: &[Record]) -> BTreeMap<String, Stats> {
+    let mut all: BTreeMap<String, Stats> = BTreeMap::new();
+    // Running totals that don't belong in Stats itself: attempts summed over wins, and the
+    // current streak.
+    let mut totals: BTreeMap<&str, (u32, u32)> = BTreeMap::new();
+    for record in records {
+        let stats = all.entry(record.player.clone()).or_default();
+        let (attempts, streak) = totals.entry(&record.player).or_insert((0, 0));
+        stats.games += 1;
+        if record.won {
+            stats.wins += 1;
+            *attempts += record.attempts;
+            *streak += 1;
+            stats.best_streak = stats.best_streak.max(*streak);
+            stats.average_attempts = Some(f64::from(*attempts) / f64::from(stats.wins));
+        } else {
+            *streak = 0;
+        }
+    }
+    all
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ranking {
+    pub player: String,
+    pub best_score: u32,
+    // Attempts taken in the game that scored best.
+    pub attempts: u32,
+}
+
+// Players ranked by their best score at one difficulty. Ties go to whoever needed fewer attempts,
+// then alphabetically so the order is stable.
+pub fn rankings(records: &[Record], difficulty: Difficulty) -> Vec<Ranking> {
+    let mut best: BTreeMap<&str, Ranking> = BTreeMap::new();
+    for record in records.iter().filter(|r| r.won && r.difficulty == difficulty) {
+        let candidate = Ranking {
+            player: record.player.clone(),
+            best_score: record.score,
+            attempts: record.attempts,
+        };
+        let entry = best.entry(&record.player).or_insert_with(|| candidate.clone());
+        if (candidate.best_score, std::cmp::Reverse(candidate.attempts))
+            > (entry.best_score, std::cmp::Reverse(entry.attempts))
+        {
+            *entry = candidate;
+        }
+    }
+    let mut ranked: Vec<Ranking> = best.into_values().collect();
+    ranked.sort_by(|a, b| {
+        b.best_score
+            .cmp(&a.best_score)
+            .then(a.attempts.cmp(&b.attempts))
+            .then(a.player.cmp(&b.player))
+    });
+    ranked
+}
+
+// What `--leaderboard` prints: a table per difficulty, then everyone's statistics.
+pub fn print<W: Write>(records: &[Record], mut output: W) -> io::Result<()> {
+    for &difficulty in &[Difficulty::Easy, Difficulty::Normal, Difficulty::Hard] {
+        writeln!(output, "== {} ==", difficulty.name())?;
+        let ranked = rankings(records, difficulty);
+        if ranked.is_empty() {
+            writeln!(output, "  no wins yet")?;
+        }
+        for (i, r) in ranked.iter().enumerate() {
+            writeln!(output, "{:>3}. {:<20} {:>5} ({} attempts)", i + 1, r.player, r.best_score, r.attempts)?;
+        }
+    }
+    writeln!(output, "== players ==")?;
+    for (player, s) in stats(records) {
+        let average = match s.average_attempts {
+            Some(a) => format!("{:.1}", a),
+            None => String::from("-"),
+        };
+        writeln!(
+            output,
+            "{:<20} games {:>3}  wins {:>3}  avg attempts {:>4}  best streak {}",
+            player, s.games, s.wins, average, s.best_streak
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,18 @@
+extern crate rand; // extern is how we specify external dependencies
+
+// The game itself knows nothing about stdin or stdout. `game` is a small state machine that takes
+// guesses and answers them, `console` drives it from any reader/writer pair, and the binary just
+// plugs in the real terminal and a real random number generator.
+pub mod difficulty;
+pub mod guess;
+pub mod game;
+pub mod hints;
+pub mod bulls_and_cows;
+pub mod console;
+pub mod leaderboard;
+pub mod net;
+pub mod replay;
+pub mod solver;
+
+#[cfg(test)]
+mod tests;
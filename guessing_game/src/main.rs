@@ -1,65 +1,239 @@
-extern crate rand; // extern is how we specify external dependencies
+extern crate guessing_game;
+extern crate rand;
 
-use rand::Rng; // Rng is a *Trait*
-use std::cmp::Ordering; // Ordering is an Enum. Ordering variants are Less, Greater, and Equal
+use rand::Rng;
+use std::env;
+use std::fs::{File, OpenOptions};
 use std::io; // For reading from standard input. Why not for 'println!'?
+use std::io::Write;
+use std::path::PathBuf;
+use std::process;
 
-fn main() {
-    // println! is a macro, not a function call
-    println!("Guess the number!");
-    // let creates a variable
-    // Generate a random number between 1 and 100
-    // rand::thread_rng() gives us a random number generator local to the current thread of execution and seeded by the OS
-    // If we leave the type of secret_number unspecfied, it can inferred from its later usage!!
-    // (e.g. on comparison with guess later, Rust infers the type of secret number to be u32)
-    let secret_number: u32 = rand::thread_rng().gen_range(1, 101); // gen_range function is defined in the Rng trait
-    loop {
-        println!("Please input your guess.");
-        let mut guess = String::new(); // String type is growable and UTF-8 encoded
-        // io::stdin() returns an object of type std::io::Stdin which allows us to read from
-        // standard input
-        io::stdin().read_line(&mut guess)  // pass a mutable reference to read_line. read_line returns a Result type, which is an Enum.
-            // Result variants are Ok and Err.
-            .expect("Failed to read line"); // crash on error
+use guessing_game::difficulty::Difficulty;
+use guessing_game::hints;
+use guessing_game::leaderboard::{self, Leaderboard, Record};
+use guessing_game::net::{self, Server};
+use guessing_game::replay::{self, EventLog};
+use guessing_game::solver::{self, Binary};
 
-        // shadow variable guess
-        let guess: u32 = match guess.trim().parse() {
-            Ok(num) => num,
-            Err(error) => {
-                println!("Got error: {}", error);
-                continue;
-            }
-        };
-        println!("You guessed: {}", guess); // How to do formatted strings?
-        // match has various "arms", to which it matches the value it is provided,
-        // and executes the code associated with a matched arm.
-        match guess.cmp(&secret_number) {
-            Ordering::Less => println!("Too small!"),
-            Ordering::Greater => println!("Too big!"),
-            Ordering::Equal => {
-                println!("You win!");
-                break;
-            }
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    eprintln!("Usage: guessing_game [--difficulty easy|normal|hard] [--name NAME] [--scores FILE] [--hints MODES] [--log FILE] [--seed N]");
+    eprintln!("       guessing_game --replay FILE");
+    eprintln!("       guessing_game --variant bulls-and-cows [--difficulty easy|normal|hard]");
+    eprintln!("       guessing_game --leaderboard [--scores FILE]");
+    eprintln!("       guessing_game --solver [--difficulty easy|normal|hard]");
+    eprintln!("       guessing_game --benchmark GAMES [--seed N] [--difficulty easy|normal|hard]");
+    eprintln!("       guessing_game server [ADDR] [--players N] [--rounds N] [--difficulty easy|normal|hard]");
+    eprintln!("       guessing_game client [ADDR] [--name NAME]");
+    process::exit(1);
+}
+
+// Where the server listens and the client connects unless told otherwise.
+const DEFAULT_ADDR: &str = "127.0.0.1:7878";
+
+fn parse_difficulty(name: Option<String>) -> Difficulty {
+    match name.as_ref().and_then(|name| Difficulty::from_name(name)) {
+        Some(d) => d,
+        None => usage_error("--difficulty must be one of easy, normal or hard"),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: Option<String>) -> T {
+    match value.and_then(|n| n.parse().ok()) {
+        Some(n) => n,
+        None => usage_error(&format!("{} needs a number", flag)),
+    }
+}
+
+fn run_server<I: Iterator<Item = String>>(mut args: I) {
+    let mut addr = String::from(DEFAULT_ADDR);
+    let mut difficulty = Difficulty::default();
+    let mut players = 2;
+    let mut rounds = 3;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--difficulty" | "-d" => difficulty = parse_difficulty(args.next()),
+            "--players" => players = parse_number(&arg, args.next()),
+            "--rounds" => rounds = parse_number(&arg, args.next()),
+            _ if !arg.starts_with('-') => addr = arg,
+            _ => usage_error(&format!("Unknown argument {}", arg)),
+        }
+    }
+    let server = Server::bind(&addr[..], difficulty, players, rounds).unwrap_or_else(|e| {
+        eprintln!("Couldn't listen on {}: {}", addr, e);
+        process::exit(1);
+    });
+    println!("Waiting for {} players on {}", players, addr);
+    let results = server.run(&mut rand::thread_rng()).expect("Server failed");
+    for result in results {
+        match result.winner {
+            Some((name, attempts)) => println!("Round {}: {} found {} in {} guesses", result.round, name, result.secret, attempts),
+            None => println!("Round {}: nobody found {}", result.round, result.secret),
         }
     }
 }
 
-// From ch:09 on errors:
-// To ensure that the input is actually between 1 and 100.
-pub struct Guess {
-    value: u32,
+fn run_client<I: Iterator<Item = String>>(mut args: I) {
+    let mut addr = String::from(DEFAULT_ADDR);
+    let mut name = env::var("USER").unwrap_or_else(|_| String::from("anonymous"));
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--name" => name = args.next().unwrap_or_else(|| usage_error("--name needs a value")),
+            _ if !arg.starts_with('-') => addr = arg,
+            _ => usage_error(&format!("Unknown argument {}", arg)),
+        }
+    }
+    let input = io::BufReader::new(io::stdin());
+    if let Err(e) = net::play(&addr[..], &name, input, io::stdout()) {
+        eprintln!("Lost the connection to {}: {}", addr, e);
+        process::exit(1);
+    }
 }
 
-impl Guess {
-    pub fn new(value: u32) -> Guess {
-        if value < 1 || value > 100 {
-            panic!("Guess value must be between 1 and 100, got {}.", value);
+fn main() {
+    let mut args = env::args().skip(1).peekable();
+    match args.peek().map(|a| a.as_str()) {
+        Some("server") => {
+            args.next();
+            return run_server(args);
         }
+        Some("client") => {
+            args.next();
+            return run_client(args);
+        }
+        _ => {}
+    }
+    let mut difficulty = Difficulty::default();
+    let mut name = None;
+    let mut scores = None;
+    let mut show_leaderboard = false;
+    let mut computer_guesses = false;
+    let mut benchmark_games = None;
+    let mut seed: Option<u64> = None;
+    let mut log_file = None;
+    let mut replay_file = None;
+    let mut hint_modes = Vec::new();
+    let mut bulls_and_cows = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--difficulty" | "-d" => difficulty = parse_difficulty(args.next()),
+            "--name" => name = Some(args.next().unwrap_or_else(|| usage_error("--name needs a value"))),
+            "--scores" => scores = Some(PathBuf::from(args.next().unwrap_or_else(|| usage_error("--scores needs a file")))),
+            "--leaderboard" => show_leaderboard = true,
+            "--solver" => computer_guesses = true,
+            "--benchmark" => benchmark_games = Some(parse_number(&arg, args.next())),
+            "--seed" => seed = Some(parse_number(&arg, args.next())),
+            "--log" => log_file = Some(PathBuf::from(args.next().unwrap_or_else(|| usage_error("--log needs a file")))),
+            "--replay" => replay_file = Some(PathBuf::from(args.next().unwrap_or_else(|| usage_error("--replay needs a file")))),
+            // A comma separated list of temperature, trend and divisibility.
+            "--hints" => {
+                let list = args.next().unwrap_or_else(|| usage_error("--hints needs a list of modes"));
+                hint_modes = hints::parse_modes(&list).unwrap_or_else(|e| usage_error(&e));
+            }
+            "--variant" => match args.next().as_deref() {
+                Some("number") => bulls_and_cows = false,
+                Some("bulls-and-cows") => bulls_and_cows = true,
+                _ => usage_error("--variant must be number or bulls-and-cows"),
+            },
+            _ => usage_error(&format!("Unknown argument {}", arg)),
+        }
+    }
+    if let Some(path) = replay_file {
+        let log = File::open(&path).unwrap_or_else(|e| {
+            eprintln!("Couldn't open {}: {}", path.display(), e);
+            process::exit(1);
+        });
+        match replay::replay(io::BufReader::new(log)) {
+            Ok(report) => println!("Replayed {} games ({} inputs): every outcome matches.", report.games, report.inputs),
+            Err(e) => {
+                eprintln!("Replay of {} failed: {}", path.display(), e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(games) = benchmark_games {
+        let reports = solver::benchmark(difficulty.range(), games, seed.unwrap_or(42) as usize);
+        solver::print_reports(&reports, io::stdout()).expect("Failed to print report");
+        return;
+    }
+    if computer_guesses {
+        let stdin = io::stdin();
+        guessing_game::console::solve(Binary, difficulty.range(), stdin.lock(), io::stdout())
+            .expect("Failed to read line");
+        return;
+    }
+    // The leaderboard's scores are for the number game, so the code-breaking variant is played
+    // just for fun.
+    if bulls_and_cows {
+        let stdin = io::stdin();
+        println!("Bulls and cows!");
+        guessing_game::console::play_bulls_and_cows(&mut rand::thread_rng(), difficulty, stdin.lock(), io::stdout())
+            .expect("Failed to read line");
+        return;
+    }
+
+    // Scores live in the home directory unless told otherwise; point --scores at a shared file to
+    // run a tournament.
+    let scores = scores.unwrap_or_else(|| {
+        let home = env::var_os("HOME").map(PathBuf::from).unwrap_or_default();
+        home.join(".guessing_game_scores")
+    });
+    let board = Leaderboard::open(&scores);
 
-        Guess { value }
+    if show_leaderboard {
+        let records = board.records().unwrap_or_else(|e| {
+            eprintln!("Couldn't read {}: {}", scores.display(), e);
+            process::exit(1);
+        });
+        leaderboard::print(&records, io::stdout()).expect("Failed to print leaderboard");
+        return;
     }
 
-    pub fn value(&self) -> u32 {
-        self.value
+    // io::stdin() returns an object of type std::io::Stdin which allows us to read from
+    // standard input
+    let stdin = io::stdin();
+    let name = match name {
+        Some(name) => name,
+        None => {
+            println!("What's your name?");
+            let mut name = String::new();
+            stdin.read_line(&mut name).expect("Failed to read line");
+            name.trim().to_string()
+        }
+    };
+
+    // Games are always dealt from a seed, so that a logged game can be replayed. Unless one is
+    // given, the seed comes from rand::thread_rng(), a random number generator local to the
+    // current thread of execution and seeded by the OS.
+    let seed = seed.unwrap_or_else(|| rand::thread_rng().gen());
+    // Appending lets one log file collect every game played.
+    let log: Box<dyn Write> = match log_file {
+        Some(path) => Box::new(OpenOptions::new().create(true).append(true).open(&path).unwrap_or_else(|e| {
+            eprintln!("Couldn't open {}: {}", path.display(), e);
+            process::exit(1);
+        })),
+        None => Box::new(io::sink()),
+    };
+
+    // println! is a macro, not a function call
+    println!("Guess the number!");
+    let summary = guessing_game::console::play_logged(seed, difficulty, &hint_modes, &mut EventLog::new(log), stdin.lock(), io::stdout())
+        .expect("Failed to read line"); // crash on error
+
+    // A game abandoned halfway (end of input) is neither a win nor a loss, so it isn't recorded.
+    if summary.game.is_over() {
+        let record = Record {
+            player: if name.is_empty() { String::from("anonymous") } else { name },
+            difficulty,
+            won: summary.game.is_won(),
+            attempts: summary.game.attempts(),
+            score: summary.score.unwrap_or(0),
+            seconds: summary.elapsed.as_secs(),
+        };
+        if let Err(e) = board.record(&record) {
+            eprintln!("Couldn't save the result to {}: {}", scores.display(), e);
+        }
     }
 }
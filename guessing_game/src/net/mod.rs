@@ -0,0 +1,529 @@
+// Multiplayer over TCP: several players race to find the same secret number.
+//
+// The protocol is line based so it can be spoken by hand with `nc`. A client introduces itself
+// with NAME and then sends guesses; every guess gets exactly one reply, and the server also
+// broadcasts what everyone else is doing:
+//
+//     client -> server                 server -> client
+//     NAME <name>                      WELCOME <name>
+//     GUESS <round> <guess>            START <round> <low> <high> <attempts>
+//     QUIT                             TOO_SMALL <round> | TOO_BIG <round> | WIN <round>
+//                                      LOSE <round> <secret> | INVALID <round> <message>
+//                                      PROGRESS <round> <attempts> <name>
+//                                      WINNER <round> <attempts> <secret> <name>
+//                                      NOBODY <round> <secret>
+//                                      BYE
+//
+// Guesses and replies carry the round number. Without it, a guess still in flight when somebody
+// else wins would land in the next round. Names go last in a line so they may contain spaces.
+//
+// Guesses are checked by the same `Game` (and so the same `Guess` validation) as the single
+// player game; each player gets their own Game sharing the round's secret.
+
+use rand::Rng;
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread;
+
+use difficulty::Difficulty;
+use game::{Game, Outcome};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientMessage {
+    Name(String),
+    Guess { round: u32, guess: String },
+    Quit,
+}
+
+impl ClientMessage {
+    pub fn to_line(&self) -> String {
+        match *self {
+            ClientMessage::Name(ref name) => format!("NAME {}", name),
+            ClientMessage::Guess { round, ref guess } => format!("GUESS {} {}", round, guess.trim()),
+            ClientMessage::Quit => String::from("QUIT"),
+        }
+    }
+
+    pub fn parse(line: &str) -> Option<ClientMessage> {
+        let mut words = line.trim().splitn(2, ' ');
+        match (words.next()?, words.next()) {
+            ("NAME", Some(name)) if !name.trim().is_empty() => Some(ClientMessage::Name(name.trim().to_string())),
+            ("GUESS", Some(rest)) => {
+                let mut rest = rest.splitn(2, ' ');
+                let round = rest.next()?.parse().ok()?;
+                let guess = rest.next().unwrap_or("").to_string();
+                Some(ClientMessage::Guess { round, guess })
+            }
+            ("QUIT", None) => Some(ClientMessage::Quit),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerMessage {
+    Welcome(String),
+    Start { round: u32, low: u32, high: u32, attempts: u32 },
+    TooSmall(u32),
+    TooBig(u32),
+    Win(u32),
+    Lose { round: u32, secret: u32 },
+    Invalid { round: u32, message: String },
+    Progress { round: u32, attempts: u32, player: String },
+    Winner { round: u32, attempts: u32, secret: u32, player: String },
+    Nobody { round: u32, secret: u32 },
+    Bye,
+}
+
+impl ServerMessage {
+    pub fn to_line(&self) -> String {
+        use self::ServerMessage::*;
+        match *self {
+            Welcome(ref name) => format!("WELCOME {}", name),
+            Start { round, low, high, attempts } => format!("START {} {} {} {}", round, low, high, attempts),
+            TooSmall(round) => format!("TOO_SMALL {}", round),
+            TooBig(round) => format!("TOO_BIG {}", round),
+            Win(round) => format!("WIN {}", round),
+            Lose { round, secret } => format!("LOSE {} {}", round, secret),
+            Invalid { round, ref message } => format!("INVALID {} {}", round, message),
+            Progress { round, attempts, ref player } => format!("PROGRESS {} {} {}", round, attempts, player),
+            Winner { round, attempts, secret, ref player } => {
+                format!("WINNER {} {} {} {}", round, attempts, secret, player)
+            }
+            Nobody { round, secret } => format!("NOBODY {} {}", round, secret),
+            Bye => String::from("BYE"),
+        }
+    }
+
+    pub fn parse(line: &str) -> Option<ServerMessage> {
+        use self::ServerMessage::*;
+        let line = line.trim_end();
+        let (command, rest) = match line.find(' ') {
+            Some(i) => (&line[..i], &line[i + 1..]),
+            None => (line, ""),
+        };
+        // Splits off `n` numbers, leaving whatever follows (a name or a message) as text.
+        let numbers = |n: usize| -> Option<(Vec<u32>, String)> {
+            let mut parts = rest.splitn(n + 1, ' ');
+            let mut numbers = Vec::with_capacity(n);
+            for _ in 0..n {
+                numbers.push(parts.next()?.parse().ok()?);
+            }
+            Some((numbers, parts.next().unwrap_or("").to_string()))
+        };
+        Some(match command {
+            "WELCOME" => Welcome(rest.to_string()),
+            "START" => {
+                let (n, _) = numbers(4)?;
+                Start { round: n[0], low: n[1], high: n[2], attempts: n[3] }
+            }
+            "TOO_SMALL" => TooSmall(numbers(1)?.0[0]),
+            "TOO_BIG" => TooBig(numbers(1)?.0[0]),
+            "WIN" => Win(numbers(1)?.0[0]),
+            "LOSE" => {
+                let (n, _) = numbers(2)?;
+                Lose { round: n[0], secret: n[1] }
+            }
+            "INVALID" => {
+                let (n, message) = numbers(1)?;
+                Invalid { round: n[0], message }
+            }
+            "PROGRESS" => {
+                let (n, player) = numbers(2)?;
+                Progress { round: n[0], attempts: n[1], player }
+            }
+            "WINNER" => {
+                let (n, player) = numbers(3)?;
+                Winner { round: n[0], attempts: n[1], secret: n[2], player }
+            }
+            "NOBODY" => {
+                let (n, _) = numbers(2)?;
+                Nobody { round: n[0], secret: n[1] }
+            }
+            "BYE" => Bye,
+            _ => return None,
+        })
+    }
+
+    // How the command line client shows a message to a person.
+    pub fn describe(&self) -> String {
+        use self::ServerMessage::*;
+        match *self {
+            Welcome(ref name) => format!("Welcome, {}! Waiting for the round to start.", name),
+            Start { round, low, high, attempts } => format!(
+                "Round {}: guess a number between {} and {}. You have {} attempts.",
+                round, low, high, attempts
+            ),
+            TooSmall(_) => String::from("Too small!"),
+            TooBig(_) => String::from("Too big!"),
+            Win(_) => String::from("You win!"),
+            Lose { secret, .. } => format!("Out of attempts, you lose! The number was {}.", secret),
+            Invalid { ref message, .. } => format!("Got error: {}", message),
+            Progress { attempts, ref player, .. } => format!("{} has made {} guesses.", player, attempts),
+            Winner { attempts, secret, ref player, .. } => {
+                format!("{} found {} in {} guesses.", player, secret, attempts)
+            }
+            Nobody { secret, .. } => format!("Nobody found it. The number was {}.", secret),
+            Bye => String::from("Game over, thanks for playing!"),
+        }
+    }
+}
+
+fn send<W: Write>(mut writer: W, line: &str) -> io::Result<()> {
+    writer.write_all(line.as_bytes())?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundResult {
+    pub round: u32,
+    pub secret: u32,
+    // The winner's name and the attempts they needed.
+    pub winner: Option<(String, u32)>,
+}
+
+pub struct Server {
+    listener: TcpListener,
+    difficulty: Difficulty,
+    // How many named players must be connected before a round starts.
+    players: usize,
+    rounds: u32,
+}
+
+// What the connection threads tell the main loop.
+enum Event {
+    Joined(usize, TcpStream),
+    Line(usize, String),
+    Left(usize),
+}
+
+struct Player {
+    // None until the client has sent NAME.
+    name: Option<String>,
+    stream: TcpStream,
+    // This round's game, if they are taking part in it.
+    game: Option<Game>,
+}
+
+struct Round {
+    number: u32,
+    secret: u32,
+}
+
+impl Server {
+    pub fn bind<A: ToSocketAddrs>(addr: A, difficulty: Difficulty, players: usize, rounds: u32) -> io::Result<Server> {
+        Ok(Server {
+            listener: TcpListener::bind(addr)?,
+            difficulty,
+            players: players.max(1),
+            rounds,
+        })
+    }
+
+    // Handy after binding to port 0, which lets the OS pick a free port.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    // Hosts `rounds` rounds and returns how each one went.
+    //
+    // Every connection gets a thread that reads its lines and forwards them over a channel, so
+    // all of the game state lives on this thread and needs no locking. (The thread accepting
+    // connections stays blocked in accept() after we return; it goes away with the process.)
+    pub fn run<R: Rng>(self, rng: &mut R) -> io::Result<Vec<RoundResult>> {
+        let (events, inbox) = mpsc::channel();
+        let listener = self.listener.try_clone()?;
+        thread::spawn(move || accept(listener, events));
+
+        let mut players: BTreeMap<usize, Player> = BTreeMap::new();
+        let mut results = Vec::new();
+        let mut round: Option<Round> = None;
+        while results.len() < self.rounds as usize {
+            if round.is_none() && players.values().filter(|p| p.name.is_some()).count() >= self.players {
+                round = Some(self.start_round(rng, results.len() as u32 + 1, &mut players));
+            }
+            let event = match inbox.recv() {
+                Ok(event) => event,
+                Err(_) => break,
+            };
+            match event {
+                Event::Joined(id, stream) => {
+                    players.insert(id, Player { name: None, stream, game: None });
+                }
+                Event::Left(id) => {
+                    players.remove(&id);
+                }
+                Event::Line(id, line) => {
+                    if let Some(result) = self.handle(id, &line, &mut players, &round) {
+                        results.push(result);
+                        round = None;
+                    }
+                }
+            }
+            // The round also ends once nobody in it can still win, e.g. everyone ran out of
+            // attempts or left.
+            if let Some(r) = round.take() {
+                let still_playing = players
+                    .values()
+                    .any(|p| p.game.as_ref().is_some_and(|g| !g.is_over()));
+                if still_playing {
+                    round = Some(r);
+                } else {
+                    broadcast(&mut players, &ServerMessage::Nobody { round: r.number, secret: r.secret }, None);
+                    results.push(RoundResult { round: r.number, secret: r.secret, winner: None });
+                }
+            }
+        }
+        broadcast(&mut players, &ServerMessage::Bye, None);
+        for player in players.values() {
+            let _ = player.stream.shutdown(Shutdown::Both);
+        }
+        Ok(results)
+    }
+
+    fn start_round<R: Rng>(&self, rng: &mut R, number: u32, players: &mut BTreeMap<usize, Player>) -> Round {
+        let secret = Game::new(rng, self.difficulty).secret();
+        for player in players.values_mut().filter(|p| p.name.is_some()) {
+            player.game = Some(Game::with_secret(secret, self.difficulty));
+        }
+        let range = self.difficulty.range();
+        let start = ServerMessage::Start {
+            round: number,
+            low: *range.start(),
+            high: *range.end(),
+            attempts: self.difficulty.max_attempts(),
+        };
+        broadcast(players, &start, None);
+        Round { number, secret }
+    }
+
+    // Deals with one line from a player. Returns the result of the round if it ended.
+    fn handle(
+        &self,
+        id: usize,
+        line: &str,
+        players: &mut BTreeMap<usize, Player>,
+        round: &Option<Round>,
+    ) -> Option<RoundResult> {
+        // Lines the player sent before a QUIT can still be queued up after it.
+        if !players.contains_key(&id) {
+            return None;
+        }
+        let message = ClientMessage::parse(line);
+        let reply = |players: &mut BTreeMap<usize, Player>, message: ServerMessage| {
+            if let Some(player) = players.get_mut(&id) {
+                // A failed write means the player is gone; their reader thread will tell us.
+                let _ = send(&mut player.stream, &message.to_line());
+            }
+        };
+        let invalid = |round: u32, message: &str| ServerMessage::Invalid { round, message: message.to_string() };
+
+        match message {
+            None => reply(players, invalid(0, "unknown command")),
+            Some(ClientMessage::Quit) => {
+                if let Some(player) = players.remove(&id) {
+                    let _ = player.stream.shutdown(Shutdown::Both);
+                }
+            }
+            Some(ClientMessage::Name(name)) => {
+                if players.get(&id)?.name.is_some() {
+                    reply(players, invalid(0, "you already have a name"));
+                    return None;
+                }
+                let name = unique_name(players, &name);
+                players.get_mut(&id)?.name = Some(name.clone());
+                reply(players, ServerMessage::Welcome(name));
+            }
+            Some(ClientMessage::Guess { round: r, guess }) => {
+                let current = match *round {
+                    Some(ref current) if current.number == r => current,
+                    Some(_) => {
+                        reply(players, invalid(r, "that round is over"));
+                        return None;
+                    }
+                    None => {
+                        reply(players, invalid(r, "no round in progress"));
+                        return None;
+                    }
+                };
+                let (outcome, attempts, name) = {
+                    let player = players.get_mut(&id)?;
+                    let game = match player.game {
+                        Some(ref mut game) => game,
+                        None => {
+                            let message = if player.name.is_none() { "say NAME first" } else { "wait for the next round" };
+                            let _ = send(&mut player.stream, &invalid(r, message).to_line());
+                            return None;
+                        }
+                    };
+                    (game.submit(&guess), game.attempts(), player.name.clone().unwrap_or_default())
+                };
+                let progress = ServerMessage::Progress { round: r, attempts, player: name.clone() };
+                match outcome {
+                    Outcome::Invalid(message) => reply(players, invalid(r, &message)),
+                    Outcome::TooSmall | Outcome::TooBig => {
+                        let answer = if outcome == Outcome::TooSmall { ServerMessage::TooSmall(r) } else { ServerMessage::TooBig(r) };
+                        reply(players, answer);
+                        broadcast(players, &progress, Some(id));
+                    }
+                    Outcome::Lose => {
+                        reply(players, ServerMessage::Lose { round: r, secret: current.secret });
+                        broadcast(players, &progress, Some(id));
+                    }
+                    Outcome::Win => {
+                        reply(players, ServerMessage::Win(r));
+                        let winner = ServerMessage::Winner { round: r, attempts, secret: current.secret, player: name.clone() };
+                        broadcast(players, &winner, None);
+                        for player in players.values_mut() {
+                            player.game = None;
+                        }
+                        return Some(RoundResult { round: r, secret: current.secret, winner: Some((name, attempts)) });
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+// Sends to every named player, optionally skipping one.
+fn broadcast(players: &mut BTreeMap<usize, Player>, message: &ServerMessage, except: Option<usize>) {
+    let line = message.to_line();
+    for (&id, player) in players.iter_mut() {
+        if Some(id) != except && player.name.is_some() {
+            let _ = send(&mut player.stream, &line);
+        }
+    }
+}
+
+// Two players called "ann" become "ann" and "ann #2".
+fn unique_name(players: &BTreeMap<usize, Player>, wanted: &str) -> String {
+    let taken = |name: &str| players.values().any(|p| p.name.as_ref().is_some_and(|n| n == name));
+    let mut name = wanted.to_string();
+    let mut n = 1;
+    while taken(&name) {
+        n += 1;
+        name = format!("{} #{}", wanted, n);
+    }
+    name
+}
+
+fn accept(listener: TcpListener, events: Sender<Event>) {
+    for (id, stream) in listener.incoming().enumerate() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+        let reader = match stream.try_clone() {
+            Ok(reader) => reader,
+            Err(_) => continue,
+        };
+        // The server has finished once nobody is listening on the channel any more.
+        if events.send(Event::Joined(id, stream)).is_err() {
+            return;
+        }
+        let events = events.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                match line {
+                    Ok(line) => {
+                        if events.send(Event::Line(id, line)).is_err() {
+                            return;
+                        }
+                    }
+                    Err(_) => break,
+                }
+            }
+            let _ = events.send(Event::Left(id));
+        });
+    }
+}
+
+// A connection to a server, for programs that want to play (the command line client below, or a
+// bot in a test).
+pub struct Client {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Client {
+    pub fn connect<A: ToSocketAddrs>(addr: A, name: &str) -> io::Result<Client> {
+        let writer = TcpStream::connect(addr)?;
+        let reader = BufReader::new(writer.try_clone()?);
+        let mut client = Client { reader, writer };
+        client.send(&ClientMessage::Name(name.to_string()))?;
+        Ok(client)
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) -> io::Result<()> {
+        send(&mut self.writer, &message.to_line())
+    }
+
+    pub fn guess(&mut self, round: u32, guess: &str) -> io::Result<()> {
+        self.send(&ClientMessage::Guess { round, guess: guess.to_string() })
+    }
+
+    // The next message from the server, or None once it has hung up.
+    pub fn recv(&mut self) -> io::Result<Option<ServerMessage>> {
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            // Skip anything we don't understand rather than give up on the game.
+            if let Some(message) = ServerMessage::parse(&line) {
+                return Ok(Some(message));
+            }
+        }
+    }
+
+    // A second handle for sending, so one thread can send while another waits in recv().
+    pub fn sender(&self) -> io::Result<TcpStream> {
+        self.writer.try_clone()
+    }
+}
+
+// The interactive client: lines typed on `input` become guesses and everything the server says is
+// written to `output`. Typing and reading happen on separate threads, so other players' progress
+// shows up while you're thinking. Returns when the server says goodbye or hangs up.
+pub fn play<A, I, O>(addr: A, name: &str, input: I, mut output: O) -> io::Result<()>
+where
+    A: ToSocketAddrs,
+    I: BufRead + Send + 'static,
+    O: Write,
+{
+    let mut client = Client::connect(addr, name)?;
+    let mut sender = client.sender()?;
+    // The typing thread needs to know which round a guess is for; only the reading side sees
+    // START, so it shares the number through an atomic.
+    let round = Arc::new(AtomicUsize::new(0));
+    let typing_round = round.clone();
+    thread::spawn(move || {
+        for line in input.lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            let guess = ClientMessage::Guess { round: typing_round.load(Ordering::SeqCst) as u32, guess: line };
+            if send(&mut sender, &guess.to_line()).is_err() {
+                return;
+            }
+        }
+        let _ = send(&mut sender, &ClientMessage::Quit.to_line());
+    });
+    while let Some(message) = client.recv()? {
+        if let ServerMessage::Start { round: r, .. } = message {
+            round.store(r as usize, Ordering::SeqCst);
+        }
+        writeln!(output, "{}", message.describe())?;
+        if message == ServerMessage::Bye {
+            break;
+        }
+    }
+    Ok(())
+}
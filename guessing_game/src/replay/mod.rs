@@ -0,0 +1,237 @@
+// A record of every game played, for settling arguments afterwards.
+//
+// The log is plain text, one event per line with tab separated fields, and a file can hold any
+// number of games one after another:
+//
+//     start    <difficulty> <hint modes> <seed> <secret>
+//     input    <the line exactly as typed, with \t, \n, \r and \ escaped>
+//     guess    <value>                   or   error   <why the input was rejected>
+//     response too-small | too-big | win | lose
+//
+// The hint modes are a comma separated list, or "none". Logs written before they were recorded
+// have just the other three fields, and read as games without hints.
+//
+// A rejected input gets no response line; its error already says what the game answered. Because
+// the secret comes from a generator seeded with the logged seed, `replay` can deal the same game
+// again and push the same inputs through the engine, checking every step against the log.
+
+use rand::{SeedableRng, StdRng};
+use std::io::{self, BufRead, Write};
+
+use difficulty::Difficulty;
+use game::{Game, Outcome};
+use guess::Guess;
+use hints::{self, HintMode};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Start { difficulty: Difficulty, hints: Vec<HintMode>, seed: u64, secret: u32 },
+    Input(String),
+    Guess(u32),
+    Error(String),
+    Response(Outcome),
+}
+
+impl Event {
+    pub fn to_line(&self) -> String {
+        match *self {
+            Event::Start { difficulty, ref hints, seed, secret } => {
+                let hints = if hints.is_empty() {
+                    String::from("none")
+                } else {
+                    hints.iter().map(|mode| mode.name()).collect::<Vec<_>>().join(",")
+                };
+                format!("start\t{}\t{}\t{}\t{}", difficulty.name(), hints, seed, secret)
+            }
+            Event::Input(ref line) => format!("input\t{}", escape(line)),
+            Event::Guess(value) => format!("guess\t{}", value),
+            Event::Error(ref error) => format!("error\t{}", escape(error)),
+            Event::Response(ref outcome) => format!("response\t{}", outcome_name(outcome)),
+        }
+    }
+
+    pub fn parse(line: &str) -> Option<Event> {
+        let line = line.trim_end_matches(['\n', '\r']);
+        let mut fields = line.splitn(2, '\t');
+        let kind = fields.next()?;
+        let rest = fields.next().unwrap_or("");
+        match kind {
+            "start" => {
+                let fields: Vec<&str> = rest.split('\t').collect();
+                let (difficulty, hints, seed, secret) = match fields[..] {
+                    [difficulty, "none", seed, secret] => (difficulty, Vec::new(), seed, secret),
+                    [difficulty, hints, seed, secret] => (difficulty, hints::parse_modes(hints).ok()?, seed, secret),
+                    [difficulty, seed, secret] => (difficulty, Vec::new(), seed, secret),
+                    _ => return None,
+                };
+                Some(Event::Start {
+                    difficulty: Difficulty::from_name(difficulty)?,
+                    hints,
+                    seed: seed.parse().ok()?,
+                    secret: secret.parse().ok()?,
+                })
+            }
+            "input" => Some(Event::Input(unescape(rest))),
+            "guess" => rest.parse().ok().map(Event::Guess),
+            "error" => Some(Event::Error(unescape(rest))),
+            "response" => {
+                let outcome = match rest {
+                    "too-small" => Outcome::TooSmall,
+                    "too-big" => Outcome::TooBig,
+                    "win" => Outcome::Win,
+                    "lose" => Outcome::Lose,
+                    _ => return None,
+                };
+                Some(Event::Response(outcome))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn outcome_name(outcome: &Outcome) -> &'static str {
+    match *outcome {
+        Outcome::TooSmall => "too-small",
+        Outcome::TooBig => "too-big",
+        Outcome::Win => "win",
+        Outcome::Lose => "lose",
+        // Rejected inputs are logged as errors, never as responses.
+        Outcome::Invalid(_) => "invalid",
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+// The generator a logged game's secret is drawn from. This version of rand seeds with usizes, so
+// where those are narrower than the seed it goes in as two halves.
+pub fn rng(seed: u64) -> StdRng {
+    if cfg!(target_pointer_width = "64") {
+        SeedableRng::from_seed(&[seed as usize][..])
+    } else {
+        SeedableRng::from_seed(&[seed as usize, (seed >> 32) as usize][..])
+    }
+}
+
+// Writes events to the log as they happen. Each line is flushed straight away, so a game that is
+// killed halfway still leaves everything up to that point behind.
+pub struct EventLog<W: Write> {
+    out: W,
+}
+
+impl<W: Write> EventLog<W> {
+    pub fn new(out: W) -> EventLog<W> {
+        EventLog { out }
+    }
+
+    pub fn record(&mut self, event: &Event) -> io::Result<()> {
+        writeln!(self.out, "{}", event.to_line())?;
+        self.out.flush()
+    }
+}
+
+// An event as it reads in an error message.
+fn show(event: &Event) -> String {
+    event.to_line().replace('\t', " ")
+}
+
+// How a replay went, when everything matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Report {
+    pub games: u32,
+    pub inputs: u32,
+}
+
+// Re-runs every game in the log through the engine. Stops at the first place where the engine
+// disagrees with the log, or the log doesn't make sense, with the line number in the message.
+pub fn replay<R: BufRead>(log: R) -> Result<Report, String> {
+    let mut report = Report { games: 0, inputs: 0 };
+    let mut game: Option<Game> = None;
+    // What the engine said to the last input, still to be compared with the log.
+    let mut expected: Vec<Event> = Vec::new();
+    for (number, line) in log.lines().enumerate() {
+        let number = number + 1;
+        let line = line.map_err(|e| format!("line {}: {}", number, e))?;
+        if line.is_empty() {
+            continue;
+        }
+        let event = Event::parse(&line).ok_or_else(|| format!("line {}: not an event: {}", number, line))?;
+        match event {
+            Event::Start { difficulty, seed, secret, .. } => {
+                if let Some(next) = expected.first() {
+                    return Err(format!("line {}: the log stops before {}", number, show(next)));
+                }
+                let dealt = Game::new(&mut rng(seed), difficulty);
+                if dealt.secret() != secret {
+                    return Err(format!(
+                        "line {}: seed {} deals {}, but the log says the secret was {}",
+                        number,
+                        seed,
+                        dealt.secret(),
+                        secret
+                    ));
+                }
+                game = Some(dealt);
+                report.games += 1;
+            }
+            Event::Input(ref input) => {
+                let game = game.as_mut().ok_or_else(|| format!("line {}: input before any game started", number))?;
+                if let Some(next) = expected.first() {
+                    return Err(format!("line {}: the log skips {}", number, show(next)));
+                }
+                // The same two steps the console takes: validate, then answer.
+                expected = match Guess::parse(input, &game.range()) {
+                    Ok(guess) => {
+                        let value = guess.value();
+                        vec![Event::Guess(value), Event::Response(game.guess(guess))]
+                    }
+                    Err(error) => vec![Event::Error(error)],
+                };
+                report.inputs += 1;
+            }
+            logged => {
+                if expected.is_empty() {
+                    return Err(format!("line {}: {} doesn't follow an input", number, show(&logged)));
+                }
+                let engine = expected.remove(0);
+                if engine != logged {
+                    return Err(format!("line {}: the log says {} but the engine says {}", number, show(&logged), show(&engine)));
+                }
+            }
+        }
+    }
+    if let Some(next) = expected.first() {
+        return Err(format!("the log ends before {}", show(next)));
+    }
+    Ok(report)
+}
@@ -0,0 +1,274 @@
+// The computer plays the guesser: the human thinks of a number and answers each guess with too
+// small, too big or correct, exactly the feedback `Game` gives a human.
+//
+// The solver keeps the interval of numbers still consistent with every answer so far. A
+// `Strategy` only decides which number in that interval to try next. Because every answer narrows
+// the interval, an answer that contradicts an earlier one shows up as the interval becoming empty,
+// and the solver can point at the earlier answer it clashes with.
+
+use rand::{Rng, SeedableRng, StdRng};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+
+pub trait Strategy {
+    fn name(&self) -> &'static str;
+    // Pick a number in low..=high. Never called with an empty interval.
+    fn pick(&mut self, low: u32, high: u32) -> u32;
+}
+
+// A strategy borrowed mutably is still a strategy, which lets a caller keep one across games.
+impl<S: Strategy> Strategy for &mut S {
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn pick(&mut self, low: u32, high: u32) -> u32 {
+        (**self).pick(low, high)
+    }
+}
+
+// Halve the interval every time. Optimal: never needs more than ceil(log2(n + 1)) guesses.
+pub struct Binary;
+
+impl Strategy for Binary {
+    fn name(&self) -> &'static str {
+        "binary"
+    }
+
+    fn pick(&mut self, low: u32, high: u32) -> u32 {
+        low + (high - low) / 2
+    }
+}
+
+// Any number that is still possible.
+pub struct Random<R: Rng> {
+    rng: R,
+}
+
+impl<R: Rng> Random<R> {
+    pub fn new(rng: R) -> Random<R> {
+        Random { rng }
+    }
+}
+
+impl<R: Rng> Strategy for Random<R> {
+    fn name(&self) -> &'static str {
+        "random"
+    }
+
+    fn pick(&mut self, low: u32, high: u32) -> u32 {
+        self.rng.gen_range(low, high + 1)
+    }
+}
+
+// Cut the interval at the golden ratio instead of in the middle. Worse than binary search for
+// this game, but a good contrast in the benchmark.
+pub struct GoldenSection;
+
+impl Strategy for GoldenSection {
+    fn name(&self) -> &'static str {
+        "golden-section"
+    }
+
+    fn pick(&mut self, low: u32, high: u32) -> u32 {
+        // 1 - 1/phi
+        const CUT: f64 = 0.381_966_011_250_105;
+        low + (f64::from(high - low) * CUT).round() as u32
+    }
+}
+
+// An answer to one of the solver's guesses: how the guess compares to the secret, so Less means
+// "too small" just like in `Game`.
+pub type Answer = (u32, Ordering);
+
+// The answer just given can't be true together with an earlier one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inconsistency {
+    // None when the answer clashes with the edge of the range rather than with another answer,
+    // like "too small" for the largest number there is.
+    pub earlier: Option<Answer>,
+    pub now: Answer,
+}
+
+fn describe(answer: &Answer) -> String {
+    match answer.1 {
+        Ordering::Less => format!("{} was too small", answer.0),
+        Ordering::Greater => format!("{} was too big", answer.0),
+        Ordering::Equal => format!("{} was correct", answer.0),
+    }
+}
+
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.earlier {
+            Some(ref earlier) => write!(f, "you said {} earlier", describe(earlier)),
+            None => write!(f, "{} can't be, it's at the edge of the range", describe(&self.now)),
+        }
+    }
+}
+
+pub struct Solver<S: Strategy> {
+    strategy: S,
+    low: u32,
+    high: u32,
+    // The answers that set the current bounds, to blame when a new answer contradicts them. None
+    // while a bound is still the edge of the range.
+    low_set_by: Option<Answer>,
+    high_set_by: Option<Answer>,
+    guesses: u32,
+    solved: Option<u32>,
+}
+
+impl<S: Strategy> Solver<S> {
+    pub fn new(strategy: S, range: RangeInclusive<u32>) -> Solver<S> {
+        Solver {
+            strategy,
+            low: *range.start(),
+            high: *range.end(),
+            low_set_by: None,
+            high_set_by: None,
+            guesses: 0,
+            solved: None,
+        }
+    }
+
+    pub fn strategy(&self) -> &S {
+        &self.strategy
+    }
+
+    // The next number to try. Each call counts as a guess.
+    pub fn guess(&mut self) -> u32 {
+        self.guesses += 1;
+        self.strategy.pick(self.low, self.high)
+    }
+
+    // Feeds back the answer to `guess`. An answer that contradicts an earlier one is rejected and
+    // leaves the solver as it was, so the human can answer again.
+    pub fn answer(&mut self, guess: u32, ordering: Ordering) -> Result<(), Inconsistency> {
+        let now = (guess, ordering);
+        let clash = |earlier: Option<Answer>| Inconsistency { earlier, now };
+        match ordering {
+            Ordering::Less => {
+                if guess >= self.high {
+                    return Err(clash(self.high_set_by));
+                }
+                // An answer that doesn't narrow anything, like repeating an old one, leaves the
+                // blame where it was.
+                if guess + 1 > self.low {
+                    self.low = guess + 1;
+                    self.low_set_by = Some(now);
+                }
+            }
+            Ordering::Greater => {
+                if guess <= self.low {
+                    return Err(clash(self.low_set_by));
+                }
+                if guess - 1 < self.high {
+                    self.high = guess - 1;
+                    self.high_set_by = Some(now);
+                }
+            }
+            Ordering::Equal => {
+                if guess < self.low {
+                    return Err(clash(self.low_set_by));
+                }
+                if guess > self.high {
+                    return Err(clash(self.high_set_by));
+                }
+                self.solved = Some(guess);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn guesses(&self) -> u32 {
+        self.guesses
+    }
+
+    pub fn solved(&self) -> Option<u32> {
+        self.solved
+    }
+}
+
+// Plays a whole game against a known secret, answering honestly. Returns the number of guesses.
+pub fn solve<S: Strategy>(strategy: S, range: RangeInclusive<u32>, secret: u32) -> u32 {
+    let mut solver = Solver::new(strategy, range);
+    while solver.solved().is_none() {
+        let guess = solver.guess();
+        solver
+            .answer(guess, guess.cmp(&secret))
+            .expect("honest answers are always consistent");
+    }
+    solver.guesses()
+}
+
+pub struct Report {
+    pub strategy: &'static str,
+    // Number of guesses needed -> how many games needed that many.
+    pub distribution: BTreeMap<u32, u32>,
+}
+
+impl Report {
+    pub fn games(&self) -> u32 {
+        self.distribution.values().sum()
+    }
+
+    pub fn mean(&self) -> f64 {
+        let total: u32 = self.distribution.iter().map(|(guesses, games)| guesses * games).sum();
+        f64::from(total) / f64::from(self.games())
+    }
+
+    pub fn worst(&self) -> u32 {
+        self.distribution.keys().next_back().cloned().unwrap_or(0)
+    }
+}
+
+// Pits every strategy against the same `games` secrets, drawn from a generator seeded with `seed`
+// so that runs can be compared. The random strategy gets its own generator from the same seed.
+pub fn benchmark(range: RangeInclusive<u32>, games: u32, seed: usize) -> Vec<Report> {
+    let mut rng: StdRng = SeedableRng::from_seed(&[seed][..]);
+    let secrets: Vec<u32> = (0..games).map(|_| rng.gen_range(*range.start(), range.end() + 1)).collect();
+    let random: StdRng = SeedableRng::from_seed(&[seed, 1][..]);
+    vec![
+        run_benchmark(&mut Binary, &range, &secrets),
+        run_benchmark(&mut Random::new(random), &range, &secrets),
+        run_benchmark(&mut GoldenSection, &range, &secrets),
+    ]
+}
+
+// The strategy is borrowed rather than recreated for every game so that the random one doesn't
+// replay the same choices each time.
+fn run_benchmark<S: Strategy>(strategy: &mut S, range: &RangeInclusive<u32>, secrets: &[u32]) -> Report {
+    let mut distribution = BTreeMap::new();
+    for &secret in secrets {
+        let guesses = solve(&mut *strategy, range.clone(), secret);
+        *distribution.entry(guesses).or_insert(0) += 1;
+    }
+    Report {
+        strategy: strategy.name(),
+        distribution,
+    }
+}
+
+pub fn print_reports<W: Write>(reports: &[Report], mut output: W) -> io::Result<()> {
+    for report in reports {
+        writeln!(
+            output,
+            "{:<15} games {}  mean {:.2}  worst {}",
+            report.strategy,
+            report.games(),
+            report.mean(),
+            report.worst()
+        )?;
+        let most = report.distribution.values().cloned().max().unwrap_or(1);
+        for (guesses, games) in &report.distribution {
+            // Bars are scaled so the most common count is 40 characters wide.
+            let bar = "#".repeat(((games * 40) / most).max(1) as usize);
+            writeln!(output, "  {:>3} guesses {:>6} {}", guesses, games, bar)?;
+        }
+    }
+    Ok(())
+}
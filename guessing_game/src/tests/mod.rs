@@ -0,0 +1,470 @@
+use rand::{Rng, SeedableRng, StdRng};
+use std::io::Cursor;
+use std::time::Duration;
+
+use console::play;
+use difficulty::{score, Difficulty};
+use game::{Game, Outcome, Status};
+use guess::Guess;
+
+fn seeded() -> StdRng {
+    StdRng::from_seed(&[7, 31, 2024][..])
+}
+
+#[test]
+fn game_answers_guesses() {
+    let mut game = Game::with_secret(42, Difficulty::Normal);
+    assert_eq!(Outcome::TooSmall, game.submit("10\n"));
+    assert_eq!(Outcome::TooBig, game.guess(Guess::new(90, &game.range())));
+    assert_eq!(
+        Outcome::Invalid(String::from("invalid digit found in string")),
+        game.submit("forty two")
+    );
+    assert_eq!(
+        Outcome::Invalid(String::from("Guess value must be between 1 and 100, got 101.")),
+        game.submit("101")
+    );
+    // Neither of the invalid inputs counted.
+    assert_eq!(2, game.attempts());
+    assert_eq!(Outcome::Win, game.submit(" 42 "));
+    assert!(game.is_won());
+    assert!(matches!(game.submit("42"), Outcome::Invalid(_)));
+}
+
+#[test]
+fn guesses_follow_the_difficulty_range() {
+    let mut easy = Game::with_secret(7, Difficulty::Easy);
+    assert!(matches!(easy.submit("51"), Outcome::Invalid(_)));
+    let mut hard = Game::with_secret(700, Difficulty::Hard);
+    assert_eq!(Outcome::TooSmall, hard.submit("500"));
+}
+
+#[test]
+fn running_out_of_attempts_loses() {
+    let mut game = Game::with_secret(50, Difficulty::Easy);
+    for _ in 1..Difficulty::Easy.max_attempts() {
+        assert_eq!(Outcome::TooSmall, game.submit("1"));
+    }
+    assert_eq!(1, game.attempts_left());
+    assert_eq!(Outcome::Lose, game.submit("49"));
+    assert_eq!(Status::Lost, game.status());
+    assert_eq!(None, game.score(Duration::from_secs(1)));
+    assert!(matches!(game.submit("50"), Outcome::Invalid(_)));
+}
+
+#[test]
+fn scoring() {
+    let normal = Difficulty::Normal;
+    // First guess, instantly: all the base points plus the full time bonus.
+    assert_eq!(300, score(normal, 1, Duration::from_secs(0)));
+    // Every attempt and no time bonus left.
+    assert_eq!(200 / 9, score(normal, 9, Duration::from_secs(600)));
+    assert!(score(normal, 3, Duration::from_secs(10)) > score(normal, 4, Duration::from_secs(10)));
+    assert!(score(normal, 3, Duration::from_secs(10)) > score(normal, 3, Duration::from_secs(20)));
+    assert!(score(Difficulty::Hard, 3, Duration::from_secs(10)) > score(normal, 3, Duration::from_secs(10)));
+}
+
+#[test]
+fn seeded_games_are_repeatable() {
+    let secret = seeded().gen_range(1, 101);
+    assert_eq!(secret, Game::new(&mut seeded(), Difficulty::Normal).secret());
+}
+
+#[test]
+fn scripted_console_game() {
+    let secret = seeded().gen_range(1, 101);
+    let script = format!("abc\n0\n{}\n{}\n{}\n", secret + 100, secret, secret);
+    let mut output = Vec::new();
+    let summary = play(&mut seeded(), Difficulty::Normal, Cursor::new(script), &mut output).unwrap();
+    assert!(summary.game.is_won());
+    assert_eq!(1, summary.game.attempts());
+
+    let output = String::from_utf8(output).unwrap();
+    let expected = format!(
+        "I'm thinking of a number between 1 and 100. You have 9 attempts.\n\
+         Please input your guess.\n\
+         Got error: invalid digit found in string\n\
+         Please input your guess.\n\
+         Got error: Guess value must be between 1 and 100, got 0.\n\
+         Please input your guess.\n\
+         Got error: Guess value must be between 1 and 100, got {}.\n\
+         Please input your guess.\n\
+         You guessed: {}\n\
+         You win!\n\
+         Your score: {} (1 attempts, 0 seconds)\n",
+        secret + 100,
+        secret,
+        summary.score.unwrap()
+    );
+    assert_eq!(expected, output);
+}
+
+#[test]
+fn console_stops_at_end_of_input() {
+    let mut output = Vec::new();
+    let summary = play(&mut seeded(), Difficulty::Easy, Cursor::new(""), &mut output).unwrap();
+    assert_eq!(Status::Playing, summary.game.status());
+    assert_eq!(None, summary.score);
+    assert_eq!(
+        "I'm thinking of a number between 1 and 50. You have 10 attempts.\nPlease input your guess.\n",
+        String::from_utf8(output).unwrap()
+    );
+}
+
+#[test]
+#[should_panic(expected = "Guess value must be between 1 and 100")]
+fn guess_new_panics_out_of_range() {
+    Guess::new(200, &Difficulty::Normal.range());
+}
+
+mod leaderboard {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::thread;
+
+    use difficulty::Difficulty;
+    use leaderboard::{self, Leaderboard, Record};
+
+    // A fresh file per test, so tests running in parallel don't share one.
+    fn scratch(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("guessing_game_{}_{}", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn record(player: &str, difficulty: Difficulty, won: bool, attempts: u32, score: u32) -> Record {
+        Record {
+            player: player.to_string(),
+            difficulty,
+            won,
+            attempts,
+            score,
+            seconds: 5,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = scratch("round_trip");
+        let board = Leaderboard::open(&path);
+        assert_eq!(Vec::<Record>::new(), board.records().unwrap());
+        let ann = record("Ann\tthe great", Difficulty::Hard, true, 4, 350);
+        board.record(&ann).unwrap();
+        let records = board.records().unwrap();
+        assert_eq!("Ann the great", records[0].player);
+        assert_eq!(ann.score, records[0].score);
+        fs::write(&path, "garbage\n").unwrap();
+        assert!(board.records().is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn concurrent_writers() {
+        let path = scratch("concurrent");
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    // Each thread opens its own handle, like separate processes would.
+                    let board = Leaderboard::open(&path);
+                    for i in 0..50 {
+                        board.record(&record(&format!("player{}", t), Difficulty::Normal, i % 2 == 0, 3, 100)).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let records = Leaderboard::open(&path).records().unwrap();
+        assert_eq!(400, records.len());
+        assert_eq!(8, leaderboard::stats(&records).len());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn stats_and_rankings() {
+        let records = vec![
+            record("bob", Difficulty::Normal, true, 5, 150),
+            record("ann", Difficulty::Normal, true, 3, 220),
+            record("ann", Difficulty::Normal, true, 7, 120),
+            record("ann", Difficulty::Normal, false, 9, 0),
+            record("ann", Difficulty::Easy, true, 2, 140),
+            record("cat", Difficulty::Normal, true, 4, 150),
+        ];
+        let stats = leaderboard::stats(&records);
+        let ann = &stats["ann"];
+        assert_eq!((4, 3), (ann.games, ann.wins));
+        assert_eq!(Some(4.0), ann.average_attempts);
+        assert_eq!(2, ann.best_streak);
+
+        let normal = leaderboard::rankings(&records, Difficulty::Normal);
+        let order: Vec<(&str, u32)> = normal.iter().map(|r| (r.player.as_str(), r.best_score)).collect();
+        // cat and bob tie on score; cat needed fewer attempts.
+        assert_eq!(vec![("ann", 220), ("cat", 150), ("bob", 150)], order);
+        assert!(leaderboard::rankings(&records, Difficulty::Hard).is_empty());
+
+        let mut printed = Vec::new();
+        leaderboard::print(&records, &mut printed).unwrap();
+        let printed = String::from_utf8(printed).unwrap();
+        assert!(printed.contains("== hard ==\n  no wins yet\n"));
+        assert!(printed.contains("  1. ann                    220 (3 attempts)"));
+    }
+}
+
+mod solver {
+    use std::cmp::Ordering;
+    use std::io::Cursor;
+
+    use console;
+    use solver::{self, Binary, GoldenSection, Inconsistency, Solver};
+
+    #[test]
+    fn binary_search_is_optimal() {
+        // 100 numbers never take more than 7 guesses, and some take exactly 7.
+        let worst = (1..=100).map(|secret| solver::solve(Binary, 1..=100, secret)).max();
+        assert_eq!(Some(7), worst);
+        let worst = (1..=1000).map(|secret| solver::solve(Binary, 1..=1000, secret)).max();
+        assert_eq!(Some(10), worst);
+        assert_eq!(1, solver::solve(GoldenSection, 1..=1, 1));
+    }
+
+    #[test]
+    fn catches_inconsistent_answers() {
+        let mut s = Solver::new(Binary, 1..=100);
+        assert_eq!(50, s.guess());
+        s.answer(50, Ordering::Greater).unwrap();
+        assert_eq!(25, s.guess());
+        s.answer(25, Ordering::Less).unwrap();
+        let guess = s.guess();
+        // Saying this is too big is fine, but 25 being correct contradicts "25 was too small".
+        let err = s.answer(25, Ordering::Equal).unwrap_err();
+        assert_eq!(
+            Inconsistency { earlier: Some((25, Ordering::Less)), now: (25, Ordering::Equal) },
+            err
+        );
+        assert_eq!("you said 25 was too small earlier", err.to_string());
+        // The rejected answer changed nothing.
+        s.answer(guess, Ordering::Equal).unwrap();
+        assert_eq!(Some(guess), s.solved());
+
+        let mut s = Solver::new(Binary, 1..=100);
+        let err = s.answer(100, Ordering::Less).unwrap_err();
+        assert_eq!(None, err.earlier);
+
+        // "10 was too small" is what rules out 5; the later "3 was too small" told us nothing new.
+        let mut s = Solver::new(Binary, 1..=100);
+        s.answer(10, Ordering::Less).unwrap();
+        s.answer(3, Ordering::Less).unwrap();
+        s.answer(90, Ordering::Greater).unwrap();
+        s.answer(95, Ordering::Greater).unwrap();
+        assert_eq!(Some((10, Ordering::Less)), s.answer(5, Ordering::Equal).unwrap_err().earlier);
+        assert_eq!(Some((90, Ordering::Greater)), s.answer(92, Ordering::Equal).unwrap_err().earlier);
+    }
+
+    #[test]
+    fn console_solver() {
+        // Thinking of 30, the guesses go 50, 25, 37, 31, 28, 29 and 30. Answering "too small" to 30
+        // contradicts 31 being too big.
+        let script = "big\ns\nmaybe\n>\nb\ns\ns\ns\nc\n";
+        let mut output = Vec::new();
+        let found = console::solve(Binary, 1..=100, Cursor::new(script), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(Some(30), found, "{}", output);
+        assert!(output.contains("Got error: expected small, big or correct, got 'maybe'"));
+        assert!(output.contains("That can't be right: you said 31 was too big earlier."));
+        assert!(output.ends_with("Got it in 7 guesses: 30!\n"));
+    }
+
+    #[test]
+    fn benchmark_is_repeatable() {
+        let first = solver::benchmark(1..=100, 500, 9);
+        let again = solver::benchmark(1..=100, 500, 9);
+        let names: Vec<&str> = first.iter().map(|r| r.strategy).collect();
+        assert_eq!(vec!["binary", "random", "golden-section"], names);
+        for (a, b) in first.iter().zip(again.iter()) {
+            assert_eq!(a.distribution, b.distribution);
+            assert_eq!(500, a.games());
+        }
+        assert!(first[0].worst() <= 7);
+        assert!(first[0].mean() < first[1].mean());
+        assert!(first[0].mean() < first[2].mean());
+
+        let mut printed = Vec::new();
+        solver::print_reports(&first, &mut printed).unwrap();
+        assert!(String::from_utf8(printed).unwrap().starts_with("binary          games 500  mean "));
+    }
+}
+
+mod hints {
+    use rand::Rng;
+    use std::io::Cursor;
+
+    use bulls_and_cows::{self, BullsAndCows, Score};
+    use console::{play_bulls_and_cows, play_with_hints};
+    use difficulty::Difficulty;
+    use game::Game;
+    use guess::Code;
+    use hints::{hint, parse_modes, Hint, HintMode, Temperature, Trend};
+
+    #[test]
+    fn hint_modes() {
+        let range = 1..=100;
+        assert_eq!(Some(Hint::Temperature(Temperature::Hot)), hint(HintMode::Temperature, &range, 42, 45, None, 1));
+        assert_eq!(Some(Hint::Temperature(Temperature::Cold)), hint(HintMode::Temperature, &range, 42, 99, None, 1));
+        // No trend without an earlier guess to compare with.
+        assert_eq!(None, hint(HintMode::Trend, &range, 42, 45, None, 1));
+        assert_eq!(Some(Hint::Trend(Trend::Warmer)), hint(HintMode::Trend, &range, 42, 45, Some(10), 2));
+        assert_eq!(Some(Hint::Trend(Trend::Same)), hint(HintMode::Trend, &range, 42, 40, Some(44), 2));
+        assert_eq!("The number is even.", hint(HintMode::Divisibility, &range, 42, 1, None, 1).unwrap().to_string());
+        assert_eq!("The number is divisible by 3.", hint(HintMode::Divisibility, &range, 42, 1, None, 2).unwrap().to_string());
+        assert_eq!("The number is not divisible by 5.", hint(HintMode::Divisibility, &range, 42, 1, None, 3).unwrap().to_string());
+        assert_eq!(Ok(vec![HintMode::Temperature, HintMode::Trend]), parse_modes("temperature, trend"));
+        assert!(parse_modes("psychic").is_err());
+    }
+
+    #[test]
+    fn console_prints_hints_after_wrong_guesses() {
+        let mut game = Game::with_secret(42, Difficulty::Normal);
+        assert!(game.hints(&[HintMode::Temperature]).is_empty());
+        game.submit("90");
+        assert_eq!(vec![Hint::Temperature(Temperature::Cold)], game.hints(&[HintMode::Temperature, HintMode::Trend]));
+
+        let secret: u32 = super::seeded().gen_range(1, 101);
+        let wrong = if secret == 1 { 2 } else { 1 };
+        let script = format!("{}\n{}\n", wrong, secret);
+        let mut output = Vec::new();
+        let modes = [HintMode::Divisibility];
+        play_with_hints(&mut super::seeded(), Difficulty::Normal, &modes, Cursor::new(script), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let answer = if wrong < secret { "Too small!" } else { "Too big!" };
+        let parity = if secret.is_multiple_of(2) { "even" } else { "odd" };
+        assert!(output.contains(&format!("You guessed: {}\n{}\nThe number is {}.\n", wrong, answer, parity)));
+        // Nothing more once it's won.
+        assert!(output.contains("You win!\nYour score"));
+    }
+
+    #[test]
+    fn codes_share_guess_validation() {
+        assert_eq!(vec![0, 1, 2, 3], Code::parse("0123\n", 4).unwrap().digits().to_vec());
+        assert_eq!(Err(String::from("invalid digit found in string")), Code::parse("12a4", 4));
+        assert_eq!(Err(String::from("invalid digit found in string")), Code::parse("+123", 4));
+        assert_eq!(Err(String::from("Guess value must be between 0 and 9999, got 12345.")), Code::parse("12345", 4));
+        assert_eq!(Err(String::from("Code must have 4 digits, got 3.")), Code::parse("123", 4));
+        assert_eq!(Err(String::from("Code digits must all be different, got 1 twice.")), Code::parse("1231", 4));
+    }
+
+    #[test]
+    fn bulls_and_cows() {
+        let secret = Code::new(vec![1, 2, 3, 4]);
+        assert_eq!(Score { bulls: 1, cows: 2 }, bulls_and_cows::score(&secret, &Code::new(vec![1, 3, 2, 9])));
+
+        let mut game = BullsAndCows::with_secret(secret, Difficulty::Normal);
+        assert_eq!(bulls_and_cows::Outcome::Score(Score { bulls: 0, cows: 4 }), game.submit("4321"));
+        assert!(matches!(game.submit("4444"), bulls_and_cows::Outcome::Invalid(_)));
+        assert_eq!(bulls_and_cows::Outcome::Win, game.submit("1234"));
+        assert_eq!(2, game.attempts());
+
+        // The console goes through the same checks; a four digit code on easy doesn't count.
+        let script = "0123\n";
+        let mut output = Vec::new();
+        let game = play_bulls_and_cows(&mut super::seeded(), Difficulty::Easy, Cursor::new(script), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(
+            "I'm thinking of a 3-digit code, no digit twice. You have 10 attempts.\n\
+             Please input your guess.\n\
+             Got error: Code must have 3 digits, got 4.\n\
+             Please input your guess.\n",
+            output
+        );
+        assert_eq!(0, game.attempts());
+        assert!(!game.is_over());
+    }
+}
+
+mod replay {
+    use std::io::Cursor;
+
+    use console::play_logged;
+    use difficulty::Difficulty;
+    use game::Game;
+    use hints::HintMode;
+    use replay::{self, replay, Event, EventLog, Report};
+
+    // Plays seed 3 on normal with `script` and returns the log.
+    fn logged(script: &str) -> String {
+        let mut log = Vec::new();
+        play_logged(3, Difficulty::Normal, &[], &mut EventLog::new(&mut log), Cursor::new(script.to_string()), Vec::new()).unwrap();
+        String::from_utf8(log).unwrap()
+    }
+
+    #[test]
+    fn logs_replay_cleanly() {
+        let log = logged("50\nfifty\t\n25\n75\n");
+        let mut lines = log.lines();
+        assert!(lines.next().unwrap().starts_with("start\tnormal\tnone\t3\t"));
+        assert_eq!(Some("input\t50\\n"), lines.next());
+        assert_eq!(Some("guess\t50"), lines.next());
+        assert!(log.contains("input\tfifty\\t\\n\nerror\tinvalid digit found in string\n"));
+        assert_eq!(Ok(Report { games: 1, inputs: 4 }), replay(Cursor::new(log.clone())));
+        // Several games in one file, as when the log is appended to.
+        assert_eq!(Ok(Report { games: 2, inputs: 8 }), replay(Cursor::new(log.repeat(2))));
+    }
+
+    #[test]
+    fn tampering_is_caught() {
+        let secret = Game::new(&mut replay::rng(3), Difficulty::Normal).secret();
+        let wrong = if secret == 50 { 51 } else { 50 };
+        let log = logged(&format!("{}\n", wrong));
+        let start = log.lines().next().unwrap();
+
+        let claimed = log.replace(start, &format!("start\tnormal\tnone\t3\t{}", secret % 100 + 1));
+        assert!(replay(Cursor::new(claimed)).unwrap_err().starts_with("line 1: seed 3 deals"));
+
+        let forged = log.replace(log.lines().nth(3).unwrap(), "response\twin");
+        let error = replay(Cursor::new(forged)).unwrap_err();
+        assert!(error.starts_with("line 4: the log says response win but the engine says response too-"), "{}", error);
+
+        let truncated: String = log.lines().take(3).map(|l| format!("{}\n", l)).collect();
+        assert!(replay(Cursor::new(truncated)).unwrap_err().starts_with("the log ends before response"));
+    }
+
+    #[test]
+    fn events_round_trip() {
+        for event in &[
+            Event::Start { difficulty: Difficulty::Hard, hints: vec![], seed: 12, secret: 999 },
+            Event::Start {
+                difficulty: Difficulty::Easy,
+                hints: vec![HintMode::Trend, HintMode::Divisibility],
+                seed: u64::MAX,
+                secret: 1,
+            },
+            Event::Input(String::from("a\\b\tc\r\n")),
+            Event::Guess(7),
+            Event::Error(String::from("Guess value must be between 1 and 100, got 0.")),
+        ] {
+            assert_eq!(Some(event), Event::parse(&event.to_line()).as_ref());
+        }
+        assert_eq!(
+            "start\teasy\ttrend,divisibility\t7\t3",
+            Event::Start { difficulty: Difficulty::Easy, hints: vec![HintMode::Trend, HintMode::Divisibility], seed: 7, secret: 3 }.to_line()
+        );
+        // Logs from before hint modes were recorded.
+        assert_eq!(
+            Some(Event::Start { difficulty: Difficulty::Normal, hints: vec![], seed: 3, secret: 42 }),
+            Event::parse("start\tnormal\t3\t42")
+        );
+        assert_eq!(None, Event::parse("start\tnormal\tsideways\t3\t42"));
+    }
+
+    #[test]
+    fn logs_hint_modes() {
+        let mut log = Vec::new();
+        let modes = [HintMode::Temperature];
+        play_logged(3, Difficulty::Normal, &modes, &mut EventLog::new(&mut log), Cursor::new("50\n"), Vec::new()).unwrap();
+        let log = String::from_utf8(log).unwrap();
+        assert!(log.starts_with("start\tnormal\ttemperature\t3\t"), "{}", log);
+        assert_eq!(Ok(Report { games: 1, inputs: 1 }), replay(Cursor::new(log)));
+    }
+}
@@ -0,0 +1,193 @@
+// Runs a real server on loopback with two scripted players connected over TCP.
+extern crate guessing_game;
+extern crate rand;
+
+use rand::{SeedableRng, StdRng};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use guessing_game::difficulty::Difficulty;
+use guessing_game::net::{Client, ClientMessage, Server, ServerMessage};
+
+// One scripted player. The test drives both players from a single thread and waits for every
+// reply before the next guess, so the server sees the guesses in a fixed order and the run is the
+// same every time.
+struct Player {
+    client: Client,
+    heard: Vec<ServerMessage>,
+    round: u32,
+    low: u32,
+    high: u32,
+    // Out of attempts this round.
+    out: bool,
+}
+
+impl Player {
+    fn join(addr: SocketAddr, name: &str) -> Player {
+        let mut player = Player {
+            client: Client::connect(addr, name).unwrap(),
+            heard: Vec::new(),
+            round: 0,
+            low: 0,
+            high: 0,
+            out: false,
+        };
+        player.wait_for(|m| matches!(*m, ServerMessage::Welcome(_)));
+        player
+    }
+
+    // Reads (and remembers) messages up to the first one `wanted` accepts.
+    fn wait_for<F: Fn(&ServerMessage) -> bool>(&mut self, wanted: F) -> ServerMessage {
+        loop {
+            let message = self.client.recv().unwrap().expect("server hung up");
+            self.heard.push(message.clone());
+            if wanted(&message) {
+                return message;
+            }
+        }
+    }
+
+    fn start(&mut self) {
+        if let ServerMessage::Start { round, low, high, .. } = self.wait_for(|m| matches!(*m, ServerMessage::Start { .. })) {
+            self.round = round;
+            self.low = low;
+            self.high = high;
+            self.out = false;
+        }
+    }
+
+    // Sends a guess and returns the server's answer to it, narrowing the interval on the way.
+    fn guess(&mut self, guess: &str) -> ServerMessage {
+        self.client.guess(self.round, guess).unwrap();
+        let round = self.round;
+        let reply = self.wait_for(|m| match *m {
+            ServerMessage::TooSmall(r) | ServerMessage::TooBig(r) | ServerMessage::Win(r) => r == round,
+            ServerMessage::Lose { round: r, .. } | ServerMessage::Invalid { round: r, .. } => r == round,
+            _ => false,
+        });
+        let value: u32 = guess.parse().unwrap_or(0);
+        match reply {
+            ServerMessage::TooSmall(_) => self.low = value + 1,
+            ServerMessage::TooBig(_) => self.high = value - 1,
+            ServerMessage::Lose { .. } => self.out = true,
+            _ => {}
+        }
+        reply
+    }
+}
+
+fn heard_progress_from(player: &Player, name: &str) -> bool {
+    player
+        .heard
+        .iter()
+        .any(|m| matches!(*m, ServerMessage::Progress { ref player, .. } if player == name))
+}
+
+#[test]
+fn players_race_over_loopback() {
+    let server = Server::bind("127.0.0.1:0", Difficulty::Normal, 2, 2).unwrap();
+    let addr = server.local_addr().unwrap();
+    let host = thread::spawn(move || server.run(&mut StdRng::from_seed(&[5][..])).unwrap());
+
+    let mut ann = Player::join(addr, "ann");
+    let mut bob = Player::join(addr, "bob");
+    for _ in 0..2 {
+        ann.start();
+        bob.start();
+        // bob spells his first guess out, which the shared Guess validation rejects.
+        assert_eq!(
+            ServerMessage::Invalid { round: bob.round, message: String::from("invalid digit found in string") },
+            bob.guess("one")
+        );
+        // Then ann searches properly while bob creeps up one at a time, turn about.
+        loop {
+            let mid = ann.low + (ann.high - ann.low) / 2;
+            if let ServerMessage::Win(_) = ann.guess(&mid.to_string()) {
+                break;
+            }
+            if !bob.out {
+                let low = bob.low;
+                if let ServerMessage::Win(_) = bob.guess(&low.to_string()) {
+                    break;
+                }
+            }
+        }
+    }
+    ann.wait_for(|m| *m == ServerMessage::Bye);
+    bob.wait_for(|m| *m == ServerMessage::Bye);
+    let results = host.join().unwrap();
+
+    assert_eq!(2, results.len());
+    for player in [&ann, &bob].iter() {
+        // Both players are told how every round ended.
+        for result in &results {
+            let (ref name, attempts) = *result.winner.as_ref().expect("somebody always wins");
+            let ending = ServerMessage::Winner {
+                round: result.round,
+                attempts,
+                secret: result.secret,
+                player: name.clone(),
+            };
+            assert!(player.heard.contains(&ending), "missed {:?}", ending);
+        }
+    }
+    // Each saw the other's progress along the way.
+    assert!(heard_progress_from(&ann, "bob"));
+    assert!(heard_progress_from(&bob, "ann"));
+}
+
+#[test]
+fn lines_after_quit_are_ignored() {
+    let server = Server::bind("127.0.0.1:0", Difficulty::Normal, 1, 1).unwrap();
+    let addr = server.local_addr().unwrap();
+    let host = thread::spawn(move || server.run(&mut StdRng::from_seed(&[5][..])).unwrap());
+
+    // Everything in one write, so the lines after QUIT are already queued when it's handled.
+    let mut quitter = TcpStream::connect(addr).unwrap();
+    quitter.write_all(b"QUIT\nNAME x\nGUESS 1 5\n").unwrap();
+    // The server hangs up on us once it has dealt with the QUIT.
+    let mut rest = Vec::new();
+    quitter.read_to_end(&mut rest).unwrap();
+
+    // And is still there for everyone else. (Reads time out, so a dead server fails the test
+    // rather than hanging it.)
+    let mut probe = TcpStream::connect(addr).unwrap();
+    probe.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+    probe.write_all(b"NAME probe\n").unwrap();
+    let mut reader = BufReader::new(probe.try_clone().unwrap());
+    for expected in ["WELCOME", "START"].iter() {
+        let mut line = String::new();
+        reader.read_line(&mut line).expect("the server stopped answering");
+        assert!(line.starts_with(expected), "expected {}, got {:?}", expected, line);
+    }
+    // Leaving ends the round with nobody winning, and that was the only round.
+    probe.write_all(b"QUIT\n").unwrap();
+    let results = host.join().unwrap();
+    assert_eq!(1, results.len());
+    assert_eq!(None, results[0].winner);
+}
+
+#[test]
+fn protocol_lines_round_trip() {
+    let messages = vec![
+        ServerMessage::Welcome(String::from("ann #2")),
+        ServerMessage::Start { round: 1, low: 1, high: 100, attempts: 9 },
+        ServerMessage::TooSmall(1),
+        ServerMessage::Lose { round: 2, secret: 40 },
+        ServerMessage::Invalid { round: 0, message: String::from("unknown command") },
+        ServerMessage::Progress { round: 3, attempts: 2, player: String::from("bob the builder") },
+        ServerMessage::Winner { round: 3, attempts: 4, secret: 17, player: String::from("ann") },
+        ServerMessage::Nobody { round: 4, secret: 99 },
+        ServerMessage::Bye,
+    ];
+    for message in messages {
+        assert_eq!(Some(message.clone()), ServerMessage::parse(&message.to_line()));
+    }
+    assert_eq!(
+        Some(ClientMessage::Guess { round: 2, guess: String::from("42") }),
+        ClientMessage::parse("GUESS 2 42\r\n")
+    );
+    assert_eq!(None, ClientMessage::parse("GUESS two 42"));
+}
@@ -0,0 +1,215 @@
+// Text encoding detection and transcoding.
+//
+// `read_to_string` insists on UTF-8, so files written by Windows tools (UTF-16 with a byte order
+// mark) or old exports (Latin-1) fail to load. Here we read raw bytes instead and turn them into a
+// UTF-8 `String` ourselves. Nothing here needs an external crate: the standard library already
+// knows how to decode UTF-16 (`char::decode_utf16`), and Latin-1 is trivial because its 256 byte
+// values map one-to-one onto the first 256 unicode code points.
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Chain, Cursor, Read};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+impl Encoding {
+    // Parses the value given to `--encoding`. Labels are matched case-insensitively and a few
+    // common spellings are accepted.
+    pub fn from_label(label: &str) -> Option<Encoding> {
+        match label.to_lowercase().as_str() {
+            "utf-8" | "utf8" => Some(Encoding::Utf8),
+            // Without a BOM, plain "utf-16" is taken to be little endian since that is what
+            // Windows produces.
+            "utf-16" | "utf16" | "utf-16le" | "utf16le" => Some(Encoding::Utf16Le),
+            "utf-16be" | "utf16be" => Some(Encoding::Utf16Be),
+            "latin-1" | "latin1" | "iso-8859-1" | "iso8859-1" => Some(Encoding::Latin1),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match *self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16Be => "utf-16be",
+            Encoding::Latin1 => "latin-1",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct DecodeError {
+    encoding: Encoding,
+    reason: String,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "input is not valid {}: {}", self.encoding.label(), self.reason)
+    }
+}
+
+// Implementing Error lets '?' box a DecodeError into the Box<dyn Error> returned by `run`.
+impl Error for DecodeError {}
+
+// Looks for a byte order mark at the start of the input. Returns the encoding it announces along
+// with the length of the BOM so the caller can skip over it.
+pub fn sniff_bom(bytes: &[u8]) -> Option<(Encoding, usize)> {
+    if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        Some((Encoding::Utf8, 3))
+    } else if bytes.starts_with(&[0xFF, 0xFE]) {
+        Some((Encoding::Utf16Le, 2))
+    } else if bytes.starts_with(&[0xFE, 0xFF]) {
+        Some((Encoding::Utf16Be, 2))
+    } else {
+        None
+    }
+}
+
+// The encoding to decode with, and how many bytes of BOM to skip first. An explicitly requested
+// encoding wins over whatever a BOM says, and then only a BOM for that encoding is skipped: bytes
+// that merely look like a BOM for some other one are part of the text ("ÿþ" in Latin-1 is just
+// that). Without a request we go by the BOM and fall back to UTF-8.
+fn choose(head: &[u8], requested: Option<Encoding>) -> (Encoding, usize) {
+    match (requested, sniff_bom(head)) {
+        (None, Some((sniffed, len))) => (sniffed, len),
+        (None, None) => (Encoding::Utf8, 0),
+        (Some(requested), Some((sniffed, len))) if sniffed == requested => (requested, len),
+        (Some(requested), _) => (requested, 0),
+    }
+}
+
+// Decodes `bytes` into UTF-8, dropping the BOM `choose` picked out.
+pub fn decode(bytes: &[u8], requested: Option<Encoding>) -> Result<String, DecodeError> {
+    let (encoding, bom) = choose(bytes, requested);
+    decode_body(&bytes[bom..], encoding)
+}
+
+// Decodes bytes known not to start with a BOM.
+fn decode_body(body: &[u8], encoding: Encoding) -> Result<String, DecodeError> {
+    match encoding {
+        Encoding::Utf8 => String::from_utf8(body.to_vec()).map_err(|e| DecodeError {
+            encoding,
+            reason: format!("{} (try --encoding latin-1)", e),
+        }),
+        Encoding::Utf16Le => decode_utf16(body, encoding, u16::from_le_bytes),
+        Encoding::Utf16Be => decode_utf16(body, encoding, u16::from_be_bytes),
+        Encoding::Latin1 => Ok(body.iter().map(|&b| b as char).collect()),
+    }
+}
+
+fn decode_utf16(
+    body: &[u8],
+    encoding: Encoding,
+    to_unit: fn([u8; 2]) -> u16,
+) -> Result<String, DecodeError> {
+    if !body.len().is_multiple_of(2) {
+        return Err(DecodeError {
+            encoding,
+            reason: String::from("odd number of bytes"),
+        });
+    }
+    let units = body.chunks(2).map(|pair| to_unit([pair[0], pair[1]]));
+    std::char::decode_utf16(units)
+        .collect::<Result<String, _>>()
+        .map_err(|e| DecodeError {
+            encoding,
+            reason: e.to_string(),
+        })
+}
+
+// Reads lines out of any `Read`, transcoding as it goes, so a large file never has to be held in
+// memory at once. Line endings follow `str::lines`: "\n" or "\r\n", and a missing newline at the
+// end of the input is fine.
+pub struct DecodedLines<R> {
+    reader: BufReader<Chain<Cursor<Vec<u8>>, R>>,
+    encoding: Encoding,
+}
+
+impl<R: Read> DecodedLines<R> {
+    // Sniffs the BOM the same way `decode` does, which means peeking at the first few bytes.
+    // Whatever we peeked at and didn't consume is chained back in front of the reader. That's
+    // the only place a BOM can be: lines after it are decoded as they are.
+    pub fn new(mut reader: R, requested: Option<Encoding>) -> io::Result<DecodedLines<R>> {
+        let mut head = Vec::with_capacity(3);
+        (&mut reader).take(3).read_to_end(&mut head)?;
+        let (encoding, bom) = choose(&head, requested);
+        let head = head.split_off(bom);
+        Ok(DecodedLines {
+            reader: BufReader::new(Cursor::new(head).chain(reader)),
+            encoding,
+        })
+    }
+
+    // Reads the bytes of the next line, without its "\n". None at the end of the input.
+    fn next_raw(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut raw = Vec::new();
+        match self.encoding {
+            Encoding::Utf8 | Encoding::Latin1 => {
+                if self.reader.read_until(b'\n', &mut raw)? == 0 {
+                    return Ok(None);
+                }
+                if raw.last() == Some(&b'\n') {
+                    raw.pop();
+                }
+            }
+            // A UTF-16 newline is two bytes, and a 0x0A byte on its own may just be half of some
+            // other character, so go a code unit at a time.
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let newline = if self.encoding == Encoding::Utf16Le {
+                    [b'\n', 0]
+                } else {
+                    [0, b'\n']
+                };
+                loop {
+                    let mut unit = [0u8; 2];
+                    let n = (&mut self.reader).take(2).read(&mut unit)?;
+                    match n {
+                        0 if raw.is_empty() => return Ok(None),
+                        0 => break,
+                        // A short read may just mean the second byte hasn't arrived yet; it's only
+                        // an error if there is no second byte at all.
+                        1 if self.reader.read(&mut unit[1..])? == 0 => {
+                            return Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                "odd number of bytes in UTF-16 input",
+                            ));
+                        }
+                        _ => {}
+                    }
+                    if unit == newline {
+                        break;
+                    }
+                    raw.extend_from_slice(&unit);
+                }
+            }
+        }
+        Ok(Some(raw))
+    }
+}
+
+impl<R: Read> Iterator for DecodedLines<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<io::Result<String>> {
+        let raw = match self.next_raw() {
+            Ok(Some(raw)) => raw,
+            Ok(None) => return None,
+            Err(e) => return Some(Err(e)),
+        };
+        let mut line = match decode_body(&raw, self.encoding) {
+            Ok(line) => line,
+            Err(e) => return Some(Err(io::Error::new(io::ErrorKind::InvalidData, e))),
+        };
+        if line.ends_with('\r') {
+            line.pop();
+        }
+        Some(Ok(line))
+    }
+}
@@ -0,0 +1,93 @@
+// Turns the file arguments into the list of files to search. Directories are walked recursively,
+// and when globs are given (e.g. from a profile) only the files found there whose name matches
+// one of them are kept. Files named on the command line are always searched: naming one is as
+// clear a request as there is.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub fn expand(names: &[String], globs: &[String]) -> io::Result<Vec<String>> {
+    let mut files = Vec::new();
+    for name in names {
+        let path = Path::new(name);
+        if path.is_dir() {
+            walk(path, globs, &mut files)?;
+        } else {
+            files.push(name.clone());
+        }
+    }
+    if files.is_empty() {
+        let reason = if globs.is_empty() {
+            String::from("no files in")
+        } else {
+            format!("no files matching {} in", globs.join(" or "))
+        };
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} {}", reason, names.join(", "))));
+    }
+    Ok(files)
+}
+
+fn walk(dir: &Path, globs: &[String], files: &mut Vec<String>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    // read_dir makes no promise about ordering; sort so output is stable between runs.
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            walk(&path, globs, files)?;
+        } else if wanted(&path, globs) {
+            files.push(path.to_string_lossy().into_owned());
+        }
+    }
+    Ok(())
+}
+
+fn wanted(path: &Path, globs: &[String]) -> bool {
+    if globs.is_empty() {
+        return true;
+    }
+    let name = match path.file_name() {
+        Some(name) => name.to_string_lossy(),
+        None => return false,
+    };
+    globs.iter().any(|glob| glob_match(glob, &name))
+}
+
+// Shell style matching of a single file name: '*' matches any run of characters and '?' matches
+// exactly one.
+//
+// Walks both with one index each. On a '*' it remembers where it was and first lets the star match
+// nothing; when something later fails to match it goes back to the last star and lets it swallow
+// one more character. Only the last star ever needs revisiting, since whatever an earlier one
+// could swallow the later one can too, so this is O(glob * name) at worst rather than exponential.
+pub fn glob_match(glob: &str, name: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut g, mut n) = (0, 0);
+    // The position just after the last star seen, and where in the name it started matching.
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match glob.get(g) {
+            Some('*') => {
+                star = Some((g + 1, n));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                g += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((after, start)) => {
+                    star = Some((after, start + 1));
+                    g = after;
+                    n = start + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    // The name is used up; whatever is left of the glob has to be stars.
+    glob[g..].iter().all(|&c| c == '*')
+}
@@ -0,0 +1,428 @@
+// Interactive, full-screen search: `minigrep --interactive FILE...`
+//
+// The query is typed at a prompt and the list of matching lines is refreshed after every key
+// press. Up/Down (or Ctrl-P/Ctrl-N) move through the matches, and a preview pane shows the lines
+// around the selected one. Enter prints the selected location to stdout and exits; Esc or Ctrl-C
+// exits without printing anything.
+//
+// There is no terminal library here. The terminal is switched into raw mode with `stty` (so that
+// key presses arrive one at a time and aren't echoed) and the screen is drawn with plain ANSI
+// escape sequences. The UI talks to /dev/tty directly, which leaves stdout free for the result,
+// e.g. `vim $(minigrep --interactive src | cut -d: -f1)`.
+//
+// Everything apart from `run` is ordinary data in and data out, so the key decoding, the state
+// changes and the drawn frames can all be tested without a terminal.
+//
+// Each file is decoded a line at a time with `DecodedLines`, once, when the session starts. The
+// lines are kept, since every key press searches them all again and the preview shows the ones
+// around a match.
+
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+
+use unicode_width::UnicodeWidthChar;
+
+use super::{files, Config, Matcher};
+use encoding::{DecodedLines, Encoding};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Backspace,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Enter,
+    Escape,
+    Interrupt,
+}
+
+// Decodes the bytes a terminal in raw mode sends for key presses. Arrow keys arrive as escape
+// sequences (ESC [ A and friends); a lone ESC is the escape key itself. Bytes we don't understand
+// are dropped.
+pub fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let (key, len) = match bytes[i] {
+            0x1b => parse_escape(&bytes[i..]),
+            b'\r' | b'\n' => (Some(Key::Enter), 1),
+            0x7f | 0x08 => (Some(Key::Backspace), 1),
+            0x03 => (Some(Key::Interrupt), 1),
+            0x10 => (Some(Key::Up), 1),   // Ctrl-P
+            0x0e => (Some(Key::Down), 1), // Ctrl-N
+            b if b < 0x20 => (None, 1),
+            b => {
+                // Multi-byte UTF-8: the leading byte tells us how many bytes the char takes.
+                let len = match b {
+                    0xc0..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    0xf0..=0xf7 => 4,
+                    _ => 1,
+                };
+                let end = (i + len).min(bytes.len());
+                let key = std::str::from_utf8(&bytes[i..end])
+                    .ok()
+                    .and_then(|s| s.chars().next())
+                    .map(Key::Char);
+                (key, end - i)
+            }
+        };
+        if let Some(key) = key {
+            keys.push(key);
+        }
+        i += len;
+    }
+    keys
+}
+
+fn parse_escape(bytes: &[u8]) -> (Option<Key>, usize) {
+    match bytes.get(1) {
+        Some(b'[') | Some(b'O') => {}
+        _ => return (Some(Key::Escape), 1),
+    }
+    // A control sequence ends at the first byte in the range '@'..='~'.
+    let end = match bytes[2..].iter().position(|b| (0x40..=0x7e).contains(b)) {
+        Some(pos) => pos + 2,
+        None => return (None, bytes.len()),
+    };
+    let key = match &bytes[2..=end] {
+        b"A" => Some(Key::Up),
+        b"B" => Some(Key::Down),
+        b"5~" => Some(Key::PageUp),
+        b"6~" => Some(Key::PageDown),
+        _ => None,
+    };
+    (key, end + 1)
+}
+
+// A matching line: an index into the session's files and a 1-based line number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hit {
+    pub file: usize,
+    pub line_number: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Action {
+    Continue,
+    Quit,
+    // The user picked a match; this is what gets printed.
+    Select(String),
+}
+
+pub struct Session {
+    // (file name, lines) pairs.
+    files: Vec<(String, Vec<String>)>,
+    case_sensitive: bool,
+    query: String,
+    hits: Vec<Hit>,
+    selected: usize,
+}
+
+// How far PageUp/PageDown move the selection.
+const PAGE: usize = 10;
+
+impl Session {
+    pub fn new(files: Vec<(String, Vec<String>)>, case_sensitive: bool) -> Session {
+        let mut session = Session {
+            files,
+            case_sensitive,
+            query: String::new(),
+            hits: Vec::new(),
+            selected: 0,
+        };
+        session.refresh();
+        session
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn hits(&self) -> &[Hit] {
+        &self.hits
+    }
+
+    pub fn selected(&self) -> Option<Hit> {
+        self.hits.get(self.selected).cloned()
+    }
+
+    pub fn handle(&mut self, key: Key) -> Action {
+        match key {
+            Key::Char(c) => {
+                self.query.push(c);
+                self.refresh();
+            }
+            Key::Backspace => {
+                if self.query.pop().is_some() {
+                    self.refresh();
+                }
+            }
+            Key::Up => self.selected = self.selected.saturating_sub(1),
+            Key::Down => self.move_down(1),
+            Key::PageUp => self.selected = self.selected.saturating_sub(PAGE),
+            Key::PageDown => self.move_down(PAGE),
+            Key::Enter => {
+                if let Some(hit) = self.selected() {
+                    return Action::Select(self.location(hit));
+                }
+            }
+            Key::Escape | Key::Interrupt => return Action::Quit,
+        }
+        Action::Continue
+    }
+
+    fn move_down(&mut self, by: usize) {
+        if !self.hits.is_empty() {
+            self.selected = (self.selected + by).min(self.hits.len() - 1);
+        }
+    }
+
+    // Re-runs the search for the current query. The selection goes back to the first match since
+    // the old one may no longer be in the list.
+    fn refresh(&mut self) {
+        let matcher = Matcher::new(std::slice::from_ref(&self.query), self.case_sensitive);
+        let mut hits = Vec::new();
+        for (file, (_, lines)) in self.files.iter().enumerate() {
+            for (i, line) in lines.iter().enumerate() {
+                if matcher.is_match(line) {
+                    hits.push(Hit { file, line_number: i + 1 });
+                }
+            }
+        }
+        self.hits = hits;
+        self.selected = 0;
+    }
+
+    // "file:line:text", the same shape as `grep -n` output.
+    pub fn location(&self, hit: Hit) -> String {
+        format!("{}:{}:{}", self.files[hit.file].0, hit.line_number, self.line(hit))
+    }
+
+    fn line(&self, hit: Hit) -> &str {
+        self.files[hit.file].1.get(hit.line_number - 1).map_or("", |line| line.as_str())
+    }
+
+    // Draws a whole frame for a terminal of the given size. The frame starts by homing the
+    // cursor and clears each row as it goes, so it can be written straight over the last one.
+    pub fn render(&self, width: usize, height: usize) -> String {
+        // Prompt, status line and the separator above the preview take a row each. What's left is
+        // split between the list of matches and the preview.
+        let body = height.saturating_sub(3);
+        let list_rows = body.div_ceil(2);
+        let preview_rows = body - list_rows;
+
+        let mut rows = Vec::with_capacity(height);
+        rows.push(truncate(&format!("> {}", self.query), width));
+        let status = match self.hits.len() {
+            0 => String::from("no matches"),
+            n => format!("{}/{} matches", self.selected + 1, n),
+        };
+        rows.push(format!("\x1b[2m{}\x1b[0m", truncate(&status, width)));
+
+        // Scroll the list just enough to keep the selection on screen.
+        let top = (self.selected + 1).saturating_sub(list_rows);
+        for i in top..top + list_rows {
+            let row = match self.hits.get(i) {
+                Some(&hit) => {
+                    let text = truncate(&self.location(hit), width);
+                    if i == self.selected {
+                        format!("\x1b[7m{}\x1b[0m", text)
+                    } else {
+                        text
+                    }
+                }
+                None => String::new(),
+            };
+            rows.push(row);
+        }
+
+        match self.selected() {
+            Some(hit) => {
+                let title = format!("── {} ", self.files[hit.file].0);
+                let fill = width.saturating_sub(display_width(&title));
+                rows.push(truncate(&(title + &"─".repeat(fill)), width));
+                // Center the selected line in the preview where possible.
+                let lines = &self.files[hit.file].1;
+                let first = hit.line_number.saturating_sub(preview_rows / 2).max(1);
+                for number in first..first + preview_rows {
+                    let row = match lines.get(number - 1) {
+                        Some(text) => {
+                            let text = truncate(&format!("{:>5} {}", number, text), width);
+                            if number == hit.line_number {
+                                format!("\x1b[1m{}\x1b[0m", text)
+                            } else {
+                                text
+                            }
+                        }
+                        None => String::new(),
+                    };
+                    rows.push(row);
+                }
+            }
+            None => {
+                rows.push("─".repeat(width));
+                rows.extend((0..preview_rows).map(|_| String::new()));
+            }
+        }
+        rows.truncate(height);
+
+        let mut frame = String::from("\x1b[H");
+        for (i, row) in rows.iter().enumerate() {
+            frame.push_str(row);
+            // Clear whatever the previous frame left on the rest of this row.
+            frame.push_str("\x1b[K");
+            if i + 1 < rows.len() {
+                // Raw mode turns off the usual newline translation, so ask for the carriage
+                // return explicitly.
+                frame.push_str("\r\n");
+            }
+        }
+        // Leave the cursor at the end of the query.
+        let column = (display_width(&self.query) + 3).min(width.max(1));
+        frame.push_str(&format!("\x1b[1;{}H", column));
+        frame
+    }
+}
+
+// How many columns a char takes on screen: two for most CJK and emoji, none for combining marks
+// and other control characters. Tabs are drawn as a single space.
+fn char_width(c: char) -> usize {
+    match c {
+        '\t' => 1,
+        c if c.is_control() => 0,
+        c => c.width().unwrap_or(0),
+    }
+}
+
+fn display_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+// Cuts a line down to `width` columns. A wide char that would straddle the edge is dropped
+// whole. Tabs become spaces so that they can't push text past the edge of the screen, and other
+// control characters, which take no columns but could move the cursor, are left out.
+pub fn truncate(text: &str, width: usize) -> String {
+    let mut used = 0;
+    let mut cut = String::new();
+    for c in text.chars() {
+        let w = char_width(c);
+        if used + w > width {
+            break;
+        }
+        used += w;
+        match c {
+            '\t' => cut.push(' '),
+            c if c.is_control() => {}
+            c => cut.push(c),
+        }
+    }
+    cut
+}
+
+// Every line of a file, decoded on the way in.
+pub fn read_lines<R: Read>(reader: R, encoding: Option<Encoding>) -> io::Result<Vec<String>> {
+    DecodedLines::new(reader, encoding)?.collect()
+}
+
+pub fn run(config: &Config) -> Result<(), Box<dyn Error>> {
+    let mut loaded = Vec::new();
+    for filename in files::expand(&config.filenames, &config.globs)? {
+        let lines = File::open(&filename)
+            .and_then(|file| read_lines(file, config.encoding))
+            .map_err(|e| format!("{}: {}", filename, e))?;
+        loaded.push((filename, lines));
+    }
+    let mut session = Session::new(loaded, config.case_sensitive);
+
+    let mut input = File::open("/dev/tty")?;
+    let mut output = OpenOptions::new().write(true).open("/dev/tty")?;
+    let selection = {
+        // Dropping this at the end of the block puts the terminal back the way we found it, even
+        // if the event loop bailed out with an error.
+        let _raw = RawMode::enable()?;
+        // Switch to the alternate screen so the user's scrollback is untouched when we leave.
+        output.write_all(b"\x1b[?1049h")?;
+        let result = event_loop(&mut session, &mut input, &mut output);
+        output.write_all(b"\x1b[?1049l")?;
+        output.flush()?;
+        result?
+    };
+    if let Some(location) = selection {
+        println!("{}", location);
+    }
+    Ok(())
+}
+
+fn event_loop<R: Read, W: Write>(
+    session: &mut Session,
+    input: &mut R,
+    output: &mut W,
+) -> io::Result<Option<String>> {
+    let mut buf = [0u8; 64];
+    loop {
+        // Ask every time so that resizing the terminal just works.
+        let (rows, columns) = terminal_size();
+        output.write_all(session.render(columns, rows).as_bytes())?;
+        output.flush()?;
+        let n = input.read(&mut buf)?;
+        if n == 0 {
+            return Ok(None);
+        }
+        for key in parse_keys(&buf[..n]) {
+            match session.handle(key) {
+                Action::Continue => {}
+                Action::Quit => return Ok(None),
+                Action::Select(location) => return Ok(Some(location)),
+            }
+        }
+    }
+}
+
+struct RawMode {
+    // `stty -g` prints the current settings in a form stty accepts back.
+    saved: String,
+}
+
+impl RawMode {
+    fn enable() -> io::Result<RawMode> {
+        let saved = stty(&["-g"])?.trim().to_string();
+        stty(&["raw", "-echo"])?;
+        Ok(RawMode { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        // Nothing sensible to do if this fails; we're already on the way out.
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+// stty works on the terminal connected to its stdin, so hand it the tty explicitly in case our own
+// stdin is redirected.
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(File::open("/dev/tty")?)
+        .stderr(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("stty {} failed", args.join(" "))));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// (rows, columns), falling back to the classic 24x80 if stty can't tell us.
+fn terminal_size() -> (usize, usize) {
+    let size = stty(&["size"]).unwrap_or_default();
+    let mut numbers = size.split_whitespace().map(|n| n.parse::<usize>());
+    match (numbers.next(), numbers.next()) {
+        (Some(Ok(rows)), Some(Ok(columns))) if rows > 0 && columns > 0 => (rows, columns),
+        _ => (24, 80),
+    }
+}
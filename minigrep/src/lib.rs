@@ -1,59 +1,263 @@
 use std::fs::File;
+use std::io;
 use std::io::prelude::*; // Needed when dealing with I/O.
 use std::error::Error;
 use std::env; // for reading environment variables.
 
+extern crate serde_json;
+extern crate unicode_width;
+
+pub mod encoding;
+pub mod files;
+pub mod interactive;
+pub mod records;
+pub mod searcher;
+pub mod settings;
+
+use encoding::Encoding;
+use records::Format;
+use settings::Settings;
+
+pub use searcher::{Searcher, Sink};
+
 pub struct Config {
-    query: String,
-    filename: String,
+    // A line is printed if it contains any of the patterns. There is normally just one, the query
+    // given on the command line, but a profile can supply several.
+    patterns: Vec<String>,
+    filenames: Vec<String>,
+    // Only files whose names match one of these are searched. Empty means no filtering.
+    globs: Vec<String>,
     case_sensitive: bool,
+    // None means "work it out from the byte order mark, else assume UTF-8".
+    encoding: Option<Encoding>,
+    // Browse matches in a full-screen UI instead of printing them.
+    interactive: bool,
+    // When set, files are treated as records (CSV rows, JSON objects) rather than lines.
+    format: Option<Format>,
+    // Only match against this column / JSON path instead of the whole record.
+    field: Option<String>,
+    // Print only these columns / JSON paths of matching records.
+    select: Vec<String>,
+    // Lines of context to print before and after each match.
+    before_context: usize,
+    after_context: usize,
 }
 
 impl Config {
-    pub fn new(mut args: std::env::Args) -> Result<Config, &'static str> {
+    // Options are layered in increasing order of precedence: the config files, then environment
+    // variables, then the command line.
+    pub fn new<I>(args: I) -> Result<Config, String>
+    where
+        I: Iterator<Item = String>,
+    {
+        let mut settings = Settings::load()?;
+        // env::var is used to read environment variables. Here, we don't really care about the
+        // value of the environment variable as long as it's set.
+        if env::var("CASE_INSENSITIVE").is_ok() {
+            settings.case_insensitive = Some(true);
+        }
+        Config::with_settings(args, settings)
+    }
+
+    // Builds a Config from the command line on top of already loaded settings. Taking any iterator
+    // of Strings instead of std::env::Args lets tests build a Config from a plain Vec.
+    pub fn with_settings<I>(mut args: I, settings: Settings) -> Result<Config, String>
+    where
+        I: Iterator<Item = String>,
+    {
         // arg0 is the binary path. Ignore it.
         args.next();
 
-        let query = match args.next() {
-            Some(arg) => arg,
-            None => return Err("Didn't get a query string"),
-        };
+        let mut case_insensitive = settings.case_insensitive;
+        let mut encoding = settings.encoding;
+        let mut profile = None;
+        let mut interactive = false;
+        let mut format = None;
+        let mut field = None;
+        let mut select = Vec::new();
+        let mut before_context = 0;
+        let mut after_context = 0;
+        let mut positional = Vec::new();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--encoding" => {
+                    let label = flag_value(&arg, &mut args)?;
+                    encoding = match Encoding::from_label(&label) {
+                        Some(e) => Some(e),
+                        None => return Err(format!(
+                            "Unknown encoding '{}'; expected utf-8, utf-16le, utf-16be or latin-1",
+                            label
+                        )),
+                    };
+                }
+                "--profile" => {
+                    let name = flag_value(&arg, &mut args)?;
+                    profile = match settings.profiles.get(&name) {
+                        Some(p) => Some(p.clone()),
+                        None => return Err(format!("No profile named '{}' in the config files", name)),
+                    };
+                }
+                "-i" | "--ignore-case" => case_insensitive = Some(true),
+                "-s" | "--case-sensitive" => case_insensitive = Some(false),
+                "--interactive" => interactive = true,
+                "--csv" => format = Some(Format::Csv),
+                "--tsv" => format = Some(Format::Tsv),
+                "--jsonl" => format = Some(Format::Jsonl),
+                "--field" => field = Some(flag_value(&arg, &mut args)?),
+                "-A" | "--after-context" => after_context = count_value(&arg, &mut args)?,
+                "-B" | "--before-context" => before_context = count_value(&arg, &mut args)?,
+                "-C" | "--context" => {
+                    after_context = count_value(&arg, &mut args)?;
+                    before_context = after_context;
+                }
+                "--select" => {
+                    let fields = flag_value(&arg, &mut args)?;
+                    select.extend(fields.split(',').map(|f| f.trim().to_string()));
+                }
+                // Everything after a bare "--" is positional, so queries may start with '-'.
+                "--" => positional.extend(&mut args),
+                _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+                _ => positional.push(arg),
+            }
+        }
+        let mut positional = positional.into_iter();
 
-        let filename = match args.next() {
-            Some(arg) => arg,
-            None => return Err("Didn't get a file name"),
+        let (patterns, globs) = match profile {
+            // A profile that brings its own patterns leaves every positional argument as a file.
+            // In interactive mode the query is typed in later; a profile only narrows the files.
+            _ if interactive => (Vec::new(), profile.map(|p| p.globs).unwrap_or_default()),
+            Some(ref p) if !p.patterns.is_empty() => (p.patterns.clone(), p.globs.clone()),
+            _ => {
+                let query = match positional.next() {
+                    Some(arg) => arg,
+                    None => return Err(String::from("Didn't get a query string")),
+                };
+                let globs = profile.map(|p| p.globs).unwrap_or_default();
+                (vec![query], globs)
+            }
         };
 
-        // env::var is used to read environment variables. Here, we don't really care about the
-        // value of the environment variable as long as it's set.
-        let case_sensitive = env::var("CASE_INSENSITIVE").is_err();
-        Ok(Config {query, filename, case_sensitive})
+        let filenames: Vec<String> = positional.collect();
+        if filenames.is_empty() {
+            return Err(String::from("Didn't get a file name"));
+        }
+
+        if format.is_none() && (field.is_some() || !select.is_empty()) {
+            return Err(String::from("--field and --select need one of --csv, --tsv or --jsonl"));
+        }
+
+        let case_sensitive = !case_insensitive.unwrap_or(false);
+        Ok(Config {
+            patterns,
+            filenames,
+            globs,
+            case_sensitive,
+            encoding,
+            interactive,
+            format,
+            field,
+            select,
+            before_context,
+            after_context,
+        })
     }
 }
 
-pub fn run(config: Config) -> Result<(), Box<Error>> {
-    eprintln!("Searching for {}", config.query);
-    eprintln!("In file {}", config.filename);
-    // Read file filename.
-    // '?' at the end of an operation that can fail immediately returns Error from the current
-    // context.
-    // Question: How does it automatically get put into a "Box".
-    let mut f = File::open(config.filename)?;  // .expect("Failed to open file");
-    let mut contents = String::new();
-    f.read_to_string(&mut contents)?;  // .expect("something went wrong reading the file");
-    // This is similar to conditional assignments in python.
-    let results = if config.case_sensitive {
-        search(&(config.query), &contents)
-    } else {
-        search_case_insensitive(&(config.query), &contents)
-    };
-    eprintln!("With text:\n{}", contents);
-    for line in results {
-        println!("{}", line);
+fn flag_value<I>(flag: &str, args: &mut I) -> Result<String, String>
+where
+    I: Iterator<Item = String>,
+{
+    args.next().ok_or_else(|| format!("{} needs a value", flag))
+}
+
+fn count_value<I>(flag: &str, args: &mut I) -> Result<usize, String>
+where
+    I: Iterator<Item = String>,
+{
+    let value = flag_value(flag, args)?;
+    value.parse().map_err(|_| format!("{} needs a number, got '{}'", flag, value))
+}
+
+pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
+    if config.interactive {
+        return interactive::run(&config);
+    }
+    eprintln!("Searching for {}", config.patterns.join(", "));
+    let files = files::expand(&config.filenames, &config.globs)?;
+    // Like grep, only say which file a line came from when there is more than one.
+    let show_names = files.len() > 1;
+    let searcher = Searcher::new()
+        .patterns(&config.patterns)
+        .case_sensitive(config.case_sensitive)
+        .before_context(config.before_context)
+        .after_context(config.after_context)
+        .encoding(config.encoding);
+    let stdout = io::stdout();
+    for filename in files {
+        eprintln!("In file {}", filename);
+        let prefix = if show_names { Some(filename.as_str()) } else { None };
+        if let Some(format) = config.format {
+            // Records can span lines (quoted CSV fields), so these modes need the whole file.
+            let contents = read_file(&filename, config.encoding)?;
+            let matcher = Matcher::new(&config.patterns, config.case_sensitive);
+            let results = records::search(format, &contents, &matcher, config.field.as_deref(), &config.select)
+                .map_err(|e| format!("{}: {}", filename, e))?;
+            for line in results {
+                print_line(&mut stdout.lock(), prefix, &line)?;
+            }
+            continue;
+        }
+        // Read file filename.
+        // '?' at the end of an operation that can fail immediately returns Error from the current
+        // context.
+        // Question: How does it automatically get put into a "Box".
+        let f = File::open(&filename)?;  // .expect("Failed to open file");
+        let mut sink = PrintSink { out: stdout.lock(), prefix };
+        searcher.search(f, &mut sink)?;
     }
     Ok(())
 }
 
+// Prints results the way the command line always has: just the line, prefixed by the file name
+// when several files are searched. Breaks between context groups get grep's "--".
+struct PrintSink<'a, W> {
+    out: W,
+    prefix: Option<&'a str>,
+}
+
+impl<'a, W: Write> Sink for PrintSink<'a, W> {
+    fn matched(&mut self, _line_number: usize, line: &str) -> io::Result<bool> {
+        print_line(&mut self.out, self.prefix, line)?;
+        Ok(true)
+    }
+
+    fn context(&mut self, _line_number: usize, line: &str) -> io::Result<bool> {
+        print_line(&mut self.out, self.prefix, line)?;
+        Ok(true)
+    }
+
+    fn context_break(&mut self) -> io::Result<bool> {
+        writeln!(self.out, "--")?;
+        Ok(true)
+    }
+}
+
+fn print_line<W: Write>(out: &mut W, prefix: Option<&str>, line: &str) -> io::Result<()> {
+    match prefix {
+        Some(name) => writeln!(out, "{}:{}", name, line),
+        None => writeln!(out, "{}", line),
+    }
+}
+
+// Reads the whole file and transcodes it to UTF-8. We read bytes rather than using
+// read_to_string, which would reject anything that isn't already UTF-8.
+pub fn read_file(filename: &str, encoding: Option<Encoding>) -> Result<String, Box<dyn Error>> {
+    let mut f = File::open(filename)?;  // .expect("Failed to open file");
+    let mut bytes = Vec::new();
+    f.read_to_end(&mut bytes)?;  // .expect("something went wrong reading the file");
+    Ok(encoding::decode(&bytes, encoding)?)
+}
+
 // Need to specify lifetime because we are passing 2 arguments. Output should have the same
 // lifetime as that of the content.
 pub fn search<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
@@ -71,5 +275,56 @@ pub fn search_case_insensitive<'a>(query: &str, contents: &'a str) -> Vec<&'a st
         .collect()
 }
 
+// Lines containing at least one of the patterns.
+pub fn search_any<'a>(patterns: &[String], contents: &'a str, case_sensitive: bool) -> Vec<&'a str> {
+    search_numbered(patterns, contents, case_sensitive)
+        .into_iter()
+        .map(|(_, line)| line)
+        .collect()
+}
+
+// Same as search_any, but each line comes with its (1-based) line number.
+pub fn search_numbered<'a>(
+    patterns: &[String],
+    contents: &'a str,
+    case_sensitive: bool,
+) -> Vec<(usize, &'a str)> {
+    let matcher = Matcher::new(patterns, case_sensitive);
+    contents
+        .lines()
+        .enumerate()
+        .filter(|&(_, line)| matcher.is_match(line))
+        .map(|(i, line)| (i + 1, line))
+        .collect()
+}
+
+// Decides whether a piece of text contains any of a set of patterns. Patterns are lowercased once
+// up front for case-insensitive matching instead of once per line.
+pub struct Matcher {
+    patterns: Vec<String>,
+    case_sensitive: bool,
+}
+
+impl Matcher {
+    pub fn new(patterns: &[String], case_sensitive: bool) -> Matcher {
+        // This is similar to conditional assignments in python.
+        let patterns = if case_sensitive {
+            patterns.to_vec()
+        } else {
+            patterns.iter().map(|p| p.to_lowercase()).collect()
+        };
+        Matcher { patterns, case_sensitive }
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        if self.case_sensitive {
+            self.patterns.iter().any(|p| text.contains(p.as_str()))
+        } else {
+            let text = text.to_lowercase();
+            self.patterns.iter().any(|p| text.contains(p.as_str()))
+        }
+    }
+}
+
 #[cfg(test)]
 mod test;
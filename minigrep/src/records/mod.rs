@@ -0,0 +1,248 @@
+// Column-aware search over CSV, TSV and JSON Lines files.
+//
+// Grepping a line of CSV matches the query against every column at once, so searching for "500"
+// finds rows where the *size* is 500 just as happily as rows where the *status* is. In these modes
+// a file is read as a list of records instead:
+//
+//   --csv / --tsv  the first row is a header naming the columns
+//   --jsonl        every non-blank line is a JSON value
+//
+// `--field NAME` limits matching to one column (or a dotted JSON path such as `user.name` or
+// `tags.0`), and `--select A,B` prints only those fields of each matching record.
+
+use serde_json::{self, Value};
+
+use super::Matcher;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Tsv,
+    Jsonl,
+}
+
+// Returns the output lines for the records that match. For CSV and TSV the (possibly projected)
+// header comes first so that the output is itself a valid file of the same kind.
+pub fn search(
+    format: Format,
+    contents: &str,
+    matcher: &Matcher,
+    field: Option<&str>,
+    select: &[String],
+) -> Result<Vec<String>, String> {
+    match format {
+        Format::Csv => search_delimited(contents, ',', matcher, field, select),
+        Format::Tsv => search_delimited(contents, '\t', matcher, field, select),
+        Format::Jsonl => search_jsonl(contents, matcher, field, select),
+    }
+}
+
+// One row of a delimited file. `raw` is the row exactly as it appears in the file, which can
+// span several lines when a quoted field contains a newline.
+#[derive(Debug, PartialEq)]
+pub struct Row<'a> {
+    pub line_number: usize,
+    pub raw: &'a str,
+    pub fields: Vec<String>,
+}
+
+// Splits CSV-style text into rows. Fields may be wrapped in double quotes, in which case they can
+// contain the delimiter, newlines, and quotes written twice ("").
+pub fn parse_delimited(contents: &str, delimiter: char) -> Result<Vec<Row<'_>>, String> {
+    let mut rows = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut at_field_start = true;
+    let mut row_start = 0;
+    let mut line_number = 1;
+    let mut row_line = 1;
+
+    let mut chars = contents.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if in_quotes {
+            if c == '"' {
+                if let Some(&(_, '"')) = chars.peek() {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                if c == '\n' {
+                    line_number += 1;
+                }
+                field.push(c);
+            }
+            continue;
+        }
+        match c {
+            '"' if at_field_start => {
+                in_quotes = true;
+                at_field_start = false;
+            }
+            '\n' => {
+                fields.push(field.split_off(0));
+                // Blank lines are skipped rather than read as a row with one empty field.
+                if fields.len() > 1 || !fields[0].is_empty() {
+                    rows.push(Row {
+                        line_number: row_line,
+                        raw: contents[row_start..i].trim_end_matches('\r'),
+                        fields: fields.split_off(0),
+                    });
+                }
+                fields.clear();
+                at_field_start = true;
+                row_start = i + 1;
+                line_number += 1;
+                row_line = line_number;
+            }
+            // The '\r' of a "\r\n" line ending is not part of the last field.
+            '\r' if chars.peek().map(|&(_, c)| c) == Some('\n') => {}
+            c if c == delimiter => {
+                fields.push(field.split_off(0));
+                at_field_start = true;
+            }
+            c => {
+                field.push(c);
+                at_field_start = false;
+            }
+        }
+    }
+    if in_quotes {
+        return Err(format!("unterminated quoted field starting on line {}", row_line));
+    }
+    if row_start < contents.len() {
+        fields.push(field);
+        rows.push(Row {
+            line_number: row_line,
+            raw: contents[row_start..].trim_end_matches('\r'),
+            fields,
+        });
+    }
+    Ok(rows)
+}
+
+fn search_delimited(
+    contents: &str,
+    delimiter: char,
+    matcher: &Matcher,
+    field: Option<&str>,
+    select: &[String],
+) -> Result<Vec<String>, String> {
+    let mut rows = parse_delimited(contents, delimiter)?.into_iter();
+    let header = match rows.next() {
+        Some(header) => header,
+        None => return Ok(Vec::new()),
+    };
+    let column = |name: &str| {
+        header
+            .fields
+            .iter()
+            .position(|h| h == name)
+            .ok_or_else(|| format!("no column named '{}'", name))
+    };
+    let field = match field {
+        Some(name) => Some(column(name)?),
+        None => None,
+    };
+    let projection = select.iter().map(|name| column(name)).collect::<Result<Vec<_>, _>>()?;
+
+    let render = |row: &Row| -> String {
+        if projection.is_empty() {
+            row.raw.to_string()
+        } else {
+            let fields: Vec<String> = projection
+                .iter()
+                .map(|&i| quote(row.fields.get(i).map(|f| f.as_str()).unwrap_or(""), delimiter))
+                .collect();
+            fields.join(&delimiter.to_string())
+        }
+    };
+
+    let mut output = Vec::new();
+    for row in rows {
+        let matched = match field {
+            // A short row simply doesn't have the column, so it can't match.
+            Some(i) => row.fields.get(i).is_some_and(|f| matcher.is_match(f)),
+            None => row.fields.iter().any(|f| matcher.is_match(f)),
+        };
+        if matched {
+            if output.is_empty() {
+                output.push(render(&header));
+            }
+            output.push(render(&row));
+        }
+    }
+    Ok(output)
+}
+
+// Quotes a field for output if leaving it bare would change how it is read back.
+fn quote(field: &str, delimiter: char) -> String {
+    if field.contains(delimiter) || field.contains('"') || field.contains('\n') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn search_jsonl(
+    contents: &str,
+    matcher: &Matcher,
+    field: Option<&str>,
+    select: &[String],
+) -> Result<Vec<String>, String> {
+    let mut output = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: Value =
+            serde_json::from_str(line).map_err(|e| format!("line {}: {}", i + 1, e))?;
+        let matched = match field {
+            // Records in a JSON Lines file needn't share a shape; one without the field just
+            // doesn't match.
+            Some(path) => lookup(&record, path).is_some_and(|v| leaves(v).iter().any(|t| matcher.is_match(t))),
+            None => leaves(&record).iter().any(|t| matcher.is_match(t)),
+        };
+        if !matched {
+            continue;
+        }
+        if select.is_empty() {
+            output.push(line.to_string());
+        } else {
+            // Built by hand rather than through serde_json::Map so the keys keep the order they
+            // were asked for in.
+            let pairs: Vec<String> = select
+                .iter()
+                .map(|path| {
+                    let value = lookup(&record, path).cloned().unwrap_or(Value::Null);
+                    format!("{}:{}", Value::String(path.clone()), value)
+                })
+                .collect();
+            output.push(format!("{{{}}}", pairs.join(",")));
+        }
+    }
+    Ok(output)
+}
+
+// Follows a dotted path like `user.address.city` or `tags.0` into a JSON value.
+pub fn lookup<'v>(value: &'v Value, path: &str) -> Option<&'v Value> {
+    path.split('.').try_fold(value, |value, key| match *value {
+        Value::Object(ref map) => map.get(key),
+        Value::Array(ref items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => None,
+    })
+}
+
+// The scalar values inside a JSON value, as text. Strings are taken without their quotes so that
+// a query matches what a person would read; object keys are not included.
+fn leaves(value: &Value) -> Vec<String> {
+    match *value {
+        Value::Object(ref map) => map.values().flat_map(leaves).collect(),
+        Value::Array(ref items) => items.iter().flat_map(leaves).collect(),
+        Value::String(ref s) => vec![s.clone()],
+        Value::Null => Vec::new(),
+        ref other => vec![other.to_string()],
+    }
+}
@@ -0,0 +1,142 @@
+// The search engine behind the command line, for other crates to embed.
+//
+// A `Searcher` is set up once with a builder and can then search any number of inputs. It reads
+// from anything implementing `Read`, a line at a time, and hands each result to a `Sink` instead
+// of printing it, so the caller decides what happens to matches: print them, collect them, count
+// them or stop early.
+//
+//     let searcher = Searcher::new().pattern("nobody").case_sensitive(false).context(1);
+//     let mut matches: Vec<(usize, String)> = Vec::new();
+//     searcher.search(File::open("poem.txt")?, &mut matches)?;
+
+use std::collections::VecDeque;
+use std::io::{self, Read};
+
+use encoding::{DecodedLines, Encoding};
+use Matcher;
+
+// Receives the results of a search. Each method returns whether the search should keep going, so
+// a sink can stop after the first match, say. Line numbers start at 1.
+pub trait Sink {
+    fn matched(&mut self, line_number: usize, line: &str) -> io::Result<bool>;
+
+    // A line printed only because it is near a match. Ignored unless overridden.
+    fn context(&mut self, _line_number: usize, _line: &str) -> io::Result<bool> {
+        Ok(true)
+    }
+
+    // Called between two groups of context lines that aren't adjacent (grep prints "--" here).
+    fn context_break(&mut self) -> io::Result<bool> {
+        Ok(true)
+    }
+}
+
+// The simplest useful sink: collect the matching lines, ignoring context.
+impl Sink for Vec<(usize, String)> {
+    fn matched(&mut self, line_number: usize, line: &str) -> io::Result<bool> {
+        self.push((line_number, line.to_string()));
+        Ok(true)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Searcher {
+    patterns: Vec<String>,
+    case_insensitive: bool,
+    before_context: usize,
+    after_context: usize,
+    encoding: Option<Encoding>,
+}
+
+impl Searcher {
+    // Starts out case sensitive, without context, with the encoding taken from the BOM, and with
+    // no patterns at all, in which case nothing matches.
+    pub fn new() -> Searcher {
+        Searcher::default()
+    }
+
+    // Adds a pattern. A line matches if it contains any of them.
+    pub fn pattern(mut self, pattern: &str) -> Searcher {
+        self.patterns.push(pattern.to_string());
+        self
+    }
+
+    pub fn patterns(mut self, patterns: &[String]) -> Searcher {
+        self.patterns.extend_from_slice(patterns);
+        self
+    }
+
+    pub fn case_sensitive(mut self, yes: bool) -> Searcher {
+        self.case_insensitive = !yes;
+        self
+    }
+
+    // Lines of context on both sides of a match, like grep -C.
+    pub fn context(self, lines: usize) -> Searcher {
+        self.before_context(lines).after_context(lines)
+    }
+
+    pub fn before_context(mut self, lines: usize) -> Searcher {
+        self.before_context = lines;
+        self
+    }
+
+    pub fn after_context(mut self, lines: usize) -> Searcher {
+        self.after_context = lines;
+        self
+    }
+
+    // None (the default) means sniff the BOM and otherwise assume UTF-8.
+    pub fn encoding(mut self, encoding: Option<Encoding>) -> Searcher {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn search<R: Read, S: Sink>(&self, reader: R, sink: &mut S) -> io::Result<()> {
+        let matcher = Matcher::new(&self.patterns, !self.case_insensitive);
+        // The last few non-matching lines, in case the next line matches and they turn out to be
+        // before-context.
+        let mut before: VecDeque<(usize, String)> = VecDeque::with_capacity(self.before_context);
+        // How many more lines to pass on as after-context of the last match.
+        let mut after_left = 0;
+        // The last line handed to the sink, to know when a context break is needed. Breaks only
+        // make sense when there is context to separate.
+        let mut last_sent: Option<usize> = None;
+        let with_context = self.before_context > 0 || self.after_context > 0;
+
+        for (i, line) in DecodedLines::new(reader, self.encoding)?.enumerate() {
+            let line = line?;
+            let line_number = i + 1;
+            if matcher.is_match(&line) {
+                let first = before.front().map_or(line_number, |&(n, _)| n);
+                if let Some(last) = last_sent {
+                    if with_context && first > last + 1 && !sink.context_break()? {
+                        return Ok(());
+                    }
+                }
+                for (n, text) in before.drain(..) {
+                    if !sink.context(n, &text)? {
+                        return Ok(());
+                    }
+                }
+                if !sink.matched(line_number, &line)? {
+                    return Ok(());
+                }
+                last_sent = Some(line_number);
+                after_left = self.after_context;
+            } else if after_left > 0 {
+                if !sink.context(line_number, &line)? {
+                    return Ok(());
+                }
+                last_sent = Some(line_number);
+                after_left -= 1;
+            } else if self.before_context > 0 {
+                if before.len() == self.before_context {
+                    before.pop_front();
+                }
+                before.push_back((line_number, line));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+// Default options read from configuration files.
+//
+// Two files are consulted, the second overriding the first:
+//   1. ~/.config/minigrep/config (or $XDG_CONFIG_HOME/minigrep/config)
+//   2. .minigreprc in the current directory
+//
+// Both use the same line based format:
+//
+//     # comments start with '#'
+//     case_insensitive = true
+//     encoding = latin-1
+//
+//     [profile errors]
+//     pattern = ERROR
+//     pattern = panicked at
+//     glob = *.log
+//
+// Keys above the first section header are defaults; a `[profile NAME]` section collects the
+// patterns and globs that `--profile NAME` expands to.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use encoding::Encoding;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Settings {
+    // Every field is optional so that a later layer only overrides what it actually sets.
+    pub case_insensitive: Option<bool>,
+    pub encoding: Option<Encoding>,
+    pub profiles: HashMap<String, Profile>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Profile {
+    pub patterns: Vec<String>,
+    pub globs: Vec<String>,
+}
+
+impl Settings {
+    pub fn parse(text: &str) -> Result<Settings, String> {
+        let mut settings = Settings::default();
+        // Name of the profile section we are in, if any.
+        let mut section: Option<String> = None;
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                let header = line[1..line.len() - 1].trim();
+                let mut words = header.split_whitespace();
+                match (words.next(), words.next(), words.next()) {
+                    (Some("profile"), Some(name), None) => {
+                        settings.profiles.entry(name.to_string()).or_default();
+                        section = Some(name.to_string());
+                    }
+                    _ => return Err(format!("line {}: unknown section [{}]", i + 1, header)),
+                }
+                continue;
+            }
+            let (key, value) = match line.find('=') {
+                Some(pos) => (line[..pos].trim(), line[pos + 1..].trim()),
+                None => return Err(format!("line {}: expected key = value", i + 1)),
+            };
+            match section {
+                Some(ref name) => {
+                    let profile = settings.profiles.get_mut(name).unwrap();
+                    match key {
+                        "pattern" => profile.patterns.push(value.to_string()),
+                        "glob" => profile.globs.push(value.to_string()),
+                        _ => return Err(format!("line {}: unknown profile key '{}'", i + 1, key)),
+                    }
+                }
+                None => match key {
+                    "case_insensitive" => {
+                        settings.case_insensitive = Some(parse_bool(value).ok_or_else(|| {
+                            format!("line {}: expected true or false, got '{}'", i + 1, value)
+                        })?);
+                    }
+                    "encoding" => {
+                        settings.encoding = Some(Encoding::from_label(value).ok_or_else(|| {
+                            format!("line {}: unknown encoding '{}'", i + 1, value)
+                        })?);
+                    }
+                    _ => return Err(format!("line {}: unknown key '{}'", i + 1, key)),
+                },
+            }
+        }
+        Ok(settings)
+    }
+
+    // Layers `other` on top of self. Options set in `other` win, and a profile defined in both
+    // is replaced wholesale rather than having its pattern lists concatenated.
+    pub fn merge(&mut self, other: Settings) {
+        if other.case_insensitive.is_some() {
+            self.case_insensitive = other.case_insensitive;
+        }
+        if other.encoding.is_some() {
+            self.encoding = other.encoding;
+        }
+        self.profiles.extend(other.profiles);
+    }
+
+    // Reads the user's config and then the project's .minigreprc. Missing files are fine; a file
+    // that exists but can't be read or parsed is an error.
+    pub fn load() -> Result<Settings, String> {
+        let mut settings = Settings::default();
+        let mut paths = Vec::new();
+        if let Some(dir) = config_dir() {
+            paths.push(dir.join("minigrep").join("config"));
+        }
+        paths.push(PathBuf::from(".minigreprc"));
+        for path in paths {
+            if let Some(layer) = Settings::from_file(&path)? {
+                settings.merge(layer);
+            }
+        }
+        Ok(settings)
+    }
+
+    fn from_file(path: &Path) -> Result<Option<Settings>, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Settings::parse(&text)
+                .map(Some)
+                .map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+}
+
+fn config_dir() -> Option<PathBuf> {
+    match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => env::var_os("HOME").map(|home| Path::new(&home).join(".config")),
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "true" | "yes" | "1" => Some(true),
+        "false" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
@@ -25,3 +25,421 @@ Trust me.";
         );
 }
 
+
+#[test]
+fn encoding_flag() {
+    let args = vec!["minigrep", "--encoding", "Latin1", "rust", "poem.txt"];
+    let config = Config::with_settings(to_args(args), Settings::default()).unwrap();
+    assert_eq!(Some(Encoding::Latin1), config.encoding);
+    assert_eq!(vec!["rust"], config.patterns);
+    assert_eq!(vec!["poem.txt"], config.filenames);
+
+    let args = vec!["minigrep", "rust", "poem.txt", "--encoding", "ebcdic"];
+    assert!(Config::with_settings(to_args(args), Settings::default()).is_err());
+}
+
+#[test]
+fn decode_utf16_with_bom() {
+    // "Tapé" as UTF-16LE and UTF-16BE, each preceded by its BOM.
+    let le = [0xFF, 0xFE, b'T', 0, b'a', 0, b'p', 0, 0xE9, 0];
+    let be = [0xFE, 0xFF, 0, b'T', 0, b'a', 0, b'p', 0, 0xE9];
+    assert_eq!(Ok(String::from("Tapé")), encoding::decode(&le, None));
+    assert_eq!(Ok(String::from("Tapé")), encoding::decode(&be, None));
+    // An odd trailing byte can't be a UTF-16 code unit.
+    assert!(encoding::decode(&le[..9], None).is_err());
+}
+
+#[test]
+fn decode_latin1_and_utf8() {
+    let latin1 = [b'c', b'a', b'f', 0xE9];
+    assert_eq!(Ok(String::from("café")), encoding::decode(&latin1, Some(Encoding::Latin1)));
+    // The same bytes are not valid UTF-8, which is the default.
+    assert!(encoding::decode(&latin1, None).is_err());
+    // A UTF-8 BOM is stripped.
+    let utf8 = [0xEF, 0xBB, 0xBF, b'h', b'i'];
+    assert_eq!(Ok(String::from("hi")), encoding::decode(&utf8, None));
+    assert_eq!(Ok(String::from("hi")), encoding::decode(&utf8, Some(Encoding::Utf8)));
+    // But when another encoding was asked for, those bytes are text in it.
+    assert_eq!(Ok(String::from("ï»¿hi")), encoding::decode(&utf8, Some(Encoding::Latin1)));
+    assert_eq!(Ok(String::from("ÿþa")), encoding::decode(&[0xFF, 0xFE, b'a'], Some(Encoding::Latin1)));
+    assert_eq!(Ok(String::from("a")), encoding::decode(&[0xFF, 0xFE, b'a', 0], Some(Encoding::Utf16Le)));
+}
+
+#[test]
+fn bom_only_at_the_start() {
+    // A UTF-8 BOM, then a line that starts with the same bytes, which mid-file are a zero width
+    // no-break space and part of the text.
+    let bytes = b"\xEF\xBB\xBFone\n\xEF\xBB\xBFtwo\n".to_vec();
+    let lines: Vec<String> = encoding::DecodedLines::new(io::Cursor::new(bytes.clone()), Some(Encoding::Utf8))
+        .unwrap()
+        .map(|line| line.unwrap())
+        .collect();
+    assert_eq!(vec![String::from("one"), String::from("\u{FEFF}two")], lines);
+    // Asked for Latin-1, there's no BOM to drop, and all six bytes are text.
+    let lines: Vec<String> = encoding::DecodedLines::new(io::Cursor::new(bytes.clone()), Some(Encoding::Latin1))
+        .unwrap()
+        .map(|line| line.unwrap())
+        .collect();
+    assert_eq!(vec![String::from("\u{EF}\u{BB}\u{BF}one"), String::from("\u{EF}\u{BB}\u{BF}two")], lines);
+    let lines: Vec<String> = encoding::DecodedLines::new(io::Cursor::new(bytes), None)
+        .unwrap()
+        .map(|line| line.unwrap())
+        .collect();
+    assert_eq!(vec![String::from("one"), String::from("\u{FEFF}two")], lines);
+}
+
+fn to_args(args: Vec<&str>) -> std::vec::IntoIter<String> {
+    args.into_iter().map(String::from).collect::<Vec<_>>().into_iter()
+}
+
+const RC: &str = "\
+# defaults
+case_insensitive = true
+encoding = latin1
+
+[profile errors]
+pattern = ERROR
+pattern = panicked at
+glob = *.log
+";
+
+#[test]
+fn parse_settings() {
+    let settings = Settings::parse(RC).unwrap();
+    assert_eq!(Some(true), settings.case_insensitive);
+    assert_eq!(Some(Encoding::Latin1), settings.encoding);
+    let errors = &settings.profiles["errors"];
+    assert_eq!(vec!["ERROR", "panicked at"], errors.patterns);
+    assert_eq!(vec!["*.log"], errors.globs);
+
+    assert!(Settings::parse("colour = always").is_err());
+    assert!(Settings::parse("[section]").is_err());
+}
+
+#[test]
+fn settings_merge_order() {
+    let mut settings = Settings::parse(RC).unwrap();
+    settings.merge(Settings::parse("case_insensitive = false").unwrap());
+    assert_eq!(Some(false), settings.case_insensitive);
+    assert_eq!(Some(Encoding::Latin1), settings.encoding);
+
+    // Command line flags beat the config files.
+    let config = Config::with_settings(to_args(vec!["minigrep", "-i", "x", "f"]), settings.clone()).unwrap();
+    assert!(!config.case_sensitive);
+    let config = Config::with_settings(to_args(vec!["minigrep", "x", "f"]), settings).unwrap();
+    assert!(config.case_sensitive);
+}
+
+#[test]
+fn profile_expands_patterns() {
+    let settings = Settings::parse(RC).unwrap();
+    let args = to_args(vec!["minigrep", "--profile", "errors", "a.log", "b.txt"]);
+    let config = Config::with_settings(args, settings.clone()).unwrap();
+    assert_eq!(vec!["ERROR", "panicked at"], config.patterns);
+    assert_eq!(vec!["a.log", "b.txt"], config.filenames);
+    assert_eq!(vec!["*.log"], config.globs);
+
+    let args = to_args(vec!["minigrep", "--profile", "nope", "a.log"]);
+    assert!(Config::with_settings(args, settings).is_err());
+}
+
+#[test]
+fn search_any_pattern() {
+    let contents = "\
+ERROR: disk full
+thread 'main' panicked at 'oops'
+all good";
+    let patterns = vec![String::from("error"), String::from("PANICKED")];
+    assert_eq!(Vec::<&str>::new(), search_any(&patterns, contents, true));
+    assert_eq!(
+        vec!["ERROR: disk full", "thread 'main' panicked at 'oops'"],
+        search_any(&patterns, contents, false)
+    );
+}
+
+#[test]
+fn globs() {
+    assert!(files::glob_match("*.log", "app.log"));
+    assert!(files::glob_match("app-?.log", "app-1.log"));
+    assert!(!files::glob_match("*.log", "app.log.gz"));
+    assert!(files::glob_match("*", ""));
+    assert!(files::glob_match("*.tar.*", "a.tar.gz"));
+    assert!(files::glob_match("a*b*c", "axxbyybzc"));
+    assert!(!files::glob_match("a*b?", "ab"));
+    assert!(!files::glob_match("", "a"));
+    // Plenty of stars and a long name that almost matches: this took forever with backtracking.
+    let name = "a".repeat(200);
+    assert!(!files::glob_match(&"a*".repeat(30).replace("a*a*a*", "a*a*b*"), &name));
+    assert!(files::glob_match(&"a*".repeat(30), &name));
+}
+
+#[test]
+fn globs_only_filter_walked_files() {
+    use std::fs;
+
+    let dir = env::temp_dir().join(format!("minigrep_expand_{}", std::process::id()));
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    for file in &["a.log", "b.txt", "sub/c.log"] {
+        fs::write(dir.join(file), "").unwrap();
+    }
+    let path = |file: &str| dir.join(file).to_string_lossy().into_owned();
+    let globs = vec![String::from("*.log")];
+
+    // b.txt is named, so it's searched whatever the globs say.
+    let found = files::expand(&[dir.to_string_lossy().into_owned(), path("b.txt")], &globs).unwrap();
+    assert_eq!(vec![path("a.log"), path("sub/c.log"), path("b.txt")], found);
+    // A directory with nothing the globs want is an error rather than a silent no-op.
+    let error = files::expand(&[path("sub")], &[String::from("*.txt")]).unwrap_err();
+    assert!(error.to_string().starts_with("no files matching *.txt in"), "{}", error);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+mod interactive {
+    use interactive::*;
+
+    fn session() -> Session {
+        let poem = "I'm nobody! Who are you?\nAre you nobody, too?\nThen there's a pair of us!";
+        let other = "How dreary to be somebody!";
+        Session::new(
+            vec![
+                (String::from("poem.txt"), read_lines(poem.as_bytes(), None).unwrap()),
+                (String::from("other.txt"), read_lines(other.as_bytes(), None).unwrap()),
+            ],
+            false,
+        )
+    }
+
+    #[test]
+    fn lines_are_decoded() {
+        let utf16: Vec<u8> = vec![0xFF, 0xFE, b'a', 0, b'\r', 0, b'\n', 0, b'b', 0];
+        assert_eq!(vec!["a", "b"], read_lines(&utf16[..], None).unwrap());
+    }
+
+    #[test]
+    fn truncate_counts_columns() {
+        // CJK characters take two columns each, and one that doesn't fit whole is left off.
+        assert_eq!("日本", truncate("日本語", 5));
+        assert_eq!("日本語", truncate("日本語", 6));
+        // Combining marks take none.
+        assert_eq!("cafe\u{301}", truncate("cafe\u{301}!", 4));
+        assert_eq!("a b", truncate("a\tb\x1bc", 3));
+    }
+
+    #[test]
+    fn keys() {
+        assert_eq!(
+            vec![Key::Char('b'), Key::Char('é'), Key::Up, Key::Down, Key::Backspace, Key::Enter],
+            parse_keys("bé\x1b[A\x1bOB\x7f\r".as_bytes())
+        );
+        assert_eq!(vec![Key::Escape], parse_keys(b"\x1b"));
+        // Unknown sequences (here F5) are skipped whole.
+        assert_eq!(vec![Key::PageDown, Key::Char('x')], parse_keys(b"\x1b[15~\x1b[6~x"));
+    }
+
+    #[test]
+    fn typing_narrows_matches() {
+        let mut s = session();
+        // The empty query matches every line.
+        assert_eq!(4, s.hits().len());
+        for c in "BODY".chars() {
+            assert_eq!(Action::Continue, s.handle(Key::Char(c)));
+        }
+        assert_eq!("BODY", s.query());
+        assert_eq!(3, s.hits().len());
+        s.handle(Key::Down);
+        s.handle(Key::Down);
+        s.handle(Key::Down);
+        assert_eq!(
+            Action::Select(String::from("other.txt:1:How dreary to be somebody!")),
+            s.handle(Key::Enter)
+        );
+        s.handle(Key::Up);
+        s.handle(Key::Char('!'));
+        assert_eq!(Some(Hit { file: 0, line_number: 1 }), s.selected());
+        assert_eq!(Action::Quit, s.handle(Key::Escape));
+    }
+
+    #[test]
+    fn render_frame() {
+        let mut s = session();
+        s.handle(Key::Char('?'));
+        s.handle(Key::Down);
+        let frame = s.render(30, 7);
+        assert!(frame.starts_with("\x1b[H> ?\x1b[K\r\n"));
+        assert!(frame.contains("2/2 matches"));
+        // The selection is drawn in reverse video and cut to the terminal width.
+        assert!(frame.contains("\x1b[7mpoem.txt:2:Are you nobody, too\x1b[0m"));
+        assert!(frame.contains("── poem.txt ─"));
+        assert!(frame.contains("\x1b[1m    2 Are you nobody, too?\x1b[0m"));
+        assert_eq!(7, frame.split("\r\n").count());
+    }
+
+    #[test]
+    fn render_wide_text() {
+        let mut s = Session::new(vec![(String::from("jp.txt"), vec![String::from("日本語のテキスト")])], true);
+        s.handle(Key::Char('日'));
+        let frame = s.render(16, 5);
+        // "jp.txt:1:" takes 9 columns, leaving room for three of the wide characters.
+        assert!(frame.contains("\x1b[7mjp.txt:1:日本語\x1b[0m"), "{:?}", frame);
+        // The cursor goes after the query's two columns.
+        assert!(frame.ends_with("\x1b[1;5H"));
+    }
+}
+
+mod records {
+    use records::*;
+    use Matcher;
+
+    const CSV: &str = "\
+id,status,message
+1,200,ok
+2,500,\"upstream said \"\"500\"\", retrying\"
+3,404,\"not
+found\"
+";
+
+    fn matcher(query: &str) -> Matcher {
+        Matcher::new(&[String::from(query)], false)
+    }
+
+    #[test]
+    fn parse_quoted_csv() {
+        let rows = parse_delimited(CSV, ',').unwrap();
+        assert_eq!(4, rows.len());
+        assert_eq!(vec!["2", "500", "upstream said \"500\", retrying"], rows[2].fields);
+        assert_eq!(4, rows[3].line_number);
+        assert_eq!("3,404,\"not\nfound\"", rows[3].raw);
+        assert!(parse_delimited("a,\"b\n", ',').is_err());
+    }
+
+    #[test]
+    fn csv_field_and_projection() {
+        // Without --field any column can match: "2" is in row 1's status and row 2's id.
+        let all = search(Format::Csv, CSV, &matcher("2"), None, &[]).unwrap();
+        assert_eq!(3, all.len());
+        let rows = search(Format::Csv, CSV, &matcher("2"), Some("status"), &[]).unwrap();
+        assert_eq!(vec!["id,status,message", "1,200,ok"], rows);
+        let rows = search(Format::Csv, CSV, &matcher("40"), Some("status"), &[]).unwrap();
+        assert_eq!(vec!["id,status,message", "3,404,\"not\nfound\""], rows);
+        let select = vec![String::from("message"), String::from("id")];
+        let rows = search(Format::Csv, CSV, &matcher("500"), Some("status"), &select).unwrap();
+        assert_eq!(vec!["message,id", "\"upstream said \"\"500\"\", retrying\",2"], rows);
+        assert!(search(Format::Csv, CSV, &matcher("x"), Some("nope"), &[]).is_err());
+    }
+
+    #[test]
+    fn tsv() {
+        let tsv = "name\tlang\nferris\trust\ngopher\tgo\n";
+        let rows = search(Format::Tsv, tsv, &matcher("RUST"), Some("lang"), &[]).unwrap();
+        assert_eq!(vec!["name\tlang", "ferris\trust"], rows);
+    }
+
+    #[test]
+    fn jsonl_paths() {
+        let jsonl = r#"{"status": 500, "user": {"name": "ann"}, "tags": ["db"]}
+{"status": 200, "user": {"name": "bob"}, "tags": ["500s"]}
+
+{"status": 404}"#;
+        let rows = search(Format::Jsonl, jsonl, &matcher("500"), None, &[]).unwrap();
+        assert_eq!(2, rows.len());
+        let rows = search(Format::Jsonl, jsonl, &matcher("500"), Some("status"), &[]).unwrap();
+        assert_eq!(1, rows.len());
+        let select = vec![String::from("user.name"), String::from("tags.0")];
+        let rows = search(Format::Jsonl, jsonl, &matcher("db"), Some("tags"), &select).unwrap();
+        assert_eq!(vec![r#"{"user.name":"ann","tags.0":"db"}"#], rows);
+        assert!(search(Format::Jsonl, "{oops", &matcher("x"), None, &[]).is_err());
+    }
+}
+
+mod searcher {
+    use std::io::{self, Cursor};
+    use {Searcher, Sink};
+
+    const TEXT: &str = "one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\r\nnine";
+
+    // Records everything the searcher reports, in order.
+    #[derive(Default)]
+    struct Events(Vec<String>);
+
+    impl Sink for Events {
+        fn matched(&mut self, n: usize, line: &str) -> io::Result<bool> {
+            self.0.push(format!("{}:{}", n, line));
+            Ok(true)
+        }
+        fn context(&mut self, n: usize, line: &str) -> io::Result<bool> {
+            self.0.push(format!("{}-{}", n, line));
+            Ok(true)
+        }
+        fn context_break(&mut self) -> io::Result<bool> {
+            self.0.push(String::from("--"));
+            Ok(true)
+        }
+    }
+
+    #[test]
+    fn collects_matches() {
+        let mut matches: Vec<(usize, String)> = Vec::new();
+        Searcher::new()
+            .pattern("E")
+            .case_sensitive(false)
+            .search(Cursor::new(TEXT), &mut matches)
+            .unwrap();
+        let lines: Vec<&str> = matches.iter().map(|m| m.1.as_str()).collect();
+        assert_eq!(vec!["one", "three", "five", "seven", "eight", "nine"], lines);
+        assert_eq!(9, matches[5].0);
+    }
+
+    #[test]
+    fn context_and_breaks() {
+        let mut events = Events::default();
+        Searcher::new()
+            .pattern("two")
+            .pattern("eight")
+            .before_context(1)
+            .after_context(2)
+            .search(Cursor::new(TEXT), &mut events)
+            .unwrap();
+        assert_eq!(
+            vec!["1-one", "2:two", "3-three", "4-four", "--", "7-seven", "8:eight", "9-nine"],
+            events.0
+        );
+
+        let mut events = Events::default();
+        Searcher::new().pattern("two").pattern("six").search(Cursor::new(TEXT), &mut events).unwrap();
+        assert_eq!(vec!["2:two", "6:six"], events.0);
+    }
+
+    #[test]
+    fn sink_can_stop_early() {
+        struct First(Option<usize>);
+        impl Sink for First {
+            fn matched(&mut self, n: usize, _: &str) -> io::Result<bool> {
+                self.0 = Some(n);
+                Ok(false)
+            }
+        }
+        let mut first = First(None);
+        Searcher::new().pattern("e").search(Cursor::new(TEXT), &mut first).unwrap();
+        assert_eq!(Some(1), first.0);
+    }
+
+    #[test]
+    fn decodes_while_streaming() {
+        // "hi\r\nthé" in UTF-16BE with a BOM.
+        let bytes = vec![0xFE, 0xFF, 0, b'h', 0, b'i', 0, b'\r', 0, b'\n', 0, b't', 0, b'h', 0, 0xE9];
+        let mut matches: Vec<(usize, String)> = Vec::new();
+        Searcher::new().pattern("é").search(Cursor::new(bytes), &mut matches).unwrap();
+        assert_eq!(vec![(2, String::from("thé"))], matches);
+
+        let latin1 = vec![b'c', b'a', b'f', 0xE9];
+        assert!(Searcher::new().pattern("caf").search(Cursor::new(latin1.clone()), &mut matches).is_err());
+        let mut matches: Vec<(usize, String)> = Vec::new();
+        Searcher::new()
+            .pattern("caf")
+            .encoding(Some(::encoding::Encoding::Latin1))
+            .search(Cursor::new(latin1), &mut matches)
+            .unwrap();
+        assert_eq!(vec![(1, String::from("café"))], matches);
+    }
+}
@@ -0,0 +1,312 @@
+/*
+ * A canvas of character cells for components to draw on.
+ *
+ * Each cell holds one char and how to show it: colors and boldness. Drawing never writes outside
+ * the canvas, or outside the current clip rectangle, so a component handed a rectangle can't
+ * scribble over its neighbours however big it thinks it is.
+ *
+ * When everything is drawn, the canvas goes to the terminal with ANSI escape codes for the
+ * colors, or becomes a plain string (colors dropped, trailing spaces trimmed), which is what
+ * tests compare against.
+ *
+ * Every char takes one cell. Wide characters like 你 take two columns in a real terminal, so text
+ * using them will come out wider than the canvas thinks it is.
+ */
+
+use std::fmt;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Size {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Size {
+    pub fn new(width: u32, height: u32) -> Size {
+        Size { width, height }
+    }
+
+    // `self` cut down to fit in `available`, but no smaller than `least`.
+    pub fn fit(self, available: Size, least: Size) -> Size {
+        Size {
+            width: self.width.min(available.width).max(least.width),
+            height: self.height.min(available.height).max(least.height),
+        }
+    }
+}
+
+// A rectangle of cells, from its top left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Rect {
+        Rect { x, y, width, height }
+    }
+
+    // Rectangles running off the end of a u32 stop at u32::MAX.
+    pub fn right(&self) -> u32 {
+        self.x.saturating_add(self.width)
+    }
+
+    pub fn bottom(&self) -> u32 {
+        self.y.saturating_add(self.height)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+
+    // The part of both rectangles, which is empty if they don't overlap.
+    pub fn intersect(&self, other: Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right()).max(x);
+        let bottom = self.bottom().min(other.bottom()).max(y);
+        Rect { x, y, width: right - x, height: bottom - y }
+    }
+
+    // The rectangle `by` cells in from every side.
+    pub fn inset(&self, by: u32) -> Rect {
+        let width = self.width.saturating_sub(2 * by);
+        let height = self.height.saturating_sub(2 * by);
+        Rect { x: self.x + by.min(self.width / 2), y: self.y + by.min(self.height / 2), width, height }
+    }
+}
+
+// The eight standard terminal colors, and whatever the terminal uses by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Default,
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl Color {
+    // The ANSI code for the color as a foreground; add 10 for a background.
+    fn code(self) -> u8 {
+        match self {
+            Color::Default => 39,
+            Color::Black => 30,
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+            Color::Blue => 34,
+            Color::Magenta => 35,
+            Color::Cyan => 36,
+            Color::White => 37,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+    pub bold: bool,
+}
+
+impl Style {
+    pub const PLAIN: Style = Style { fg: Color::Default, bg: Color::Default, bold: false };
+
+    pub fn fg(fg: Color) -> Style {
+        Style { fg, ..Style::PLAIN }
+    }
+
+    pub fn bold(self) -> Style {
+        Style { bold: true, ..self }
+    }
+
+    pub fn on(self, bg: Color) -> Style {
+        Style { bg, ..self }
+    }
+}
+
+impl Default for Style {
+    fn default() -> Style {
+        Style::PLAIN
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub ch: char,
+    pub style: Style,
+}
+
+const BLANK: Cell = Cell { ch: ' ', style: Style::PLAIN };
+
+// The most cells a canvas will hold: 4096 by 4096, far beyond any real terminal, at around a
+// hundred megabytes. Asking for more (from a bogus COLUMNS, say) would otherwise overflow or
+// exhaust memory.
+pub const MAX_CELLS: usize = 1 << 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Border {
+    Single,
+    Double,
+    Rounded,
+    // For terminals that can't show box drawing characters.
+    Ascii,
+}
+
+impl Border {
+    // Top left, top right, bottom left, bottom right, horizontal, vertical.
+    fn chars(self) -> [char; 6] {
+        match self {
+            Border::Single => ['┌', '┐', '└', '┘', '─', '│'],
+            Border::Double => ['╔', '╗', '╚', '╝', '═', '║'],
+            Border::Rounded => ['╭', '╮', '╰', '╯', '─', '│'],
+            Border::Ascii => ['+', '+', '+', '+', '-', '|'],
+        }
+    }
+}
+
+pub struct Canvas {
+    size: Size,
+    // Row by row.
+    cells: Vec<Cell>,
+    // Nothing outside this is drawn.
+    clip: Rect,
+}
+
+impl Canvas {
+    // A canvas bigger than MAX_CELLS is cut down to fit, losing rows from the bottom (and columns
+    // from the right, if even one row is too wide).
+    pub fn new(size: Size) -> Canvas {
+        let width = size.width.min(MAX_CELLS as u32);
+        let height = match (width as usize).checked_mul(size.height as usize) {
+            Some(cells) if cells <= MAX_CELLS => size.height,
+            _ => (MAX_CELLS / width as usize) as u32,
+        };
+        let size = Size::new(width, height);
+        Canvas {
+            size,
+            cells: vec![BLANK; width as usize * height as usize],
+            clip: Rect::new(0, 0, width, height),
+        }
+    }
+
+    // Where the cell at (x, y) is in `cells`. Only for cells on the canvas, where it can't
+    // overflow.
+    fn index(&self, x: u32, y: u32) -> usize {
+        y as usize * self.size.width as usize + x as usize
+    }
+
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    pub fn area(&self) -> Rect {
+        Rect::new(0, 0, self.size.width, self.size.height)
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Option<Cell> {
+        if x < self.size.width && y < self.size.height {
+            Some(self.cells[self.index(x, y)])
+        } else {
+            None
+        }
+    }
+
+    // Draws with `area` as the clip rectangle, inside whatever clip there already is. The clip
+    // goes back to what it was afterwards.
+    pub fn clip<F: FnOnce(&mut Canvas)>(&mut self, area: Rect, draw: F) {
+        let outer = self.clip;
+        self.clip = outer.intersect(area);
+        draw(self);
+        self.clip = outer;
+    }
+
+    pub fn put(&mut self, x: u32, y: u32, ch: char, style: Style) {
+        if self.clip.contains(x, y) {
+            let index = self.index(x, y);
+            self.cells[index] = Cell { ch, style };
+        }
+    }
+
+    // Writes `text` from (x, y) rightwards, cut off at the clip rectangle's edge.
+    pub fn text(&mut self, x: u32, y: u32, text: &str, style: Style) {
+        for (i, ch) in text.chars().enumerate() {
+            self.put(x.saturating_add(i as u32), y, ch, style);
+        }
+    }
+
+    pub fn fill(&mut self, area: Rect, ch: char, style: Style) {
+        let area = area.intersect(self.clip);
+        for y in area.y..area.bottom() {
+            for x in area.x..area.right() {
+                self.put(x, y, ch, style);
+            }
+        }
+    }
+
+    // Draws a border just inside the edges of `area`.
+    pub fn border(&mut self, area: Rect, border: Border, style: Style) {
+        if area.width < 2 || area.height < 2 {
+            return;
+        }
+        let [top_left, top_right, bottom_left, bottom_right, horizontal, vertical] = border.chars();
+        let (right, bottom) = (area.right() - 1, area.bottom() - 1);
+        for x in area.x + 1..right {
+            self.put(x, area.y, horizontal, style);
+            self.put(x, bottom, horizontal, style);
+        }
+        for y in area.y + 1..bottom {
+            self.put(area.x, y, vertical, style);
+            self.put(right, y, vertical, style);
+        }
+        self.put(area.x, area.y, top_left, style);
+        self.put(right, area.y, top_right, style);
+        self.put(area.x, bottom, bottom_left, style);
+        self.put(right, bottom, bottom_right, style);
+    }
+
+    // Writes the canvas with ANSI escape codes, changing style only where it changes and
+    // resetting at the end of every line.
+    pub fn write_ansi<W: Write>(&self, mut out: W) -> io::Result<()> {
+        for row in self.cells.chunks(self.size.width.max(1) as usize) {
+            let mut current = Style::PLAIN;
+            for cell in row {
+                if cell.style != current {
+                    let bold = if cell.style.bold { 1 } else { 22 };
+                    write!(out, "\x1b[{};{};{}m", bold, cell.style.fg.code(), cell.style.bg.code() + 10)?;
+                    current = cell.style;
+                }
+                write!(out, "{}", cell.ch)?;
+            }
+            if current != Style::PLAIN {
+                write!(out, "\x1b[0m")?;
+            }
+            writeln!(out)?;
+        }
+        out.flush()
+    }
+}
+
+// The canvas as plain text, one line per row with trailing spaces trimmed.
+impl fmt::Display for Canvas {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.cells.chunks(self.size.width.max(1) as usize) {
+            let line: String = row.iter().map(|cell| cell.ch).collect();
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+/*
+ * Input from the terminal, turned into events.
+ *
+ * With line buffering turned off a terminal sends its input as bytes: ordinary keys as the
+ * UTF-8 for the character, Enter as \r, Tab as \t and Ctrl with a letter as the letter's place in
+ * the alphabet (Ctrl-C is 3). Everything else comes as an escape sequence starting with ESC
+ * (27): the arrow keys are ESC [ A to ESC [ D, Shift-Tab is ESC [ Z, and once mouse reporting is
+ * turned on a click arrives as ESC [ < button ; column ; row M (the "SGR" format), or in the
+ * older X10 format as ESC [ M followed by three bytes.
+ *
+ * A sequence can be split across two reads, so the decoder holds on to an unfinished one until
+ * the rest arrives. That makes Escape itself awkward: a lone ESC might be the start of a
+ * sequence. `flush` settles it once a read has ended, since terminals send a whole sequence at
+ * once.
+ */
+
+use std::str;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Tab,
+    // Shift-Tab.
+    BackTab,
+    Backspace,
+    Escape,
+    Up,
+    Down,
+    Left,
+    Right,
+    // Ctrl with a letter, the letter in lowercase.
+    Ctrl(char),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Key(Key),
+    // A press of the left mouse button, at a cell counting from 0.
+    Click { x: u32, y: u32 },
+}
+
+// Turns mouse reporting on (clicks, in the SGR format) and off again.
+pub const MOUSE_ON: &str = "\x1b[?1000h\x1b[?1006h";
+pub const MOUSE_OFF: &str = "\x1b[?1006l\x1b[?1000l";
+
+enum Decoded {
+    Event(Event, usize),
+    // Bytes that mean nothing we handle, like a right click or a function key.
+    Skip(usize),
+    Incomplete,
+}
+
+// Mouse buttons come as a number whose low two bits are the button (0 for the left one); the
+// higher bits flag drags, wheel turns and modifier keys.
+fn click(button: u32, x: u32, y: u32, len: usize) -> Decoded {
+    if button == 0 && x > 0 && y > 0 {
+        Decoded::Event(Event::Click { x: x - 1, y: y - 1 }, len)
+    } else {
+        Decoded::Skip(len)
+    }
+}
+
+// ESC [ followed by parameters and a final byte.
+fn csi(bytes: &[u8]) -> Decoded {
+    // X10 mouse: ESC [ M, then button, column and row, each plus 32.
+    if bytes.get(2) == Some(&b'M') {
+        if bytes.len() < 6 {
+            return Decoded::Incomplete;
+        }
+        let at = |i: usize| u32::from(bytes[i].saturating_sub(32));
+        return click(at(3), at(4), at(5), 6);
+    }
+    let end = match bytes[2..].iter().position(|b| (0x40..=0x7e).contains(b)) {
+        Some(end) => end + 2,
+        // Nothing legitimate runs this long without a final byte.
+        None if bytes.len() > 32 => return Decoded::Skip(bytes.len()),
+        None => return Decoded::Incomplete,
+    };
+    let params = &bytes[2..end];
+    let len = end + 1;
+    let key = match bytes[end] {
+        // SGR mouse: ESC [ < button ; column ; row, then M for a press or m for a release.
+        b'M' | b'm' if params.first() == Some(&b'<') => {
+            let numbers: Vec<u32> = str::from_utf8(&params[1..])
+                .unwrap_or("")
+                .split(';')
+                .filter_map(|n| n.parse().ok())
+                .collect();
+            return match (bytes[end], numbers.as_slice()) {
+                (b'M', &[button, x, y]) => click(button, x, y, len),
+                _ => Decoded::Skip(len),
+            };
+        }
+        b'A' => Key::Up,
+        b'B' => Key::Down,
+        b'C' => Key::Right,
+        b'D' => Key::Left,
+        b'Z' => Key::BackTab,
+        _ => return Decoded::Skip(len),
+    };
+    Decoded::Event(Event::Key(key), len)
+}
+
+fn decode(bytes: &[u8]) -> Decoded {
+    let key = match bytes[0] {
+        0x1b => {
+            return match bytes.get(1) {
+                None => Decoded::Incomplete,
+                Some(b'[') if bytes.len() < 3 => Decoded::Incomplete,
+                Some(b'[') => csi(bytes),
+                // Some terminals send the arrows as ESC O A and so on.
+                Some(b'O') => match bytes.get(2) {
+                    None => Decoded::Incomplete,
+                    Some(b'A') => Decoded::Event(Event::Key(Key::Up), 3),
+                    Some(b'B') => Decoded::Event(Event::Key(Key::Down), 3),
+                    Some(b'C') => Decoded::Event(Event::Key(Key::Right), 3),
+                    Some(b'D') => Decoded::Event(Event::Key(Key::Left), 3),
+                    Some(_) => Decoded::Skip(3),
+                },
+                // Escape, then whatever was typed after it.
+                Some(_) => Decoded::Event(Event::Key(Key::Escape), 1),
+            };
+        }
+        b'\r' | b'\n' => Key::Enter,
+        b'\t' => Key::Tab,
+        0x7f | 0x08 => Key::Backspace,
+        b @ 0x01..=0x1a => Key::Ctrl((b'a' + b - 1) as char),
+        b if b < 0x80 => Key::Char(b as char),
+        b => {
+            // The first byte of a UTF-8 character says how many bytes it has.
+            let len = match b {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => return Decoded::Skip(1),
+            };
+            if bytes.len() < len {
+                return Decoded::Incomplete;
+            }
+            return match str::from_utf8(&bytes[..len]).ok().and_then(|s| s.chars().next()) {
+                Some(c) => Decoded::Event(Event::Key(Key::Char(c)), len),
+                None => Decoded::Skip(1),
+            };
+        }
+    };
+    Decoded::Event(Event::Key(key), 1)
+}
+
+#[derive(Debug, Default)]
+pub struct Decoder {
+    // The start of a sequence still waiting for the rest of it.
+    pending: Vec<u8>,
+}
+
+impl Decoder {
+    pub fn new() -> Decoder {
+        Decoder::default()
+    }
+
+    // The events in `bytes`, along with anything left over from before.
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<Event> {
+        self.pending.extend_from_slice(bytes);
+        let mut events = Vec::new();
+        let mut at = 0;
+        while at < self.pending.len() {
+            match decode(&self.pending[at..]) {
+                Decoded::Event(event, len) => {
+                    events.push(event);
+                    at += len;
+                }
+                Decoded::Skip(len) => at += len,
+                Decoded::Incomplete => break,
+            }
+        }
+        self.pending.drain(..at);
+        events
+    }
+
+    // Call when a read has ended: an ESC with nothing after it was the Escape key. Anything else
+    // unfinished is kept for the next read.
+    pub fn flush(&mut self) -> Vec<Event> {
+        if self.pending == [0x1b] {
+            self.pending.clear();
+            vec![Event::Key(Key::Escape)]
+        } else {
+            Vec::new()
+        }
+    }
+}
@@ -0,0 +1,429 @@
+/*
+ * Containers that place other components, and the two passes that decide where everything goes.
+ *
+ * Measuring goes bottom up: each component says how big it would like to be within the space
+ * available (`Draw::measure`), containers by measuring their children and adding them up.
+ * Measuring with no space at all gives the least a component can make do with.
+ *
+ * Arranging goes top down: a container is handed its rectangle and cuts it up between its
+ * children (`Draw::arrange`). When there isn't room for everyone's wish, children give up space
+ * in proportion to how far above their least size they are. When there isn't even room for the
+ * least sizes, the last children are cut off; `Screen` shows a notice rather than a mangled
+ * screen when it comes to that.
+ *
+ * Containers own their children as trait objects, just like `Screen` does, so containers nest
+ * inside containers.
+ */
+
+use crate::canvas::{Border, Canvas, Rect, Size, Style};
+use crate::Draw;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+impl Axis {
+    // The length along the axis, and across it.
+    fn split(self, size: Size) -> (u32, u32) {
+        match self {
+            Axis::Horizontal => (size.width, size.height),
+            Axis::Vertical => (size.height, size.width),
+        }
+    }
+
+    fn join(self, along: u32, across: u32) -> Size {
+        match self {
+            Axis::Horizontal => Size::new(along, across),
+            Axis::Vertical => Size::new(across, along),
+        }
+    }
+}
+
+// Shares `available` out among children that want `wanted`, none getting less than `least`
+// unless even the least sizes don't fit, in which case everyone gets their least and the total
+// overflows.
+pub fn distribute(wanted: &[u32], least: &[u32], available: u32) -> Vec<u32> {
+    let total: u32 = wanted.iter().sum();
+    if total <= available {
+        return wanted.to_vec();
+    }
+    let give: Vec<u32> = wanted.iter().zip(least).map(|(&w, &l)| w.saturating_sub(l)).collect();
+    let can_give: u32 = give.iter().sum();
+    let deficit = total - available;
+    if deficit >= can_give {
+        return wanted.iter().zip(&give).map(|(&w, &g)| w - g).collect();
+    }
+    // Everyone gives their share rounded down, then whatever rounding left over comes from the
+    // last children that still can give.
+    let mut sizes: Vec<u32> = wanted
+        .iter()
+        .zip(&give)
+        .map(|(&w, &g)| w - (u64::from(g) * u64::from(deficit) / u64::from(can_give)) as u32)
+        .collect();
+    let mut left: u32 = sizes.iter().sum::<u32>() - available;
+    for (size, &l) in sizes.iter_mut().zip(least).rev() {
+        let cut = left.min(*size - l.min(*size));
+        *size -= cut;
+        left -= cut;
+    }
+    sizes
+}
+
+// Measures children laid end to end along `axis` with `spacing` between them.
+pub(crate) fn measure_line(axis: Axis, spacing: u32, children: &[Box<dyn Draw>], available: Size) -> Size {
+    let mut along = spacing * (children.len() as u32).saturating_sub(1);
+    let mut across = 0;
+    for child in children {
+        let (a, c) = axis.split(child.measure(available));
+        along += a;
+        across = across.max(c);
+    }
+    axis.join(along, across)
+}
+
+// Lays children end to end along `axis` within `area`. Each gets as much across as it asks for,
+// up to the width (or height) of the area.
+pub fn arrange_line(axis: Axis, spacing: u32, children: &[Box<dyn Draw>], area: Rect) -> Vec<Rect> {
+    let size = Size::new(area.width, area.height);
+    let (length, breadth) = axis.split(size);
+    let gaps = spacing * (children.len() as u32).saturating_sub(1);
+    let measured: Vec<(u32, u32)> = children.iter().map(|c| axis.split(c.measure(size))).collect();
+    let wanted: Vec<u32> = measured.iter().map(|m| m.0).collect();
+    let least: Vec<u32> = children.iter().map(|c| axis.split(c.measure(Size::default())).0).collect();
+    let lengths = distribute(&wanted, &least, length.saturating_sub(gaps));
+
+    let mut at = 0;
+    let mut rects = Vec::new();
+    for (&along, &(_, across)) in lengths.iter().zip(&measured) {
+        let rect = match axis {
+            Axis::Horizontal => Rect::new(area.x + at, area.y, along, across.min(breadth)),
+            Axis::Vertical => Rect::new(area.x, area.y + at, across.min(breadth), along),
+        };
+        rects.push(rect);
+        at += along + spacing;
+    }
+    rects
+}
+
+// Draws each child in its rectangle, clipped to it.
+pub(crate) fn draw_children(children: &[Box<dyn Draw>], rects: &[Rect], canvas: &mut Canvas) {
+    for (child, &rect) in children.iter().zip(rects) {
+        canvas.clip(rect, |canvas| child.draw(canvas, rect));
+    }
+}
+
+// Children one after another, across or down.
+pub struct Stack {
+    pub axis: Axis,
+    // Empty cells between neighbours.
+    pub spacing: u32,
+    pub children: Vec<Box<dyn Draw>>,
+}
+
+impl Stack {
+    pub fn horizontal(children: Vec<Box<dyn Draw>>) -> Stack {
+        Stack { axis: Axis::Horizontal, spacing: 0, children }
+    }
+
+    pub fn vertical(children: Vec<Box<dyn Draw>>) -> Stack {
+        Stack { axis: Axis::Vertical, spacing: 0, children }
+    }
+
+    pub fn spacing(self, spacing: u32) -> Stack {
+        Stack { spacing, ..self }
+    }
+}
+
+impl Draw for Stack {
+    fn measure(&self, available: Size) -> Size {
+        measure_line(self.axis, self.spacing, &self.children, available)
+    }
+
+    fn arrange(&self, area: Rect) -> Vec<Rect> {
+        arrange_line(self.axis, self.spacing, &self.children, area)
+    }
+
+    fn children(&self) -> Vec<&dyn Draw> {
+        self.children.iter().map(|c| c.as_ref()).collect()
+    }
+
+    fn child_mut(&mut self, index: usize) -> Option<&mut dyn Draw> {
+        self.children.get_mut(index).map(|c| c.as_mut() as &mut dyn Draw)
+    }
+
+    fn draw(&self, canvas: &mut Canvas, area: Rect) {
+        draw_children(&self.children, &self.arrange(area), canvas);
+    }
+}
+
+// Children in rows of `columns`, every column as wide as its widest child and every row as tall
+// as its tallest. Each child fills its cell.
+pub struct Grid {
+    pub columns: usize,
+    pub spacing: u32,
+    pub children: Vec<Box<dyn Draw>>,
+}
+
+impl Grid {
+    pub fn new(columns: usize, children: Vec<Box<dyn Draw>>) -> Grid {
+        Grid { columns: columns.max(1), spacing: 0, children }
+    }
+
+    // The widths of the columns and the heights of the rows, as measured within `available`.
+    fn tracks(&self, available: Size) -> (Vec<u32>, Vec<u32>) {
+        let rows = self.children.len().div_ceil(self.columns);
+        let mut widths = vec![0; self.columns.min(self.children.len())];
+        let mut heights = vec![0; rows];
+        for (i, child) in self.children.iter().enumerate() {
+            let size = child.measure(available);
+            widths[i % self.columns] = widths[i % self.columns].max(size.width);
+            heights[i / self.columns] = heights[i / self.columns].max(size.height);
+        }
+        (widths, heights)
+    }
+
+    fn gaps(&self, tracks: usize) -> u32 {
+        self.spacing * (tracks as u32).saturating_sub(1)
+    }
+}
+
+impl Draw for Grid {
+    fn measure(&self, available: Size) -> Size {
+        let (widths, heights) = self.tracks(available);
+        Size::new(
+            widths.iter().sum::<u32>() + self.gaps(widths.len()),
+            heights.iter().sum::<u32>() + self.gaps(heights.len()),
+        )
+    }
+
+    fn arrange(&self, area: Rect) -> Vec<Rect> {
+        let (widths, heights) = self.tracks(Size::new(area.width, area.height));
+        let (least_widths, least_heights) = self.tracks(Size::default());
+        let widths = distribute(&widths, &least_widths, area.width.saturating_sub(self.gaps(widths.len())));
+        let heights = distribute(&heights, &least_heights, area.height.saturating_sub(self.gaps(heights.len())));
+        // Where each column and row starts.
+        let starts = |lengths: &[u32], from: u32| {
+            lengths
+                .iter()
+                .scan(from, |at, &length| {
+                    let start = *at;
+                    *at += length + self.spacing;
+                    Some(start)
+                })
+                .collect::<Vec<u32>>()
+        };
+        let (xs, ys) = (starts(&widths, area.x), starts(&heights, area.y));
+        (0..self.children.len())
+            .map(|i| {
+                let (column, row) = (i % self.columns, i / self.columns);
+                Rect::new(xs[column], ys[row], widths[column], heights[row])
+            })
+            .collect()
+    }
+
+    fn children(&self) -> Vec<&dyn Draw> {
+        self.children.iter().map(|c| c.as_ref()).collect()
+    }
+
+    fn child_mut(&mut self, index: usize) -> Option<&mut dyn Draw> {
+        self.children.get_mut(index).map(|c| c.as_mut() as &mut dyn Draw)
+    }
+
+    fn draw(&self, canvas: &mut Canvas, area: Rect) {
+        draw_children(&self.children, &self.arrange(area), canvas);
+    }
+}
+
+// Space on each side of something.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Insets {
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+    pub left: u32,
+}
+
+impl Insets {
+    pub fn all(by: u32) -> Insets {
+        Insets { top: by, right: by, bottom: by, left: by }
+    }
+
+    fn grow(&self, size: Size) -> Size {
+        Size::new(size.width + self.left + self.right, size.height + self.top + self.bottom)
+    }
+
+    fn shrink_size(&self, size: Size) -> Size {
+        Size::new(
+            size.width.saturating_sub(self.left + self.right),
+            size.height.saturating_sub(self.top + self.bottom),
+        )
+    }
+
+    fn shrink(&self, area: Rect) -> Rect {
+        let size = self.shrink_size(Size::new(area.width, area.height));
+        Rect::new(
+            area.x + self.left.min(area.width),
+            area.y + self.top.min(area.height),
+            size.width,
+            size.height,
+        )
+    }
+}
+
+// Empty space around a component.
+pub struct Padding {
+    pub insets: Insets,
+    pub child: Box<dyn Draw>,
+}
+
+impl Padding {
+    pub fn new(insets: Insets, child: Box<dyn Draw>) -> Padding {
+        Padding { insets, child }
+    }
+}
+
+impl Draw for Padding {
+    fn measure(&self, available: Size) -> Size {
+        self.insets.grow(self.child.measure(self.insets.shrink_size(available)))
+    }
+
+    fn arrange(&self, area: Rect) -> Vec<Rect> {
+        vec![self.insets.shrink(area)]
+    }
+
+    fn children(&self) -> Vec<&dyn Draw> {
+        vec![self.child.as_ref()]
+    }
+
+    fn child_mut(&mut self, index: usize) -> Option<&mut dyn Draw> {
+        if index == 0 {
+            Some(self.child.as_mut())
+        } else {
+            None
+        }
+    }
+
+    fn draw(&self, canvas: &mut Canvas, area: Rect) {
+        let inside = self.insets.shrink(area);
+        canvas.clip(inside, |canvas| self.child.draw(canvas, inside));
+    }
+}
+
+// A border around a component, with an optional title in the top edge.
+pub struct Bordered {
+    pub border: Border,
+    pub style: Style,
+    pub title: Option<String>,
+    pub child: Box<dyn Draw>,
+}
+
+impl Bordered {
+    pub fn new(child: Box<dyn Draw>) -> Bordered {
+        Bordered { border: Border::Single, style: Style::PLAIN, title: None, child }
+    }
+
+    pub fn title(self, title: &str) -> Bordered {
+        Bordered { title: Some(title.to_string()), ..self }
+    }
+}
+
+impl Draw for Bordered {
+    fn measure(&self, available: Size) -> Size {
+        Insets::all(1).grow(self.child.measure(Insets::all(1).shrink_size(available)))
+    }
+
+    fn arrange(&self, area: Rect) -> Vec<Rect> {
+        vec![Insets::all(1).shrink(area)]
+    }
+
+    fn children(&self) -> Vec<&dyn Draw> {
+        vec![self.child.as_ref()]
+    }
+
+    fn child_mut(&mut self, index: usize) -> Option<&mut dyn Draw> {
+        if index == 0 {
+            Some(self.child.as_mut())
+        } else {
+            None
+        }
+    }
+
+    fn draw(&self, canvas: &mut Canvas, area: Rect) {
+        canvas.border(area, self.border, self.style);
+        if let Some(ref title) = self.title {
+            let edge = Rect::new(area.x + 1, area.y, area.width.saturating_sub(2), 1);
+            canvas.clip(edge, |canvas| canvas.text(area.x + 2, area.y, &format!(" {} ", title), self.style));
+        }
+        let inside = Insets::all(1).shrink(area);
+        canvas.clip(inside, |canvas| self.child.draw(canvas, inside));
+    }
+}
+
+// Keeps a component between a least and a greatest size, whatever it would like itself.
+pub struct Constrained {
+    pub min: Size,
+    pub max: Size,
+    pub child: Box<dyn Draw>,
+}
+
+impl Constrained {
+    pub fn new(min: Size, max: Size, child: Box<dyn Draw>) -> Constrained {
+        Constrained { min, max, child }
+    }
+
+    fn clamp(&self, size: Size) -> Size {
+        Size::new(
+            size.width.min(self.max.width).max(self.min.width),
+            size.height.min(self.max.height).max(self.min.height),
+        )
+    }
+}
+
+impl Draw for Constrained {
+    fn measure(&self, available: Size) -> Size {
+        let within = Size::new(available.width.min(self.max.width), available.height.min(self.max.height));
+        self.clamp(self.child.measure(within))
+    }
+
+    fn arrange(&self, area: Rect) -> Vec<Rect> {
+        vec![Rect::new(area.x, area.y, area.width.min(self.max.width), area.height.min(self.max.height))]
+    }
+
+    fn children(&self) -> Vec<&dyn Draw> {
+        vec![self.child.as_ref()]
+    }
+
+    fn child_mut(&mut self, index: usize) -> Option<&mut dyn Draw> {
+        if index == 0 {
+            Some(self.child.as_mut())
+        } else {
+            None
+        }
+    }
+
+    fn draw(&self, canvas: &mut Canvas, area: Rect) {
+        let area = self.arrange(area)[0];
+        canvas.clip(area, |canvas| self.child.draw(canvas, area));
+    }
+}
+
+// Where a component and everything inside it ended up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub area: Rect,
+    pub children: Vec<Layout>,
+}
+
+// Runs the arrange pass over a whole tree of components, for when the rectangles are wanted
+// without drawing anything.
+pub fn layout(component: &dyn Draw, area: Rect) -> Layout {
+    let children = component
+        .children()
+        .into_iter()
+        .zip(component.arrange(area))
+        .map(|(child, rect)| layout(child, rect))
+        .collect();
+    Layout { area, children }
+}
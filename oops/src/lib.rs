@@ -21,8 +21,52 @@ types to fill in the generic type parameters with.
 Question: Is this similar to type-erasure in Java?
  *
  * */
+pub mod canvas;
+pub mod event;
+pub mod layout;
+
+use std::cell::Cell;
+use std::env;
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+
+pub use crate::canvas::{Border, Canvas, Color, Rect, Size, Style};
+pub use crate::event::{Event, Key};
+use crate::event::Decoder;
+use crate::layout::{Axis, Layout};
+
 pub trait Draw {
-    fn draw(&self);
+    // How much room the component would like within `available`. It may ask for more than that
+    // if it can't make do with less; measuring with no room at all, Size::default(), gives the
+    // least it can make do with.
+    fn measure(&self, available: Size) -> Size;
+    // Draws the component into `area` of the canvas. The canvas is clipped to `area` already, so
+    // drawing past its edges does no harm.
+    fn draw(&self, canvas: &mut Canvas, area: Rect);
+    // Containers say where each of their children goes within `area`, in the order `children`
+    // gives them. Everything else has no children.
+    fn arrange(&self, _area: Rect) -> Vec<Rect> {
+        Vec::new()
+    }
+    fn children(&self) -> Vec<&dyn Draw> {
+        Vec::new()
+    }
+    // The same child as `children()[index]`, but mutable, so that events can reach it.
+    fn child_mut(&mut self, _index: usize) -> Option<&mut dyn Draw> {
+        None
+    }
+    // Whether Tab (or a click) can move the focus here.
+    fn focusable(&self) -> bool {
+        false
+    }
+    // Told when the focus arrives or leaves, so the component can show it.
+    fn set_focus(&mut self, _focused: bool) {}
+    // Reacts to an event: keys while it has the focus, clicks inside `area`, which is where it
+    // was laid out. Says whether anything changed, so the screen knows to draw again.
+    fn handle(&mut self, _event: &Event, _area: Rect) -> bool {
+        false
+    }
 }
 
 pub struct Screen {
@@ -41,17 +85,267 @@ pub struct Screen {
      *
      * So, this is a trade-off between flexibility and runtime performance.
      */
-    pub components: Vec<Box<Draw>>,
+    pub components: Vec<Box<dyn Draw>>,
+    // The way to the focused component: its index among the components, then among its
+    // container's children, and so on down.
+    focus: Option<Vec<usize>>,
+    quit: Quit,
+}
+
+// A flag that ends `Screen::run` once it's set, for callbacks to share: an OK button that closes
+// the screen holds a clone. Every clone sets the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct Quit(Rc<Cell<bool>>);
+
+impl Quit {
+    pub fn new() -> Quit {
+        Quit::default()
+    }
+
+    pub fn quit(&self) {
+        self.0.set(true);
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.0.get()
+    }
+}
+
+// Every focusable component in `component` and below, in Tab order, with where it was laid out.
+fn focusables(component: &dyn Draw, layout: &Layout, path: &mut Vec<usize>, found: &mut Vec<(Vec<usize>, Rect)>) {
+    if component.focusable() {
+        found.push((path.clone(), layout.area));
+    }
+    for (i, (child, child_layout)) in component.children().into_iter().zip(&layout.children).enumerate() {
+        path.push(i);
+        focusables(child, child_layout, path, found);
+        path.pop();
+    }
 }
 
 impl Screen {
-    pub fn run(&self) {
-        for component in self.components.iter() {
-            component.draw();
+    pub fn new(components: Vec<Box<dyn Draw>>) -> Screen {
+        Screen { components, focus: None, quit: Quit::new() }
+    }
+
+    // Runs only until `quit` is set (as well as until Escape or Ctrl-C).
+    pub fn until(self, quit: Quit) -> Screen {
+        Screen { quit, ..self }
+    }
+
+    // The least room the components need, one below the other.
+    pub fn least_size(&self) -> Size {
+        layout::measure_line(Axis::Vertical, 0, &self.components, Size::default())
+    }
+
+    // Lays the components out one below the other on a canvas of the given size and draws them.
+    // If they can't fit even at their smallest, the canvas says so instead.
+    pub fn render(&self, size: Size) -> Canvas {
+        let mut canvas = Canvas::new(size);
+        let least = self.least_size();
+        if !self.fits(size) {
+            let warning = Style::fg(Color::Yellow).bold();
+            canvas.text(0, 0, "Terminal too small", warning);
+            canvas.text(0, 1, &format!("need {}x{}", least.width, least.height), warning);
+            return canvas;
+        }
+        let rects = layout::arrange_line(Axis::Vertical, 0, &self.components, canvas.area());
+        layout::draw_children(&self.components, &rects, &mut canvas);
+        canvas
+    }
+
+    fn fits(&self, size: Size) -> bool {
+        let least = self.least_size();
+        least.width <= size.width && least.height <= size.height
+    }
+
+    // Where every component ends up on a screen of the given size, as `render` lays them out.
+    pub fn layout(&self, size: Size) -> Vec<Layout> {
+        let rects = layout::arrange_line(Axis::Vertical, 0, &self.components, Rect::new(0, 0, size.width, size.height));
+        self.components.iter().zip(rects).map(|(c, rect)| layout::layout(c.as_ref(), rect)).collect()
+    }
+
+    // The way to the focused component, as indices from the top (see `focus`).
+    pub fn focused(&self) -> Option<&[usize]> {
+        self.focus.as_deref()
+    }
+
+    fn component_mut(&mut self, path: &[usize]) -> Option<&mut dyn Draw> {
+        let (first, rest) = path.split_first()?;
+        let mut component: &mut dyn Draw = self.components.get_mut(*first)?.as_mut();
+        for &i in rest {
+            component = component.child_mut(i)?;
+        }
+        Some(component)
+    }
+
+    fn focus_on(&mut self, path: Option<Vec<usize>>) {
+        if path == self.focus {
+            return;
+        }
+        if let Some(old) = self.focus.take() {
+            if let Some(component) = self.component_mut(&old) {
+                component.set_focus(false);
+            }
+        }
+        if let Some(component) = path.as_ref().and_then(|path| self.component_mut(path)) {
+            component.set_focus(true);
+        }
+        self.focus = path;
+    }
+
+    // Passes an event to whichever component it's for, laid out on a screen of the given size.
+    // Tab and Shift-Tab move the focus; a click focuses the innermost focusable component under
+    // it and goes to that; other keys go to the focused component. Says whether anything changed.
+    pub fn handle(&mut self, event: &Event, size: Size) -> bool {
+        if !self.fits(size) {
+            return false;
+        }
+        let mut targets = Vec::new();
+        for (i, (component, layout)) in self.components.iter().zip(self.layout(size)).enumerate() {
+            focusables(component.as_ref(), &layout, &mut vec![i], &mut targets);
+        }
+        let at = self.focus.as_ref().and_then(|focus| targets.iter().position(|(path, _)| path == focus));
+        match *event {
+            Event::Key(key @ Key::Tab) | Event::Key(key @ Key::BackTab) => {
+                let count = targets.len();
+                let next = match (key, at) {
+                    _ if count == 0 => return false,
+                    (Key::Tab, Some(at)) => (at + 1) % count,
+                    (Key::Tab, None) => 0,
+                    (_, Some(at)) => (at + count - 1) % count,
+                    (_, None) => count - 1,
+                };
+                self.focus_on(Some(targets.swap_remove(next).0));
+                true
+            }
+            // Later targets are inside earlier ones, so the last one hit is the innermost.
+            Event::Click { x, y } => match targets.into_iter().rev().find(|(_, area)| area.contains(x, y)) {
+                Some((path, area)) => {
+                    let moved = self.focus.as_ref() != Some(&path);
+                    self.focus_on(Some(path.clone()));
+                    let handled = self.component_mut(&path).is_some_and(|c| c.handle(event, area));
+                    moved || handled
+                }
+                None => false,
+            },
+            Event::Key(_) => match at {
+                Some(at) => {
+                    let (path, area) = targets.swap_remove(at);
+                    self.component_mut(&path).is_some_and(|c| c.handle(event, area))
+                }
+                None => false,
+            },
+        }
+    }
+
+    // Renders the screen to fit the terminal, then handles keys and clicks until Escape, Ctrl-C
+    // or the screen's `Quit` is set. When input isn't coming from a terminal, just renders it once.
+    pub fn run(&mut self) -> io::Result<()> {
+        let size = terminal_size();
+        let stdout = io::stdout();
+        match RawMode::enable() {
+            Some(_raw) => {
+                let stdin = io::stdin();
+                self.run_with(stdin.lock(), stdout.lock(), size)
+            }
+            None => self.render(size).write_ansi(stdout.lock()),
+        }
+    }
+
+    // Draws the screen on `output`, then reads events from `input`, drawing again after each
+    // read that changed anything, until Escape, Ctrl-C, the screen's `Quit` or the end of the
+    // input.
+    pub fn run_with<R: Read, W: Write>(&mut self, mut input: R, mut output: W, size: Size) -> io::Result<()> {
+        // Left set by the last run, if any.
+        self.quit.0.set(false);
+        write!(output, "{}", event::MOUSE_ON)?;
+        let result = self.event_loop(&mut input, &mut output, size);
+        write!(output, "{}", event::MOUSE_OFF)?;
+        output.flush()?;
+        result
+    }
+
+    fn event_loop<R: Read, W: Write>(&mut self, input: &mut R, output: &mut W, size: Size) -> io::Result<()> {
+        let mut decoder = Decoder::new();
+        let mut buffer = [0; 64];
+        loop {
+            // Back to the top left, and clear the screen.
+            write!(output, "\x1b[H\x1b[2J")?;
+            self.render(size).write_ansi(&mut *output)?;
+            let mut changed = false;
+            while !changed {
+                let read = match input.read(&mut buffer) {
+                    Ok(0) => return Ok(()),
+                    Ok(read) => read,
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                };
+                let mut events = decoder.feed(&buffer[..read]);
+                events.extend(decoder.flush());
+                for event in events {
+                    if let Event::Key(Key::Escape) | Event::Key(Key::Ctrl('c')) = event {
+                        return Ok(());
+                    }
+                    changed |= self.handle(&event, size);
+                    if self.quit.is_set() {
+                        return Ok(());
+                    }
+                }
+            }
         }
     }
 }
 
+// The terminal switched to passing on every key as it's pressed, without echoing it or turning
+// Ctrl-C into a signal, until this is dropped. That's a job for the termios calls in libc; the
+// stty command makes them for us.
+struct RawMode {
+    // The settings to go back to, as `stty -g` prints them.
+    saved: String,
+}
+
+fn stty(args: &[&str]) -> Option<String> {
+    let output = Command::new("stty").args(args).stdin(Stdio::inherit()).stderr(Stdio::null()).output().ok()?;
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        None
+    }
+}
+
+impl RawMode {
+    // None if stdin isn't a terminal (stty fails then).
+    fn enable() -> Option<RawMode> {
+        let saved = stty(&["-g"])?;
+        stty(&["-icanon", "-echo", "-isig", "min", "1"])?;
+        Some(RawMode { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        stty(&[&self.saved]);
+    }
+}
+
+// Asks the terminal itself through `stty size`, which prints rows then columns. Shells keep
+// COLUMNS and LINES up to date too, but as shell variables that programs they start don't see
+// unless they've been exported, so those come second; without either, the classic 80 by 24.
+pub fn terminal_size() -> Size {
+    let asked = stty(&["size"]).and_then(|size| {
+        let mut numbers = size.split_whitespace().map(|n| n.parse::<u32>().ok());
+        match (numbers.next()??, numbers.next()??) {
+            (0, _) | (_, 0) => None,
+            (rows, columns) => Some(Size::new(columns, rows)),
+        }
+    });
+    asked.unwrap_or_else(|| {
+        let get = |name: &str, default: u32| env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Size::new(get("COLUMNS", 80), get("LINES", 24))
+    })
+}
+
 /*
 Implementation of Screen using generics with trait bounds would look like this:
 If we are sure are elements will be of the same time, this is more preferable because of compile time monomorphization.
@@ -75,10 +369,146 @@ pub struct Button {
     pub width: u32,
     pub height: u32,
     pub label: String,
+    // Called when the button is clicked, or Enter or space is pressed on it.
+    on_press: Option<Box<dyn FnMut()>>,
+    focused: bool,
+}
+
+impl Button {
+    pub fn new(label: &str, width: u32, height: u32) -> Button {
+        Button { width, height, label: label.to_string(), on_press: None, focused: false }
+    }
+
+    pub fn on_press<F: FnMut() + 'static>(self, on_press: F) -> Button {
+        Button { on_press: Some(Box::new(on_press)), ..self }
+    }
 }
 
 impl Draw for Button {
-    fn draw(&self) {
-        // code to actually draw a button
+    // As big as it was made, or down to just big enough for the label and a border.
+    fn measure(&self, available: Size) -> Size {
+        let least = Size::new(self.label.chars().count() as u32 + 2, 3);
+        Size::new(self.width, self.height).fit(available, least)
+    }
+
+    // A box with the label in the middle, doubled and highlighted when focused.
+    fn draw(&self, canvas: &mut Canvas, area: Rect) {
+        let (border, label) = if self.focused {
+            (Border::Double, Style::PLAIN.bold().on(Color::Blue))
+        } else {
+            (Border::Rounded, Style::PLAIN.bold())
+        };
+        canvas.border(area, border, Style::fg(Color::Cyan));
+        let label_width = self.label.chars().count() as u32;
+        let x = area.x + area.width.saturating_sub(label_width) / 2;
+        let y = area.y + area.height.saturating_sub(1) / 2;
+        canvas.clip(area.inset(1), |canvas| canvas.text(x, y, &self.label, label));
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn set_focus(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    fn handle(&mut self, event: &Event, _area: Rect) -> bool {
+        match *event {
+            Event::Click { .. } | Event::Key(Key::Enter) | Event::Key(Key::Char(' ')) => {
+                if let Some(ref mut on_press) = self.on_press {
+                    on_press();
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+// Told the index of the newly chosen option, and the option.
+pub type OnChange = Box<dyn FnMut(usize, &str)>;
+
+// A list of options with one of them chosen, picked with the arrow keys or a click.
+pub struct SelectBox {
+    pub width: u32,
+    pub height: u32,
+    pub options: Vec<String>,
+    selected: usize,
+    // Called whenever the choice changes.
+    on_change: Option<OnChange>,
+    focused: bool,
+}
+
+impl SelectBox {
+    // The first option starts out chosen.
+    pub fn new(options: Vec<String>, width: u32, height: u32) -> SelectBox {
+        SelectBox { width, height, options, selected: 0, on_change: None, focused: false }
+    }
+
+    pub fn on_change<F: FnMut(usize, &str) + 'static>(self, on_change: F) -> SelectBox {
+        SelectBox { on_change: Some(Box::new(on_change)), ..self }
+    }
+
+    pub fn selected(&self) -> Option<&str> {
+        self.options.get(self.selected).map(|o| o.as_str())
+    }
+
+    // Chooses the option at `index`, telling `on_change` about it. Says whether the choice
+    // changed; an index past the end changes nothing.
+    pub fn select(&mut self, index: usize) -> bool {
+        if index == self.selected || index >= self.options.len() {
+            return false;
+        }
+        self.selected = index;
+        if let Some(ref mut on_change) = self.on_change {
+            on_change(index, &self.options[index]);
+        }
+        true
+    }
+}
+
+impl Draw for SelectBox {
+    // As big as it was made, or down to just big enough for the longest option.
+    fn measure(&self, available: Size) -> Size {
+        let longest = self.options.iter().map(|o| o.chars().count() as u32).max().unwrap_or(0);
+        Size::new(self.width, self.height).fit(available, Size::new(longest + 5, 3))
+    }
+
+    // A box with the options listed inside it, the chosen one marked.
+    fn draw(&self, canvas: &mut Canvas, area: Rect) {
+        canvas.border(area, if self.focused { Border::Double } else { Border::Single }, Style::PLAIN);
+        let inside = area.inset(1);
+        canvas.clip(inside, |canvas| {
+            for (i, option) in self.options.iter().enumerate() {
+                let (mark, style) = if i == self.selected {
+                    ('●', Style::fg(Color::Green).bold())
+                } else {
+                    ('○', Style::fg(Color::Green))
+                };
+                canvas.text(inside.x + 1, inside.y + i as u32, &format!("{} {}", mark, option), style);
+            }
+        });
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn set_focus(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    fn handle(&mut self, event: &Event, area: Rect) -> bool {
+        match *event {
+            Event::Key(Key::Up) => self.select(self.selected.saturating_sub(1)),
+            Event::Key(Key::Down) => self.select(self.selected + 1),
+            // Only clicks on the options shown inside the border pick one.
+            Event::Click { x, y } if area.inset(1).contains(x, y) => self.select((y - area.y - 1) as usize),
+            _ => false,
+        }
     }
 }
+
+#[cfg(test)]
+mod tests;
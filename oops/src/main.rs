@@ -1,39 +1,42 @@
 extern crate gui;
 
-use gui::{Button, Screen};
-use gui::Draw;
+use std::cell::RefCell;
+use std::rc::Rc;
 
-struct SelectBox {
-    width: u32,
-    height: u32,
-    options: Vec<String>,
-}
-
-impl Draw for SelectBox {
-    fn draw(&self) {
-        // code to actually draw a select box
-    }
-}
+use gui::layout::Stack;
+use gui::{Button, Quit, Screen, SelectBox};
 
 fn main() {
-    let screen = Screen {
-        components: vec![
-            Box::new(SelectBox {
-                width: 75,
-                height: 10,
-                options: vec![
-                    String::from("Yes"),
-                    String::from("Maybe"),
-                    String::from("No"),
-                ],
-            }),
-            Box::new(Button {
-                width: 50,
-                height: 10,
-                label: String::from("OK"),
-            }),
-        ],
-    };
+    // What's chosen, shared with the callbacks. OK and Cancel both close the screen; only OK
+    // keeps the choice.
+    let choice = Rc::new(RefCell::new(Some(String::from("Yes"))));
+    let (changed, cancelled) = (Rc::clone(&choice), Rc::clone(&choice));
+    let quit = Quit::new();
+    let (ok, cancel) = (quit.clone(), quit.clone());
 
-    screen.run();
+    let mut screen = Screen::new(vec![
+        Box::new(
+            SelectBox::new(vec![String::from("Yes"), String::from("Maybe"), String::from("No")], 75, 10)
+                .on_change(move |_, option| *changed.borrow_mut() = Some(option.to_string())),
+        ),
+        Box::new(
+            Stack::horizontal(vec![
+                Box::new(Button::new("OK", 50, 10).on_press(move || ok.quit())),
+                Box::new(Button::new("Cancel", 20, 10).on_press(move || {
+                    *cancelled.borrow_mut() = None;
+                    cancel.quit();
+                })),
+            ])
+            .spacing(2),
+        ),
+    ])
+    .until(quit.clone());
+
+    screen.run().expect("Failed to run the screen");
+
+    // Escape closes the screen without an answer too.
+    let chosen = choice.borrow().clone();
+    if let (true, Some(option)) = (quit.is_set(), chosen) {
+        println!("You chose {}", option);
+    }
 }
@@ -0,0 +1,249 @@
+use crate::{Border, Button, Canvas, Color, Rect, Screen, Size, Style};
+
+#[test]
+fn canvas_clips_and_renders() {
+    let mut canvas = Canvas::new(Size::new(8, 4));
+    canvas.border(canvas.area(), Border::Ascii, Style::PLAIN);
+    // The clip keeps the text inside the border.
+    canvas.clip(Rect::new(1, 1, 6, 2), |canvas| {
+        canvas.text(1, 1, "overflowing", Style::fg(Color::Red));
+        canvas.fill(Rect::new(0, 2, 20, 20), '.', Style::PLAIN);
+    });
+    canvas.text(6, 3, "xyz", Style::PLAIN);
+    assert_eq!("+------+\n|overfl|\n|......|\n+-----xy\n", canvas.to_string());
+    assert_eq!(Some(Color::Red), canvas.get(1, 1).map(|cell| cell.style.fg));
+
+    let mut ansi = Vec::new();
+    let mut small = Canvas::new(Size::new(3, 1));
+    small.text(1, 0, "a", Style::fg(Color::Red).bold());
+    small.write_ansi(&mut ansi).unwrap();
+    assert_eq!(" \x1b[1;31;49ma\x1b[22;39;49m \n", String::from_utf8(ansi).unwrap());
+
+    // Sizes nothing could show are cut down rather than overflowing.
+    let mut huge = Canvas::new(Size::new(70_000, 70_000));
+    assert_eq!(Size::new(70_000, 239), huge.size());
+    huge.put(69_999, 238, 'x', Style::PLAIN);
+    assert_eq!(Some('x'), huge.get(69_999, 238).map(|cell| cell.ch));
+    assert_eq!(None, huge.get(0, 239));
+    assert_eq!(u32::MAX, Rect::new(u32::MAX - 1, 0, 10, 1).right());
+}
+
+#[test]
+fn screen_renders_components() {
+    let screen = Screen::new(vec![
+        Box::new(Button::new("OK", 10, 3)),
+        Box::new(Button::new("A longer label", 30, 4)),
+    ]);
+    // The second button gives up width and height down to what its label needs.
+    assert_eq!(
+        "╭────────╮\n\
+         │   OK   │\n\
+         ╰────────╯\n\
+         ╭──────────────╮\n\
+         │A longer label│\n\
+         ╰──────────────╯\n",
+        screen.render(Size::new(16, 6)).to_string()
+    );
+    assert_eq!(Size::new(16, 6), screen.least_size());
+    assert_eq!("Terminal too small\nneed 16x6\n\n\n", screen.render(Size::new(20, 4)).to_string());
+}
+
+mod layout {
+    use crate::layout::{distribute, layout, Bordered, Constrained, Grid, Insets, Layout, Padding, Stack};
+    use crate::{Button, Canvas, Draw, Rect, Size};
+
+    fn button(label: &str, width: u32, height: u32) -> Box<dyn Draw> {
+        Box::new(Button::new(label, width, height))
+    }
+
+    fn leaf(x: u32, y: u32, width: u32, height: u32) -> Layout {
+        Layout { area: Rect::new(x, y, width, height), children: Vec::new() }
+    }
+
+    #[test]
+    fn shares_out_space() {
+        assert_eq!(vec![4, 6], distribute(&[4, 6], &[1, 1], 20));
+        // 4 too many. The first can give 3 and the second 5, so in proportion they give 1 and 2,
+        // and the last takes the one left over from rounding down.
+        assert_eq!(vec![3, 3], distribute(&[4, 6], &[1, 1], 6));
+        assert_eq!(vec![1, 1], distribute(&[4, 6], &[1, 1], 1));
+        assert_eq!(vec![3, 0, 2], distribute(&[3, 4, 5], &[3, 0, 2], 5));
+    }
+
+    #[test]
+    fn arranges_containers() {
+        let form = Padding::new(
+            Insets { top: 1, right: 0, bottom: 0, left: 2 },
+            Box::new(Stack::vertical(vec![
+                Box::new(Grid::new(2, vec![button("A", 5, 3), button("B", 7, 3), button("C", 6, 3)])),
+                Box::new(Stack::horizontal(vec![button("OK", 6, 3), button("Cancel", 10, 3)]).spacing(1)),
+            ])),
+        );
+        assert_eq!(Size::new(19, 10), form.measure(Size::new(80, 24)));
+        assert_eq!(
+            Layout {
+                area: Rect::new(0, 0, 40, 20),
+                children: vec![Layout {
+                    area: Rect::new(2, 1, 38, 19),
+                    children: vec![
+                        Layout {
+                            area: Rect::new(2, 1, 13, 6),
+                            children: vec![leaf(2, 1, 6, 3), leaf(8, 1, 7, 3), leaf(2, 4, 6, 3)],
+                        },
+                        Layout { area: Rect::new(2, 7, 17, 3), children: vec![leaf(2, 7, 6, 3), leaf(9, 7, 10, 3)] },
+                    ],
+                }],
+            },
+            layout(&form, Rect::new(0, 0, 40, 20))
+        );
+    }
+
+    #[test]
+    fn keeps_within_constraints() {
+        let wide = Constrained::new(Size::new(0, 0), Size::new(12, 3), button("Save", 40, 9));
+        assert_eq!(Size::new(12, 3), wide.measure(Size::new(80, 24)));
+        let narrow = Constrained::new(Size::new(10, 5), Size::new(20, 5), button("Go", 4, 3));
+        assert_eq!(Size::new(10, 5), narrow.measure(Size::new(80, 24)));
+
+        let titled = Bordered::new(Box::new(wide)).title("File");
+        assert_eq!(Size::new(14, 5), titled.measure(Size::new(16, 5)));
+        let mut canvas = Canvas::new(Size::new(16, 5));
+        titled.draw(&mut canvas, Rect::new(0, 0, 14, 5));
+        assert_eq!(
+            "┌─ File ─────┐\n\
+             │╭──────────╮│\n\
+             ││   Save   ││\n\
+             │╰──────────╯│\n\
+             └────────────┘\n",
+            canvas.to_string()
+        );
+    }
+}
+
+mod event {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::event::{Decoder, Event, Key};
+    use crate::layout::Stack;
+    use crate::{Button, Draw, Quit, Rect, Screen, SelectBox, Size};
+
+    #[test]
+    fn decodes_keys_and_clicks() {
+        let mut decoder = Decoder::new();
+        assert_eq!(
+            vec![
+                Event::Key(Key::Char('a')),
+                Event::Key(Key::Char('é')),
+                Event::Key(Key::Tab),
+                Event::Key(Key::Enter),
+                Event::Key(Key::Ctrl('c')),
+                Event::Key(Key::Up),
+                Event::Key(Key::BackTab),
+                Event::Click { x: 4, y: 1 },
+                Event::Click { x: 0, y: 2 },
+            ],
+            decoder.feed(b"a\xc3\xa9\t\r\x03\x1b[A\x1b[Z\x1b[<0;5;2M\x1b[<0;5;2m\x1b[M !#")
+        );
+        // Right clicks and unknown sequences are dropped.
+        assert_eq!(vec![Event::Key(Key::Down)], decoder.feed(b"\x1b[<2;1;1M\x1b[15~\x1bOB"));
+
+        // A sequence split across reads waits for the rest; a lone ESC is Escape once the read is over.
+        assert_eq!(Vec::<Event>::new(), decoder.feed(b"\x1b[<0;1"));
+        assert_eq!(Vec::<Event>::new(), decoder.flush());
+        assert_eq!(vec![Event::Click { x: 9, y: 9 }], decoder.feed(b"0;10M"));
+        assert_eq!(Vec::<Event>::new(), decoder.feed(b"\x1b"));
+        assert_eq!(vec![Event::Key(Key::Escape)], decoder.flush());
+    }
+
+    #[test]
+    fn focuses_and_presses() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let (chosen, ok, cancel) = (Rc::clone(&log), Rc::clone(&log), Rc::clone(&log));
+        let mut screen = Screen::new(vec![
+            Box::new(
+                SelectBox::new(vec![String::from("Yes"), String::from("No")], 10, 4)
+                    .on_change(move |i, option| chosen.borrow_mut().push(format!("{} {}", i, option))),
+            ),
+            Box::new(Stack::horizontal(vec![
+                Box::new(Button::new("OK", 6, 3).on_press(move || ok.borrow_mut().push(String::from("ok")))),
+                Box::new(Button::new("Cancel", 8, 3).on_press(move || cancel.borrow_mut().push(String::from("cancel")))),
+            ])),
+        ]);
+        let size = Size::new(20, 8);
+        let key = |key| Event::Key(key);
+
+        // Nothing has the focus, so keys go nowhere.
+        assert!(!screen.handle(&key(Key::Enter), size));
+        assert!(screen.handle(&key(Key::Tab), size));
+        assert_eq!(Some(&[0][..]), screen.focused());
+        assert!(screen.handle(&key(Key::Down), size));
+        assert!(!screen.handle(&key(Key::Down), size));
+        assert!(screen.handle(&key(Key::BackTab), size));
+        assert_eq!(Some(&[1, 1][..]), screen.focused());
+        assert!(screen.handle(&key(Key::Char(' ')), size));
+        // Round from the last to the first.
+        screen.handle(&key(Key::Tab), size);
+        assert_eq!(Some(&[0][..]), screen.focused());
+
+        // OK is at (0, 4), 6 wide; the first option is on the select box's second row.
+        assert!(screen.handle(&Event::Click { x: 3, y: 5 }, size));
+        assert_eq!(Some(&[1, 0][..]), screen.focused());
+        assert!(screen.handle(&Event::Click { x: 2, y: 1 }, size));
+        assert!(!screen.handle(&Event::Click { x: 19, y: 7 }, size));
+        assert_eq!(Some(&[0][..]), screen.focused());
+        assert_eq!(vec!["1 No", "cancel", "ok", "0 Yes"], *log.borrow());
+
+        assert_eq!(
+            "╔════════╗\n\
+             ║ ● Yes  ║\n\
+             ║ ○ No   ║\n\
+             ╚════════╝\n\
+             ╭────╮╭──────╮\n\
+             │ OK ││Cancel│\n\
+             ╰────╯╰──────╯\n\
+             \n",
+            screen.render(size).to_string()
+        );
+    }
+
+    #[test]
+    fn picks_only_options_shown() {
+        // Room for two of the three options.
+        let mut select = SelectBox::new(vec![String::from("a"), String::from("b"), String::from("c")], 6, 4);
+        let area = Rect::new(0, 0, 6, 4);
+        // The bottom border, where "c" would be, and the left border.
+        assert!(!select.handle(&Event::Click { x: 2, y: 3 }, area));
+        assert!(!select.handle(&Event::Click { x: 0, y: 2 }, area));
+        assert_eq!(Some("a"), select.selected());
+        assert!(select.handle(&Event::Click { x: 2, y: 2 }, area));
+        assert_eq!(Some("b"), select.selected());
+    }
+
+    #[test]
+    fn runs_until_escape() {
+        let pressed = Rc::new(RefCell::new(0));
+        let counter = Rc::clone(&pressed);
+        let mut screen = Screen::new(vec![Box::new(Button::new("Go", 4, 3).on_press(move || *counter.borrow_mut() += 1))]);
+        let mut output = Vec::new();
+        // The second Enter never arrives: Escape ends it first.
+        screen.run_with(&b"\t\r\x1b[<0;2;2M\x1b"[..], &mut output, Size::new(4, 3)).unwrap();
+        screen.run_with(&b"\r"[..], &mut output, Size::new(4, 3)).unwrap();
+        assert_eq!(3, *pressed.borrow());
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with(crate::event::MOUSE_ON));
+        assert!(output.ends_with(crate::event::MOUSE_OFF));
+
+        // A callback can end it too, whatever input is still to come.
+        let quit = Quit::new();
+        let closer = quit.clone();
+        let mut screen = Screen::new(vec![Box::new(Button::new("Done", 6, 3).on_press(move || closer.quit()))]).until(quit);
+        let counter = Rc::clone(&pressed);
+        screen.components.push(Box::new(Button::new("Go", 4, 3).on_press(move || *counter.borrow_mut() += 1)));
+        screen.run_with(&b"\t\r\t\r"[..], Vec::new(), Size::new(6, 6)).unwrap();
+        assert_eq!(3, *pressed.borrow());
+        // And the next run starts afresh.
+        screen.run_with(&b"\t\r"[..], Vec::new(), Size::new(6, 6)).unwrap();
+        assert_eq!(4, *pressed.borrow());
+    }
+}